@@ -0,0 +1,851 @@
+use crate::games::hypothetical_tree::*;
+use crate::games::transposition_tree::*;
+use crate::prelude::*;
+use crate::search::*;
+use crate::tt::*;
+
+use colored::Colorize;
+use seq_macro::seq;
+
+use std::time::{Duration, Instant};
+
+// Averaged statistics of a single algorithm over a number of test cases,
+// together with the sample standard deviation of each, for drawing error bars.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AlgorithmStats {
+    pub avg_leaves: f64,
+    pub avg_ms: f64,
+    pub avg_us: f64,
+    pub avg_ns: f64,
+    pub sd_leaves: f64,
+    pub sd_ms: f64,
+    pub sd_us: f64,
+    pub sd_ns: f64,
+}
+
+impl AlgorithmStats {
+    pub fn new() -> Self {
+        Self {
+            avg_leaves: 0.0,
+            avg_ms: 0.0,
+            avg_us: 0.0,
+            avg_ns: 0.0,
+            sd_leaves: 0.0,
+            sd_ms: 0.0,
+            sd_us: 0.0,
+            sd_ns: 0.0,
+        }
+    }
+
+    // The statistics of the leaf count and elapsed time of one run per test case.
+    // The samples are only reduced here, rather than accumulated as they are collected,
+    // so that the variance is computed from the mean of all samples without losing precision.
+    // The standard deviation of a single sample is `0.0`, and no samples give `new()`.
+    pub fn from_samples(samples: &[(u128, Duration)]) -> Self {
+        if samples.is_empty() {
+            return Self::new();
+        }
+        let (avg_leaves, sd_leaves) = mean_and_sd(samples.iter().map(|&(leaves, _)| leaves as f64));
+        let (avg_ms, sd_ms) = mean_and_sd(samples.iter().map(|(_, t)| t.as_millis() as f64));
+        let (avg_us, sd_us) = mean_and_sd(samples.iter().map(|(_, t)| t.as_micros() as f64));
+        let (avg_ns, sd_ns) = mean_and_sd(samples.iter().map(|(_, t)| t.as_nanos() as f64));
+        Self {
+            avg_leaves,
+            avg_ms,
+            avg_us,
+            avg_ns,
+            sd_leaves,
+            sd_ms,
+            sd_us,
+            sd_ns,
+        }
+    }
+}
+
+// The mean and sample standard deviation of a non-empty list of values,
+// using Bessel's correction so that the variance is unbiased.
+fn mean_and_sd(values: impl Iterator<Item = f64> + Clone) -> (f64, f64) {
+    let n = values.clone().count() as f64;
+    let mean = values.clone().sum::<f64>() / n;
+    if n < 2.0 {
+        return (mean, 0.0);
+    }
+    let variance = values.map(|x| (x - mean) * (x - mean)).sum::<f64>() / (n - 1.0);
+    (mean, variance.sqrt())
+}
+
+impl Default for AlgorithmStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The effective branching factor of a search, which is the fanout of the uniform tree
+// that would have the same number of leaves at the same depth, i.e. `leaves^(1/depth)`.
+// A search of `depth == 0` does not branch and a search of no leaves searched nothing,
+// so both are reported as `0.0`.
+pub fn effective_branching_factor(leaves: u128, depth: usize) -> f64 {
+    if depth == 0 || leaves == 0 {
+        return 0.0;
+    }
+    (leaves as f64).powf(1.0 / depth as f64)
+}
+
+// The statistics of one algorithm on one named game tree setting,
+// which is the unit that gets exported for plotting.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StatsRecord {
+    pub position_name: String,
+    pub algorithm: String,
+    // The depth the game trees were searched to.
+    pub depth: usize,
+    pub stats: AlgorithmStats,
+}
+
+impl StatsRecord {
+    pub const CSV_HEADER: &'static str =
+        "position,algorithm,depth,avg_leaves,avg_ms,avg_us,avg_ns,ebf,sd_leaves,sd_ms,sd_us,sd_ns";
+
+    // The effective branching factor of the average number of leaves evaluated.
+    pub fn effective_branching_factor(&self) -> f64 {
+        effective_branching_factor(self.stats.avg_leaves.round() as u128, self.depth)
+    }
+
+    // Position names such as `U(2, 2)` contain commas,
+    // so text fields are always quoted with any inner quotes doubled.
+    pub fn to_csv_row(&self) -> String {
+        let quote = |s: &str| format!("\"{}\"", s.replace('"', "\"\""));
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            quote(&self.position_name),
+            quote(&self.algorithm),
+            self.depth,
+            self.stats.avg_leaves,
+            self.stats.avg_ms,
+            self.stats.avg_us,
+            self.stats.avg_ns,
+            self.effective_branching_factor(),
+            self.stats.sd_leaves,
+            self.stats.sd_ms,
+            self.stats.sd_us,
+            self.stats.sd_ns,
+        )
+    }
+
+    pub fn to_json(&self) -> String {
+        let quote = |s: &str| {
+            let mut escaped = String::from("\"");
+            for c in s.chars() {
+                match c {
+                    '"' => escaped.push_str("\\\""),
+                    '\\' => escaped.push_str("\\\\"),
+                    c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+                    c => escaped.push(c),
+                }
+            }
+            escaped.push('"');
+            escaped
+        };
+        // JSON has no representation of infinities or NaN, so they are written as `null`.
+        let number = |x: f64| {
+            if x.is_finite() {
+                x.to_string()
+            } else {
+                "null".to_string()
+            }
+        };
+        format!(
+            "{{\"position\":{},\"algorithm\":{},\"depth\":{},\"avg_leaves\":{},\"avg_ms\":{},\"avg_us\":{},\"avg_ns\":{},\"ebf\":{},\"sd_leaves\":{},\"sd_ms\":{},\"sd_us\":{},\"sd_ns\":{}}}",
+            quote(&self.position_name),
+            quote(&self.algorithm),
+            self.depth,
+            number(self.stats.avg_leaves),
+            number(self.stats.avg_ms),
+            number(self.stats.avg_us),
+            number(self.stats.avg_ns),
+            number(self.effective_branching_factor()),
+            number(self.stats.sd_leaves),
+            number(self.stats.sd_ms),
+            number(self.stats.sd_us),
+            number(self.stats.sd_ns),
+        )
+    }
+
+    // The coloured human-readable format printed by the driver program.
+    pub fn to_console(&self) -> String {
+        let AlgorithmStats {
+            avg_leaves,
+            avg_ms,
+            avg_us,
+            avg_ns,
+            sd_leaves,
+            sd_ms,
+            sd_us,
+            sd_ns,
+        } = self.stats;
+        [
+            format!("Algorithm Tested: {}", self.algorithm.bright_cyan()),
+            format!(
+                "Average number of leaf nodes evaluated: {} (s.d. {})",
+                format!("{:.2}", avg_leaves).bright_yellow(),
+                format!("{:.2}", sd_leaves).bright_yellow()
+            ),
+            format!(
+                "Effective branching factor (2 d.p.): {}",
+                format!("{:.2}", self.effective_branching_factor()).bright_yellow()
+            ),
+            format!(
+                "Average compute time (milliseconds, 2 d.p.): {} ms (s.d. {} ms)",
+                format!("{:.2}", avg_ms).bright_blue(),
+                format!("{:.2}", sd_ms).bright_blue()
+            ),
+            format!(
+                "Average compute time (microseconds, 2 d.p.): {} us (s.d. {} us)",
+                format!("{:.2}", avg_us).bright_blue(),
+                format!("{:.2}", sd_us).bright_blue()
+            ),
+            format!(
+                "Average compute time (nanoseconds, 2 d.p.): {} ns (s.d. {} ns)",
+                format!("{:.2}", avg_ns).bright_blue(),
+                format!("{:.2}", sd_ns).bright_blue()
+            ),
+        ]
+        .join("\n")
+    }
+}
+
+// A full CSV document, including the header line.
+pub fn records_to_csv(records: &[StatsRecord]) -> String {
+    std::iter::once(StatsRecord::CSV_HEADER.to_string())
+        .chain(records.iter().map(StatsRecord::to_csv_row))
+        .map(|line| line + "\n")
+        .collect()
+}
+
+// A JSON array with one object per record.
+pub fn records_to_json(records: &[StatsRecord]) -> String {
+    format!(
+        "[{}]",
+        records
+            .iter()
+            .map(StatsRecord::to_json)
+            .collect::<Vec<_>>()
+            .join(",")
+    )
+}
+
+fn root_call_bb<THandler, TPosition, const DEPTH: usize>(
+    searcher: &mut Searcher,
+    handler: &THandler,
+    root: TPosition,
+) -> EvalAndPV<THandler, TPosition, DEPTH>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    searcher.branch_and_bound::<THandler, TPosition, DEPTH>(
+        handler,
+        root,
+        DEPTH,
+        <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM,
+    )
+}
+
+fn root_call_ab<THandler, TPosition, const DEPTH: usize>(
+    searcher: &mut Searcher,
+    handler: &THandler,
+    root: TPosition,
+) -> EvalAndPV<THandler, TPosition, DEPTH>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    searcher.alpha_beta::<THandler, TPosition, DEPTH>(
+        handler,
+        root,
+        DEPTH,
+        <THandler as GameHandler<TPosition>>::EVAL_MINIMUM,
+        <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM,
+    )
+}
+
+fn root_call_pab<THandler, TPosition, const DEPTH: usize>(
+    searcher: &mut Searcher,
+    handler: &THandler,
+    root: TPosition,
+) -> EvalAndPV<THandler, TPosition, DEPTH>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    searcher.p_alpha_beta(handler, root, DEPTH)
+}
+
+fn root_call_pvs<THandler, TPosition, const DEPTH: usize>(
+    searcher: &mut Searcher,
+    handler: &THandler,
+    root: TPosition,
+) -> EvalAndPV<THandler, TPosition, DEPTH>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    searcher.pvs(
+        handler,
+        root,
+        DEPTH,
+        <THandler as GameHandler<TPosition>>::EVAL_MINIMUM,
+        <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM,
+    )
+}
+
+fn root_call_scout<THandler, TPosition, const DEPTH: usize>(
+    searcher: &mut Searcher,
+    handler: &THandler,
+    root: TPosition,
+) -> EvalAndPV<THandler, TPosition, DEPTH>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    searcher.scout(handler, root, DEPTH)
+}
+
+fn root_call_sss<THandler, TPosition, const DEPTH: usize>(
+    searcher: &mut Searcher,
+    handler: &THandler,
+    root: TPosition,
+) -> EvalAndPV<THandler, TPosition, DEPTH>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    searcher.sss(handler, root, DEPTH)
+}
+
+// The first guess is zero, the midpoint of the evaluations, since `EVAL_MINIMUM == -EVAL_MAXIMUM`.
+fn root_call_mtdf<THandler, TPosition, const DEPTH: usize>(
+    searcher: &mut Searcher,
+    handler: &THandler,
+    root: TPosition,
+) -> EvalAndPV<THandler, TPosition, DEPTH>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    searcher.mtdf(
+        handler,
+        root,
+        DEPTH,
+        <THandler as GameHandler<TPosition>>::EVAL_MINIMUM
+            + <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM,
+    )
+}
+
+fn root_call_dual_star<THandler, TPosition, const DEPTH: usize>(
+    searcher: &mut Searcher,
+    handler: &THandler,
+    root: TPosition,
+) -> EvalAndPV<THandler, TPosition, DEPTH>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    searcher.dual_star(handler, root, DEPTH)
+}
+
+// Names of the eight algorithms in the order that `run_once` and `run_average` run them.
+pub const ALGORITHM_NAMES: [&str; 8] = [
+    "branch_and_bound",
+    "alpha_beta",
+    "p_alpha_beta",
+    "pvs",
+    "scout",
+    "sss",
+    "mtdf",
+    "dual_star",
+];
+
+// The outcome of a single algorithm searching a single game tree.
+pub struct AlgorithmRun<THandler, TPosition, const DEPTH: usize>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    pub algorithm: &'static str,
+    pub result: EvalAndPV<THandler, TPosition, DEPTH>,
+    pub verification: Result<(), Mismatch<<THandler as GameHandler<TPosition>>::Eval>>,
+    pub leaf_count: u128,
+    pub elapsed: Duration,
+}
+
+impl<THandler, TPosition, const DEPTH: usize> AlgorithmRun<THandler, TPosition, DEPTH>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    // Whether replaying the returned line reproduces the returned evaluation.
+    pub fn line_matches(&self) -> bool {
+        self.verification.is_ok()
+    }
+}
+
+// Whether all algorithms returned the same evaluation and principal variation.
+pub fn runs_agree<THandler, TPosition, const DEPTH: usize>(
+    runs: &[AlgorithmRun<THandler, TPosition, DEPTH>],
+) -> bool
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    runs.windows(2).all(|pair| pair[0].result == pair[1].result)
+}
+
+// Runs each of the eight algorithms once on the given game tree,
+// in the order given by `ALGORITHM_NAMES`.
+pub fn run_once<THandler, TPosition, const DEPTH: usize>(
+    searcher: &mut Searcher,
+    handler: &THandler,
+    startpos: TPosition,
+) -> Vec<AlgorithmRun<THandler, TPosition, DEPTH>>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    let algorithms = (
+        root_call_bb,
+        root_call_ab,
+        root_call_pab,
+        root_call_pvs,
+        root_call_scout,
+        root_call_sss,
+        root_call_mtdf,
+        root_call_dual_star,
+    );
+
+    let mut runs = Vec::with_capacity(8);
+    seq!(N in 0..8 {
+        searcher.reset_leaf_count();
+        let s = Instant::now();
+        let result: EvalAndPV<THandler, TPosition, DEPTH> = algorithms.N(searcher, handler, startpos);
+        let elapsed = s.elapsed();
+        runs.push(AlgorithmRun {
+            algorithm: ALGORITHM_NAMES[N],
+            result,
+            verification: verify_line(handler, startpos, result),
+            leaf_count: searcher.get_leaf_count(),
+            elapsed,
+        });
+    });
+    searcher.reset_leaf_count();
+    runs
+}
+
+// Runs each of the eight algorithms on the game trees given by the first `times` handler parameters,
+// and returns their averaged statistics in the order given by `ALGORITHM_NAMES`.
+// After each iteration, `on_iteration` is called with the 1-based iteration number
+// and that iteration's runs, so that callers can report progress or mismatches.
+pub fn run_average<THandler, TPosition, const DEPTH: usize>(
+    searcher: &mut Searcher,
+    position_name: &str,
+    times: usize,
+    handler_params: Vec<<THandler as GameHandler<TPosition>>::Params>,
+    startpos_params: <TPosition as GamePosition>::Params,
+    mut on_iteration: impl FnMut(usize, &[AlgorithmRun<THandler, TPosition, DEPTH>]),
+) -> Vec<StatsRecord>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    if handler_params.len() < times {
+        panic!("List of GameHandler parameters needs to be equal to or more than the number of iterations");
+    }
+
+    let startpos = <TPosition as GamePosition>::startpos(startpos_params);
+    let mut samples: [Vec<(u128, Duration)>; 8] = Default::default();
+
+    for (i, param) in (1usize..).zip(handler_params.into_iter().take(times)) {
+        let handler = <THandler as GameHandler<TPosition>>::new(param);
+        let runs = run_once::<THandler, TPosition, DEPTH>(searcher, &handler, startpos);
+        for (algorithm_samples, run) in samples.iter_mut().zip(runs.iter()) {
+            algorithm_samples.push((run.leaf_count, run.elapsed));
+        }
+        on_iteration(i, &runs);
+    }
+
+    samples
+        .iter()
+        .zip(ALGORITHM_NAMES)
+        .map(|(algorithm_samples, algorithm)| StatsRecord {
+            position_name: position_name.to_string(),
+            algorithm: algorithm.to_string(),
+            depth: DEPTH,
+            stats: AlgorithmStats::from_samples(algorithm_samples),
+        })
+        .collect()
+}
+
+// The deepest hypothetical game tree that `sweep` can search.
+pub const MAX_SWEEP_DEPTH: usize = 16;
+
+// The averaged statistics of the eight algorithms on hypothetical game trees of one shape,
+// in the order given by `ALGORITHM_NAMES`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SweepResult {
+    pub depth: usize,
+    pub width: usize,
+    pub records: Vec<StatsRecord>,
+    // The number of iterations in which the algorithms disagreed or returned an inconsistent line.
+    pub mismatches: usize,
+}
+
+// `run_average` on hypothetical game trees, with the search depth fixed by the function itself,
+// so that an instance for each depth can be stored in a table and chosen at runtime.
+type HypTreeAverage = fn(&mut Searcher, &str, usize, Vec<HypTreeParams>, usize) -> SweepResult;
+
+fn hyp_tree_average<const DEPTH: usize>(
+    searcher: &mut Searcher,
+    position_name: &str,
+    times: usize,
+    handler_params: Vec<HypTreeParams>,
+    width: usize,
+) -> SweepResult {
+    let mut mismatches = 0;
+    let records = run_average::<UnordIndHypTreeHandler, HypTreePos, DEPTH>(
+        searcher,
+        position_name,
+        times,
+        handler_params,
+        width,
+        |_, runs| {
+            if !runs_agree(runs) || !runs.iter().all(AlgorithmRun::line_matches) {
+                mismatches += 1;
+            }
+        },
+    );
+    SweepResult {
+        depth: DEPTH,
+        width,
+        records,
+        mismatches,
+    }
+}
+
+// Entry `d - 1` searches to depth `d`.
+const HYP_TREE_AVERAGES: [HypTreeAverage; MAX_SWEEP_DEPTH] = seq!(D in 1..=16 {
+    [#(hyp_tree_average::<D>,)*]
+});
+
+// The first `(depth, width)` pair given to `sweep` whose depth is not between 1 and
+// `MAX_SWEEP_DEPTH`, in which case no searches are run at all.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnsupportedSweepDepth {
+    pub depth: usize,
+    pub width: usize,
+}
+
+// Runs the eight algorithms on unordered-independent hypothetical game trees
+// of every `(depth, width)` pair, averaging each pair over the first `iterations` seeds.
+// Since the algorithms need the depth at compile time, each depth up to `MAX_SWEEP_DEPTH`
+// has its own instance of `run_average`, which is looked up from the depth at runtime.
+// Every pair is checked before searching, so an unsupported depth is reported immediately.
+pub fn sweep(
+    searcher: &mut Searcher,
+    pairs: &[(usize, usize)],
+    seeds: &[u64],
+    iterations: usize,
+) -> Result<Vec<SweepResult>, UnsupportedSweepDepth> {
+    if let Some(&(depth, width)) = pairs
+        .iter()
+        .find(|&&(depth, _)| depth == 0 || depth > MAX_SWEEP_DEPTH)
+    {
+        return Err(UnsupportedSweepDepth { depth, width });
+    }
+    let results = pairs
+        .iter()
+        .map(|&(depth, width)| {
+            let handler_params = seeds
+                .iter()
+                .map(|&seed| HypTreeParams {
+                    depth,
+                    width,
+                    seed,
+                    early_terminal: 0,
+                })
+                .collect();
+            HYP_TREE_AVERAGES[depth - 1](
+                searcher,
+                &format!("U({}, {})", width, depth),
+                iterations,
+                handler_params,
+                width,
+            )
+        })
+        .collect();
+    Ok(results)
+}
+
+// The average leaf counts of `alpha_beta` and `alpha_beta_tt` on transposition trees
+// of a single transposition density.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TTSpeedup {
+    pub density: usize,
+    pub avg_leaves: f64,
+    pub avg_tt_leaves: f64,
+}
+
+impl TTSpeedup {
+    // How many times fewer leaves the transposition table needed, where `1.0` means no gain.
+    pub fn ratio(&self) -> f64 {
+        self.avg_leaves / self.avg_tt_leaves
+    }
+}
+
+// Searches transposition trees of the given width to `DEPTH` plies with `alpha_beta`
+// and with `alpha_beta_tt` (without internal iterative deepening),
+// for each transposition density and averaged over the seeds,
+// to measure how much a transposition table of `tt_capacity` entries saves.
+// Each search starts with an empty table.
+pub fn tt_speedup<const DEPTH: usize>(
+    searcher: &mut Searcher,
+    width: usize,
+    densities: &[usize],
+    seeds: &[u64],
+    tt_capacity: usize,
+) -> Vec<TTSpeedup> {
+    densities
+        .iter()
+        .map(|&density| {
+            let startpos = TranspTreePos::startpos((width, density));
+            let (mut leaves, mut tt_leaves) = (0, 0);
+            for &seed in seeds {
+                let handler = TranspTreeHandler::new(TranspTreeParams {
+                    depth: DEPTH,
+                    width,
+                    density,
+                    seed,
+                });
+                searcher.reset_leaf_count();
+                searcher.alpha_beta::<TranspTreeHandler, TranspTreePos, DEPTH>(
+                    &handler,
+                    startpos,
+                    DEPTH,
+                    TranspTreeHandler::EVAL_MINIMUM,
+                    TranspTreeHandler::EVAL_MAXIMUM,
+                );
+                leaves += searcher.get_leaf_count();
+
+                let mut tt = TranspositionTable::new(tt_capacity);
+                searcher.reset_leaf_count();
+                searcher.alpha_beta_tt::<TranspTreeHandler, TranspTreePos, DEPTH, false>(
+                    &handler,
+                    startpos,
+                    DEPTH,
+                    TranspTreeHandler::EVAL_MINIMUM,
+                    TranspTreeHandler::EVAL_MAXIMUM,
+                    &mut tt,
+                );
+                tt_leaves += searcher.get_leaf_count();
+            }
+            searcher.reset_leaf_count();
+            TTSpeedup {
+                density,
+                avg_leaves: leaves as f64 / seeds.len() as f64,
+                avg_tt_leaves: tt_leaves as f64 / seeds.len() as f64,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(avg_leaves: f64) -> StatsRecord {
+        StatsRecord {
+            position_name: "U(2, \"2\")".to_string(),
+            algorithm: "Alpha-Beta".to_string(),
+            depth: 4,
+            stats: AlgorithmStats {
+                avg_leaves,
+                ..AlgorithmStats::from_samples(&[
+                    (16, Duration::from_millis(2)),
+                    (16, Duration::from_millis(4)),
+                ])
+            },
+        }
+    }
+
+    fn hyp_tree_params(depth: usize, width: usize, seeds: &[u64]) -> Vec<HypTreeParams> {
+        seeds
+            .iter()
+            .map(|&seed| HypTreeParams {
+                depth,
+                width,
+                seed,
+                early_terminal: 0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn run_average_agrees_across_algorithms() {
+        let mut iterations = 0;
+        let records = run_average::<UnordIndHypTreeHandler, HypTreePos, 3>(
+            &mut Searcher::new(),
+            "U(3, 4)",
+            3,
+            hyp_tree_params(3, 4, &[1, 2, 3, 4]),
+            4,
+            |i, runs| {
+                iterations += 1;
+                assert_eq!(i, iterations);
+                assert!(runs_agree(runs));
+                assert!(runs.iter().all(AlgorithmRun::line_matches));
+            },
+        );
+        assert_eq!(iterations, 3);
+        assert_eq!(records.len(), ALGORITHM_NAMES.len());
+        for (record, name) in records.iter().zip(ALGORITHM_NAMES) {
+            assert_eq!(record.algorithm, name);
+            assert_eq!(record.depth, 3);
+            // No algorithm can evaluate fewer leaves than the minimal tree, `4^2 + 4^1 - 1`.
+            assert!(record.stats.avg_leaves >= 19.0);
+        }
+        // Alpha-beta evaluates at most every leaf, and SSS* never evaluates more than alpha-beta.
+        assert!(records[1].stats.avg_leaves <= 64.0);
+        assert!(records[5].stats.avg_leaves <= records[1].stats.avg_leaves);
+    }
+
+    #[test]
+    fn sweep_runs_pairs_chosen_at_runtime() {
+        let mut searcher = Searcher::new();
+        let pairs = [(2, 2), (3, 2), (2, 3)];
+        let results = sweep(&mut searcher, &pairs, &[1, 2], 2).unwrap();
+        assert_eq!(results.len(), pairs.len());
+        for (result, &(depth, width)) in results.iter().zip(&pairs) {
+            assert_eq!((result.depth, result.width), (depth, width));
+            assert_eq!(result.mismatches, 0);
+            assert_eq!(result.records.len(), ALGORITHM_NAMES.len());
+        }
+        for depth in [0, MAX_SWEEP_DEPTH + 1] {
+            assert_eq!(
+                sweep(&mut searcher, &[(2, 2), (depth, 2)], &[1], 1),
+                Err(UnsupportedSweepDepth { depth, width: 2 })
+            );
+        }
+    }
+
+    #[test]
+    fn denser_transpositions_give_larger_tt_speedup() {
+        let mut searcher = Searcher::new();
+        let speedups = tt_speedup::<5>(&mut searcher, 4, &[0, 50, 80], &[0, 1, 2, 3], 1 << 14);
+        // Without transpositions every node is reached once, so no entry is ever found again.
+        assert_eq!(speedups[0].ratio(), 1.0);
+        assert!(speedups[0].ratio() < speedups[1].ratio());
+        assert!(speedups[1].ratio() < speedups[2].ratio());
+    }
+
+    #[test]
+    fn effective_branching_factor_of_known_trees() {
+        assert!((effective_branching_factor(64, 3) - 4.0).abs() < 1e-9);
+        assert_eq!(effective_branching_factor(64, 0), 0.0);
+        assert_eq!(effective_branching_factor(0, 3), 0.0);
+        // A perfectly ordered alpha-beta search of a tree of width 4 and depth 8
+        // evaluates `4^4 + 4^4 - 1` leaves, close to `sqrt(4)` per ply.
+        assert!((effective_branching_factor(511, 8) - 2.0).abs() < 0.2);
+    }
+
+    #[test]
+    fn csv_quotes_text_fields() {
+        let csv = records_to_csv(&[record(16.0)]);
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], StatsRecord::CSV_HEADER);
+        assert_eq!(lines[1], "\"U(2, \"\"2\"\")\",\"Alpha-Beta\",4,16,3,3000,3000000,2,0,1.4142135623730951,1414.213562373095,1414213.562373095");
+    }
+
+    #[test]
+    fn json_writes_non_finite_numbers_as_null() {
+        let json = record(f64::NAN).to_json();
+        assert!(json.starts_with("{\"position\":\"U(2, \\\"2\\\")\",\"algorithm\":\"Alpha-Beta\""));
+        assert!(json.contains("\"avg_leaves\":null"));
+        assert!(!json.contains("NaN") && !json.contains("inf"));
+        let json = records_to_json(&[record(f64::INFINITY), record(16.0)]);
+        assert!(json.contains("\"avg_leaves\":null"));
+        assert!(json.contains("\"avg_leaves\":16,"));
+    }
+
+    #[test]
+    fn from_samples_gives_mean_and_sample_deviation() {
+        let stats = AlgorithmStats::from_samples(&[
+            (10, Duration::from_millis(1)),
+            (20, Duration::from_millis(2)),
+            (30, Duration::from_millis(6)),
+        ]);
+        assert_eq!(stats.avg_leaves, 20.0);
+        assert_eq!(stats.avg_ms, 3.0);
+        assert_eq!(stats.avg_us, 3000.0);
+        // Sample variances of `100` and `7`.
+        assert_eq!(stats.sd_leaves, 10.0);
+        assert!((stats.sd_ms - 7f64.sqrt()).abs() < 1e-12);
+        let single = AlgorithmStats::from_samples(&[(7, Duration::from_nanos(5))]);
+        assert_eq!(
+            (single.avg_leaves, single.sd_leaves, single.sd_ns),
+            (7.0, 0.0, 0.0)
+        );
+        assert_eq!(AlgorithmStats::from_samples(&[]), AlgorithmStats::new());
+    }
+
+    #[test]
+    fn run_average_matches_manual_mean() {
+        let seeds = [3, 5, 8, 13, 21];
+        let mut searcher = Searcher::new();
+        let records = run_average::<UnordIndHypTreeHandler, HypTreePos, 4>(
+            &mut searcher,
+            "U(4, 3)",
+            seeds.len(),
+            hyp_tree_params(4, 3, &seeds),
+            3,
+            |_, _| {},
+        );
+        // Leaf counts are deterministic, so running each tree again gives the same samples.
+        let mut leaves = vec![Vec::new(); ALGORITHM_NAMES.len()];
+        for param in hyp_tree_params(4, 3, &seeds) {
+            let handler = UnordIndHypTreeHandler::new(param);
+            let runs = run_once::<UnordIndHypTreeHandler, HypTreePos, 4>(
+                &mut searcher,
+                &handler,
+                HypTreePos::startpos(3),
+            );
+            for (samples, run) in leaves.iter_mut().zip(&runs) {
+                samples.push(run.leaf_count as f64);
+            }
+        }
+        for (record, samples) in records.iter().zip(&leaves) {
+            let mean = samples.iter().sum::<f64>() / seeds.len() as f64;
+            let variance = samples.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>()
+                / (seeds.len() - 1) as f64;
+            assert!((record.stats.avg_leaves - mean).abs() < 1e-9);
+            assert!((record.stats.sd_leaves - variance.sqrt()).abs() < 1e-9);
+            assert!(record.stats.sd_leaves >= 0.0 && record.stats.sd_ns >= 0.0);
+        }
+    }
+
+    // Ultimate Tic-Tac-Toe evaluates from the perspective of the player to move, so a benchmark
+    // at an odd depth shows whether each algorithm scores its leaves for the right player.
+    #[test]
+    fn run_once_agrees_on_ultimate_tic_tac_toe_at_odd_depth() {
+        use crate::games::ut3::*;
+
+        let handler = Ut3Handler::new(Ut3Params::default());
+        let runs = run_once::<Ut3Handler, Ut3Board, 3>(
+            &mut Searcher::new(),
+            &handler,
+            Ut3Board::startpos(()),
+        );
+        assert!(runs.iter().all(|run| run.result.0 == runs[0].result.0));
+        assert!(runs.iter().all(AlgorithmRun::line_matches));
+    }
+}
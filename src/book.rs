@@ -0,0 +1,163 @@
+use crate::prelude::*;
+use crate::search::*;
+
+use rand::Rng;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaChaRng;
+
+use std::collections::HashMap;
+
+// A table of known positions, each with a list of moves and their relative weights,
+// indexed by `ZobristKey::key`.
+// Moves are picked at random in proportion to their weights, but the random choice
+// is seeded from the book's seed and the position, so the same book always
+// picks the same move in the same position.
+pub struct OpeningBook<TPosition>
+where
+    TPosition: GamePosition + ZobristKey,
+{
+    seed: u64,
+    entries: HashMap<u64, Vec<(<TPosition as GamePosition>::Move, u32)>>,
+}
+
+impl<TPosition> OpeningBook<TPosition>
+where
+    TPosition: GamePosition + ZobristKey,
+{
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            entries: HashMap::new(),
+        }
+    }
+
+    // Adds a move to the entry for `pos`, or increases its weight if it is already present.
+    pub fn add_move(&mut self, pos: TPosition, mv: <TPosition as GamePosition>::Move, weight: u32) {
+        let moves = self.entries.entry(pos.key()).or_default();
+        match moves.iter_mut().find(|(book_mv, _)| *book_mv == mv) {
+            Some((_, book_weight)) => *book_weight += weight,
+            None => moves.push((mv, weight)),
+        }
+    }
+
+    pub fn moves(&self, pos: TPosition) -> Option<&[(<TPosition as GamePosition>::Move, u32)]> {
+        self.entries.get(&pos.key()).map(|moves| moves.as_slice())
+    }
+
+    // Picks a book move for `pos` in proportion to the move weights,
+    // or `None` if the position is not in the book or all its weights are zero.
+    pub fn pick(&self, pos: TPosition) -> Option<<TPosition as GamePosition>::Move> {
+        let moves = self.moves(pos)?;
+        let total: u64 = moves.iter().map(|&(_, weight)| weight as u64).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut rng = ChaChaRng::seed_from_u64(self.seed ^ pos.key());
+        let mut choice = rng.gen_range(0..total);
+        for &(mv, weight) in moves {
+            if choice < weight as u64 {
+                return Some(mv);
+            }
+            choice -= weight as u64;
+        }
+        None
+    }
+}
+
+// The outcome of `Searcher::search_with_book`.
+pub enum BookOrSearch<THandler, TPosition, const SIZE: usize>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    // The root position was in the book, and this move was picked without searching.
+    Book(<TPosition as GamePosition>::Move),
+    // The root position was not in the book, so the fallback algorithm was used.
+    Searched(EvalAndPV<THandler, TPosition, SIZE>),
+}
+
+impl<THandler, TPosition, const SIZE: usize> BookOrSearch<THandler, TPosition, SIZE>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    // The move to play, regardless of where it came from.
+    pub fn best_move(&self) -> Option<<TPosition as GamePosition>::Move> {
+        match self {
+            Self::Book(mv) => Some(*mv),
+            Self::Searched((_, pv)) => pv[0],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::ut3::*;
+
+    fn search(
+        searcher: &mut Searcher,
+        handler: &Ut3Handler,
+        pos: Ut3Board,
+    ) -> EvalAndPV<Ut3Handler, Ut3Board, 2> {
+        searcher.alpha_beta::<Ut3Handler, Ut3Board, 2>(
+            handler,
+            pos,
+            2,
+            Ut3Handler::EVAL_MINIMUM,
+            Ut3Handler::EVAL_MAXIMUM,
+        )
+    }
+
+    #[test]
+    fn book_move_is_used_for_known_positions() {
+        let handler = Ut3Handler::new(Ut3Params::default());
+        let start = Ut3Board::startpos(());
+        let moves = handler.get_legal_moves(start).collect::<Vec<_>>();
+        let reply_pos = start.play_move(moves[40]);
+        let replies = handler.get_legal_moves(reply_pos).collect::<Vec<_>>();
+
+        let mut book = OpeningBook::new(7);
+        book.add_move(start, moves[40], 1);
+        book.add_move(reply_pos, replies[0], 0);
+        book.add_move(reply_pos, replies[1], 3);
+
+        let mut searcher = Searcher::new();
+        for (pos, expected) in [(start, moves[40]), (reply_pos, replies[1])] {
+            let result = searcher.search_with_book(&book, &handler, pos, search);
+            assert!(matches!(result, BookOrSearch::Book(mv) if mv == expected));
+            assert_eq!(searcher.get_leaf_count(), 0);
+        }
+
+        // A position outside the book falls back to the search.
+        let other = start.play_move(moves[0]);
+        let result = searcher.search_with_book(&book, &handler, other, search);
+        let expected = search(&mut Searcher::new(), &handler, other);
+        assert!(matches!(result, BookOrSearch::Searched(searched) if searched == expected));
+        assert!(searcher.get_leaf_count() > 0);
+    }
+
+    #[test]
+    fn transposed_positions_share_book_entries() {
+        use crate::games::chess::*;
+
+        let handler = ChessHandler::new(ChessParams::default());
+        let play = |moves: &str| {
+            moves
+                .split_whitespace()
+                .fold(ChessPos::startpos(()), |pos, uci| {
+                    pos.play_move(handler.move_from_uci(pos, uci).unwrap())
+                })
+        };
+        let knights_first = play("g1f3 g8f6 b1c3");
+        let transposed = play("b1c3 g8f6 g1f3");
+        assert_eq!(knights_first.key(), transposed.key());
+
+        let mv = handler.move_from_uci(knights_first, "e7e5").unwrap();
+        let mut book = OpeningBook::new(3);
+        book.add_move(knights_first, mv, 1);
+        assert_eq!(book.moves(transposed), Some(&[(mv, 1)][..]));
+        assert_eq!(book.pick(transposed), Some(mv));
+        assert_eq!(book.moves(play("g1f3 g8f6")), None);
+    }
+}
@@ -0,0 +1,228 @@
+use crate::prelude::*;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// A `GameHandler` which delegates everything to an inner handler,
+// while counting the calls made to move generation and static evaluation.
+// This allows nodes to be counted for any algorithm, including those that are not part of `Searcher`,
+// without threading a mutable counter through the search.
+// The counters are atomic so that the handler can still be shared between threads by parallel searches.
+pub struct CountingHandler<'a, THandler> {
+    inner: &'a THandler,
+    move_generations: AtomicU64,
+    evaluations: AtomicU64,
+}
+
+impl<'a, THandler> CountingHandler<'a, THandler> {
+    pub fn inner(&self) -> &'a THandler {
+        self.inner
+    }
+
+    // The number of times legal moves were generated, through either `get_legal_moves`
+    // or `legal_moves_into`, which is the number of interior nodes a search visited.
+    pub fn move_generations(&self) -> u128 {
+        self.move_generations.load(Ordering::Relaxed) as u128
+    }
+
+    // The number of calls to `evaluate` and `terminal_eval`, which is the number of leaves
+    // a search evaluated, as counted by `Searcher::get_leaf_count` for the algorithms of `Searcher`.
+    pub fn evaluations(&self) -> u128 {
+        self.evaluations.load(Ordering::Relaxed) as u128
+    }
+
+    pub fn reset_counts(&self) {
+        self.move_generations.store(0, Ordering::Relaxed);
+        self.evaluations.store(0, Ordering::Relaxed);
+    }
+}
+
+// The handler is built from a reference to the handler it wraps,
+// and every method is forwarded so that any overrides in the inner handler are kept.
+impl<'a, THandler, TPosition> GameHandler<TPosition> for CountingHandler<'a, THandler>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    type Eval = <THandler as GameHandler<TPosition>>::Eval;
+    type Params = &'a THandler;
+
+    const EVAL_MINIMUM: Self::Eval = THandler::EVAL_MINIMUM;
+    const EVAL_MAXIMUM: Self::Eval = THandler::EVAL_MAXIMUM;
+    const EVAL_EPSILON: Self::Eval = THandler::EVAL_EPSILON;
+
+    fn new(inner: &'a THandler) -> Self {
+        Self {
+            inner,
+            move_generations: AtomicU64::new(0),
+            evaluations: AtomicU64::new(0),
+        }
+    }
+
+    fn get_legal_moves(
+        &self,
+        pos: TPosition,
+    ) -> impl Iterator<Item = <TPosition as GamePosition>::Move> {
+        self.move_generations.fetch_add(1, Ordering::Relaxed);
+        self.inner.get_legal_moves(pos)
+    }
+
+    fn legal_moves_into(&self, pos: TPosition, buf: &mut Vec<<TPosition as GamePosition>::Move>) {
+        self.move_generations.fetch_add(1, Ordering::Relaxed);
+        self.inner.legal_moves_into(pos, buf);
+    }
+
+    fn get_loud_moves(
+        &self,
+        pos: TPosition,
+    ) -> impl Iterator<Item = <TPosition as GamePosition>::Move> {
+        self.move_generations.fetch_add(1, Ordering::Relaxed);
+        self.inner.get_loud_moves(pos)
+    }
+
+    fn is_legal(&self, pos: TPosition, mv: <TPosition as GamePosition>::Move) -> bool {
+        self.inner.is_legal(pos, mv)
+    }
+
+    fn evaluate(&self, pos: TPosition, depth: usize, max_depth: usize) -> Self::Eval {
+        self.evaluations.fetch_add(1, Ordering::Relaxed);
+        self.inner.evaluate(pos, depth, max_depth)
+    }
+
+    fn terminal_eval(&self, pos: TPosition, depth: usize, max_depth: usize) -> Self::Eval {
+        self.evaluations.fetch_add(1, Ordering::Relaxed);
+        self.inner.terminal_eval(pos, depth, max_depth)
+    }
+
+    fn is_repetition_draw(&self, pos: TPosition, occurrences: usize) -> bool {
+        self.inner.is_repetition_draw(pos, occurrences)
+    }
+
+    fn draw_eval(&self, pos: TPosition, depth: usize, max_depth: usize) -> Self::Eval {
+        self.evaluations.fetch_add(1, Ordering::Relaxed);
+        self.inner.draw_eval(pos, depth, max_depth)
+    }
+
+    fn current_player(&self, pos: TPosition) -> usize {
+        self.inner.current_player(pos)
+    }
+
+    fn should_extend(&self, pos: TPosition) -> bool {
+        self.inner.should_extend(pos)
+    }
+
+    fn is_quiet_move(&self, pos: TPosition, mv: <TPosition as GamePosition>::Move) -> bool {
+        self.inner.is_quiet_move(pos, mv)
+    }
+
+    fn order_moves(&self, pos: TPosition, moves: &mut Vec<<TPosition as GamePosition>::Move>) {
+        self.inner.order_moves(pos, moves)
+    }
+
+    fn evaluate_absolute(&self, pos: TPosition, depth: usize, max_depth: usize) -> Self::Eval {
+        self.inner.evaluate_absolute(pos, depth, max_depth)
+    }
+
+    fn eval_to_win_prob(&self, eval: Self::Eval) -> f64 {
+        self.inner.eval_to_win_prob(eval)
+    }
+
+    fn format_move(&self, pos: TPosition, mv: <TPosition as GamePosition>::Move) -> String {
+        self.inner.format_move(pos, mv)
+    }
+}
+
+impl<'a, THandler, TPosition> BoundedEvaluation<TPosition> for CountingHandler<'a, THandler>
+where
+    THandler: BoundedEvaluation<TPosition>,
+    TPosition: GamePosition,
+{
+    fn evaluate_optimistic(&self, pos: TPosition, depth: usize, max_depth: usize) -> Self::Eval {
+        self.inner.evaluate_optimistic(pos, depth, max_depth)
+    }
+
+    fn evaluate_pessimistic(&self, pos: TPosition, depth: usize, max_depth: usize) -> Self::Eval {
+        self.inner.evaluate_pessimistic(pos, depth, max_depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::hypothetical_tree::*;
+    use crate::games::stockman::*;
+    use crate::search::*;
+
+    // A plain minimax search written outside of `Searcher`, which counts nothing itself.
+    fn negamax<THandler, TPosition>(
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+    ) -> THandler::Eval
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        if depth == 0 {
+            return handler.evaluate(pos, depth, depth);
+        }
+        handler
+            .get_legal_moves(pos)
+            .map(|mv| -negamax(handler, pos.play_move(mv), depth - 1))
+            .max()
+            .unwrap_or_else(|| handler.terminal_eval(pos, depth, depth))
+    }
+
+    #[test]
+    fn counts_match_searcher_leaf_count() {
+        let stockman = StockmanHandler::new(());
+        let handler = CountingHandler::new(&stockman);
+        let root = StockmanPos::startpos(());
+        // In debug builds, each search evaluates the end of its line again to check it.
+        let line_checks = u128::from(cfg!(debug_assertions));
+        let mut searcher = Searcher::new();
+        searcher.alpha_beta::<_, _, 4>(
+            &handler,
+            root,
+            4,
+            StockmanHandler::EVAL_MINIMUM,
+            StockmanHandler::EVAL_MAXIMUM,
+        );
+        assert_eq!(
+            handler.evaluations(),
+            searcher.get_leaf_count() + line_checks
+        );
+        assert!(handler.move_generations() > 0);
+
+        handler.reset_counts();
+        searcher.reset_leaf_count();
+        searcher.sss::<_, _, 4>(&handler, root, 4);
+        assert_eq!(
+            handler.evaluations(),
+            searcher.get_leaf_count() + line_checks
+        );
+
+        // The full tree has 16 leaves below 15 interior nodes.
+        handler.reset_counts();
+        negamax(&handler, root, 4);
+        assert_eq!(
+            (handler.evaluations(), handler.move_generations()),
+            (16, 15)
+        );
+    }
+
+    #[test]
+    fn counts_hypothetical_tree_leaves() {
+        let tree = UnordIndHypTreeHandler::new(HypTreeParams {
+            depth: 3,
+            width: 3,
+            seed: 1,
+            early_terminal: 0,
+        });
+        let handler = CountingHandler::new(&tree);
+        negamax(&handler, HypTreePos::startpos(3), 3);
+        assert_eq!(
+            (handler.evaluations(), handler.move_generations()),
+            (27, 13)
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use crate::games::chess::*;
+use crate::prelude::*;
+use crate::search::*;
+
+// A single Extended Position Description record.
+// The first four fields form a FEN without move counters,
+// and the remaining text is a sequence of `;`-terminated opcodes.
+// Only the `bm` (best move), `am` (avoid move) and `id` opcodes are interpreted,
+// with moves given in Standard Algebraic Notation.
+#[derive(Clone, Debug)]
+pub struct Epd {
+    pub pos: ChessPos,
+    pub best_moves: Vec<u64>,
+    pub avoid_moves: Vec<u64>,
+    pub id: Option<String>,
+}
+
+// The outcome of searching a single EPD record.
+#[derive(Clone, Debug)]
+pub struct EpdResult {
+    pub id: Option<String>,
+    pub chosen_move: Option<u64>,
+    pub chosen_san: Option<String>,
+    pub passed: bool,
+}
+
+impl Epd {
+    // Returns `None` if the position is malformed
+    // or any of the `bm` and `am` moves are not legal in the position.
+    pub fn parse(handler: &ChessHandler, line: &str) -> Option<Self> {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        if fields.len() < 4 {
+            return None;
+        }
+        let pos = handler.position_from_fen(&fields[..4].join(" "))?;
+        let operations = fields[4..].join(" ");
+
+        let mut epd = Self {
+            pos,
+            best_moves: Vec::new(),
+            avoid_moves: Vec::new(),
+            id: None,
+        };
+        for operation in operations.split(';') {
+            let operation = operation.trim();
+            let (opcode, operands) = match operation.split_once(char::is_whitespace) {
+                Some((opcode, operands)) => (opcode, operands.trim()),
+                None => continue,
+            };
+            match opcode {
+                "bm" | "am" => {
+                    let moves = operands
+                        .split_whitespace()
+                        .map(|san| handler.move_from_san(pos, san))
+                        .collect::<Option<Vec<_>>>()?;
+                    if opcode == "bm" {
+                        epd.best_moves = moves;
+                    } else {
+                        epd.avoid_moves = moves;
+                    }
+                }
+                "id" => epd.id = Some(operands.trim_matches('"').to_string()),
+                _ => {}
+            }
+        }
+        Some(epd)
+    }
+
+    // A move passes if it is one of the best moves (when any are given)
+    // and is not one of the moves to avoid.
+    pub fn accepts(&self, mv: u64) -> bool {
+        (self.best_moves.is_empty() || self.best_moves.contains(&mv))
+            && !self.avoid_moves.contains(&mv)
+    }
+}
+
+// Parses every non-empty line of an EPD file, skipping lines which fail to parse.
+pub fn parse_epd_file(handler: &ChessHandler, contents: &str) -> Vec<Epd> {
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| Epd::parse(handler, line))
+        .collect()
+}
+
+// Searches each position of the suite to `MAX_DEPTH` plies with alpha-beta
+// and compares the first move of the principal variation against the record's opcodes.
+pub fn run_epd_suite<const MAX_DEPTH: usize>(
+    searcher: &mut Searcher,
+    handler: &ChessHandler,
+    suite: &[Epd],
+) -> Vec<EpdResult> {
+    suite
+        .iter()
+        .map(|epd| {
+            let (_, pv) = searcher.alpha_beta::<ChessHandler, ChessPos, MAX_DEPTH>(
+                handler,
+                epd.pos,
+                MAX_DEPTH,
+                ChessHandler::EVAL_MINIMUM,
+                ChessHandler::EVAL_MAXIMUM,
+            );
+            let chosen_move = pv[0];
+            EpdResult {
+                id: epd.id.clone(),
+                chosen_move,
+                chosen_san: chosen_move.map(|mv| handler.move_san(epd.pos, mv)),
+                passed: chosen_move.is_some_and(|mv| epd.accepts(mv)),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUITE: &str = "\
+6k1/5ppp/8/8/8/8/5PPP/4R1K1 w - - bm Re8#; id \"back rank\";
+
+not a position bm e4;
+6k1/5ppp/8/8/8/8/5PPP/4R1K1 w - - bm Rxe9; id \"illegal\";
+4k3/8/8/8/8/8/8/R3K3 w Q - am Kd1 Kf1; id \"avoid\";
+";
+
+    #[test]
+    fn parses_suite_and_skips_bad_lines() {
+        let handler = ChessHandler::new(ChessParams::default());
+        let suite = parse_epd_file(&handler, SUITE);
+        assert_eq!(suite.len(), 2);
+        assert_eq!(suite[0].id.as_deref(), Some("back rank"));
+        assert_eq!(suite[0].best_moves.len(), 1);
+        assert_eq!(handler.move_string(suite[0].best_moves[0], 0), "e1e8");
+        assert_eq!(suite[1].avoid_moves.len(), 2);
+        assert!(suite[1].best_moves.is_empty());
+        assert!(!suite[1].accepts(suite[1].avoid_moves[0]));
+    }
+
+    #[test]
+    fn suite_search_finds_mate() {
+        let handler = ChessHandler::new(ChessParams::default());
+        let suite = parse_epd_file(&handler, SUITE);
+        let results = run_epd_suite::<2>(&mut Searcher::new(), &handler, &suite[..1]);
+        assert!(results[0].passed);
+        assert_eq!(results[0].chosen_move, Some(suite[0].best_moves[0]));
+    }
+}
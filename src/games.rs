@@ -1,5 +1,6 @@
 pub mod chess;
 pub mod hypothetical_tree;
 pub mod stockman;
+pub mod transposition_tree;
 pub mod uniform_2b_wide;
 pub mod ut3;
@@ -2,7 +2,10 @@
 
 use crate::prelude::*;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+// With the `serde` feature, positions serialise as their raw bitboards and counters,
+// so the packed fields of `squares` are preserved exactly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ChessPos {
     pub pawn: u64,
     pub ortho: u64,
@@ -25,6 +28,129 @@ struct SMagic {
 pub struct ChessHandler {
     bishop_magics: [SMagic; 64],
     rook_magics: [SMagic; 64],
+    chess960: bool,
+    mobility_weight: i32,
+    king_safety_weight: i32,
+    pawn_structure: bool,
+    piece_square_tables: bool,
+    win_prob_scale: i32,
+    contempt: i32,
+}
+
+#[derive(Default)]
+pub struct ChessParams {
+    // Whether castling should follow Chess960 (Fischer Random) rules,
+    // where the king and castling rooks may start on any file.
+    pub chess960: bool,
+    // Centipawns awarded per square a knight, bishop, rook or queen can move to,
+    // beyond the number the opponent's pieces can move to. Zero disables the term.
+    pub mobility_weight: i32,
+    // Centipawns deducted per attacked square around the king (including its own square),
+    // beyond the number around the opponent's king. Zero disables the term.
+    pub king_safety_weight: i32,
+    // Whether `evaluate` includes `pawn_structure_eval`.
+    pub pawn_structure: bool,
+    // Whether `evaluate` includes `piece_square_eval`.
+    pub piece_square_tables: bool,
+    // The centipawn advantage at which `eval_to_win_prob` considers the player to move
+    // ten times as likely to win as to lose. Zero uses `DEFAULT_WIN_PROB_SCALE`.
+    pub win_prob_scale: i32,
+    // The centipawns the player to move at the root of a search is willing to give up
+    // to avoid a draw, such as against a weaker opponent.
+    // A negative contempt instead makes draws attractive. Zero scores draws as equal.
+    pub contempt: i32,
+}
+
+// The evaluation of a chess position in centipawns, from the perspective of the player to move.
+// Arithmetic saturates at the limits of `i32` instead of overflowing,
+// so that window adjustments around `EVAL_MINIMUM` and `EVAL_MAXIMUM` are always safe.
+// Checkmates are scored as `MATE_VALUE` less the number of plies from the root
+// it takes to deliver them, and are constructed and queried through `mate` and `mate_in`
+// rather than by adjusting the raw value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Centipawn(pub i32);
+
+impl Centipawn {
+    // The magnitude of the evaluation of a checkmate at the root.
+    pub const MATE_VALUE: i32 = 1000000;
+    // Evaluations within this many plies of `MATE_VALUE` are mate scores,
+    // which is far beyond both the deepest search and the largest material advantage.
+    pub const MAX_MATE_PLIES: i32 = 1000;
+
+    // The evaluation of delivering checkmate `plies` plies from the root.
+    pub const fn mate(plies: usize) -> Self {
+        Self(Self::MATE_VALUE - plies as i32)
+    }
+
+    // The evaluation of being checkmated `plies` plies from the root.
+    pub const fn mated(plies: usize) -> Self {
+        Self(-Self::MATE_VALUE + plies as i32)
+    }
+
+    pub fn is_mate(self) -> bool {
+        (Self::MATE_VALUE - Self::MAX_MATE_PLIES..=Self::MATE_VALUE)
+            .contains(&self.0.saturating_abs())
+    }
+
+    // The number of plies from the root to the checkmate of a mate score,
+    // which is positive when the player to move delivers it and negative when they receive it.
+    // A root position that is already checkmated gives `Some(0)`.
+    pub fn mate_in(self) -> Option<i32> {
+        if !self.is_mate() {
+            None
+        } else if self.0 > 0 {
+            Some(Self::MATE_VALUE - self.0)
+        } else {
+            Some(-(Self::MATE_VALUE + self.0))
+        }
+    }
+}
+
+impl std::ops::Add for Centipawn {
+    type Output = Centipawn;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl std::ops::Sub for Centipawn {
+    type Output = Centipawn;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl std::ops::Neg for Centipawn {
+    type Output = Centipawn;
+
+    fn neg(self) -> Self::Output {
+        Self(self.0.saturating_neg())
+    }
+}
+
+impl std::ops::AddAssign for Centipawn {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl std::ops::SubAssign for Centipawn {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+// Mate scores are shown as `#` followed by `mate_in`, and other evaluations as plain centipawns.
+impl std::fmt::Display for Centipawn {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.mate_in() {
+            Some(plies) => write!(f, "#{}", plies),
+            None => write!(f, "{}", self.0),
+        }
+    }
 }
 
 const KINGSIDE_CASTLE_CLEARANCE_MASK: u64 = 0x60;
@@ -43,6 +169,12 @@ const PRMT_BISHOP: u64 = 2;
 
 const NO_EN_PASSANT: u64 = 64;
 
+// The files of the castling rooks are stored in `squares`, shared by both sides
+// (as in every Chess960 starting position). Standard chess uses the h-file and a-file respectively.
+const KINGSIDE_ROOK_FILE_SHIFT: u64 = 24;
+const QUEENSIDE_ROOK_FILE_SHIFT: u64 = 27;
+const ROOK_FILES_MASK: u64 = 0x3f << KINGSIDE_ROOK_FILE_SHIFT;
+
 const FILE_A: u64 = 0x0101010101010101u64;
 const RANK_1: u64 = 0xffu64;
 const MAJOR_DIAG: u64 = 0x8040201008040201u64;
@@ -395,8 +527,10 @@ const fn make_move(origin: u64, dest: u64, flag: u64, promote: u64) -> u64 {
     (promote << 14) | (flag << 12) | (dest << 6) | origin
 }
 
+// Mirrors a bitboard vertically, so that rank 1 becomes rank 8 and vice versa,
+// which converts between the perspectives of the two sides.
 #[inline]
-const fn flip_bb(mut bb: u64) -> u64 {
+pub const fn flip_bb(mut bb: u64) -> u64 {
     bb = (bb & 0x00000000ffffffff) << 32 | (bb >> 32) & 0x00000000ffffffff;
     bb = (bb & 0x0000ffff0000ffff) << 16 | (bb >> 16) & 0x0000ffff0000ffff;
     bb = (bb & 0x00ff00ff00ff00ff) << 8 | (bb >> 8) & 0x00ff00ff00ff00ff;
@@ -408,9 +542,29 @@ pub const fn flip_square(sq: u64) -> u64 {
     (!sq & 0x38) | (sq & 0x07)
 }
 
+// A bitboard drawn as eight lines of `1` for set squares and `.` for clear squares,
+// with rank 8 at the top and the a-file on the left, for inspecting bitboards by eye.
+pub fn format_bitboard(bb: u64) -> String {
+    (0..8)
+        .rev()
+        .map(|rank| {
+            (0..8)
+                .map(|file| {
+                    if ((bb >> (rank * 8 + file)) & 1) == 1 {
+                        '1'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 #[inline]
 const fn log2(x: u64) -> u64 {
-    LOG_2_TABLE[((x * LOG_2_DE_BRUIJN) >> 58) as usize]
+    LOG_2_TABLE[(x.wrapping_mul(LOG_2_DE_BRUIJN) >> 58) as usize]
 }
 
 impl ChessPos {
@@ -422,7 +576,7 @@ impl ChessPos {
             diag: flip_bb(self.diag),
             own: flip_bb(self.other),
             other: flip_bb(self.own),
-            squares: (self.squares & (1 << 19))
+            squares: (self.squares & ((1 << 19) | ROOK_FILES_MASK))
                 | ((self.squares & (0x5 << 21)) >> 1)
                 | ((self.squares & (0x5 << 20)) << 1)
                 | (if ((self.squares >> 12) & 0x7f) == NO_EN_PASSANT {
@@ -518,8 +672,23 @@ impl ChessPos {
         let w_ooo = (flags >> 22) & 1;
         let b_ooo = (flags >> 23) & 1;
 
+        let kingside_file = (flags >> KINGSIDE_ROOK_FILE_SHIFT) & 7;
+        let queenside_file = (flags >> QUEENSIDE_ROOK_FILE_SHIFT) & 7;
+
         let castling_string = if (w_oo | b_oo | w_ooo | b_ooo) == 0 {
             "-".to_string()
+        } else if kingside_file != 7 || queenside_file != 0 {
+            // Shredder-FEN notation, naming the file of each castling rook.
+            [
+                (w_oo, b'A' + kingside_file as u8),
+                (w_ooo, b'A' + queenside_file as u8),
+                (b_oo, b'a' + kingside_file as u8),
+                (b_ooo, b'a' + queenside_file as u8),
+            ]
+            .into_iter()
+            .filter(|&(right, _)| right == 1)
+            .map(|(_, file)| file as char)
+            .collect::<String>()
         } else {
             format!(
                 "{}{}{}{}",
@@ -588,17 +757,6 @@ impl ChessPos {
             return None;
         }
 
-        for c in castle.chars() {
-            match c {
-                'K' => pos.squares |= 1 << 20,
-                'Q' => pos.squares |= 1 << 22,
-                'k' => pos.squares |= 1 << 21,
-                'q' => pos.squares |= 1 << 23,
-                '-' => {}
-                _ => return None,
-            }
-        }
-
         if ep == "-" {
             pos.squares |= NO_EN_PASSANT << 12;
         } else if let Some(s) = Self::string_to_square(ep) {
@@ -685,6 +843,59 @@ impl ChessPos {
             }
         }
 
+        // Castling rights are given either in standard notation (`KQkq`),
+        // where each letter refers to the outermost rook on that side of the king (X-FEN),
+        // or in Shredder-FEN notation (e.g. `HAha`), which names the file of the castling rook.
+        let mut kingside_file = 7;
+        let mut queenside_file = 0;
+        for c in castle.chars() {
+            let (king_sq, rank, side_bb) = if c.is_ascii_uppercase() {
+                (pos.squares & 0x3f, 0, pos.own)
+            } else {
+                ((pos.squares >> 6) & 0x3f, 56, pos.other)
+            };
+            let rooks = side_bb & pos.ortho & !pos.diag;
+            match c {
+                'K' | 'k' => {
+                    pos.squares |= if c == 'K' { 1 << 20 } else { 1 << 21 };
+                    if let Some(f) = ((king_sq & 7) + 1..8)
+                        .rev()
+                        .find(|f| ((rooks >> (rank + f)) & 1) == 1)
+                    {
+                        kingside_file = f;
+                    }
+                }
+                'Q' | 'q' => {
+                    pos.squares |= if c == 'Q' { 1 << 22 } else { 1 << 23 };
+                    if let Some(f) = (0..king_sq & 7).find(|f| ((rooks >> (rank + f)) & 1) == 1) {
+                        queenside_file = f;
+                    }
+                }
+                'A'..='H' | 'a'..='h' => {
+                    let f = (c.to_ascii_lowercase() as u64) - ('a' as u64);
+                    if f > (king_sq & 7) {
+                        pos.squares |= if c.is_ascii_uppercase() {
+                            1 << 20
+                        } else {
+                            1 << 21
+                        };
+                        kingside_file = f;
+                    } else {
+                        pos.squares |= if c.is_ascii_uppercase() {
+                            1 << 22
+                        } else {
+                            1 << 23
+                        };
+                        queenside_file = f;
+                    }
+                }
+                '-' => {}
+                _ => return None,
+            }
+        }
+        pos.squares |= (kingside_file << KINGSIDE_ROOK_FILE_SHIFT)
+            | (queenside_file << QUEENSIDE_ROOK_FILE_SHIFT);
+
         if side == "b" {
             pos.squares |= 1 << 19;
             Some(pos.flip_position())
@@ -692,6 +903,104 @@ impl ChessPos {
             Some(pos)
         }
     }
+
+    // Offsets into `ZOBRIST_KEYS` of the keys of each component of a position.
+    // Pieces have a key for each colour, piece type and square, indexed as in `colour_pieces`.
+    // Castling rights have a key for each of the four rights, and en passant a key for each file.
+    const ZOBRIST_PIECE: usize = 0;
+    const ZOBRIST_SIDE: usize = 768;
+    const ZOBRIST_CASTLING: usize = 769;
+    const ZOBRIST_EN_PASSANT: usize = 773;
+    const ZOBRIST_KEYS: [u64; 781] = {
+        let mut keys = [0; 781];
+        let mut state: u64 = 0x4348_4553_535f_4b45;
+        let mut i = 0;
+        while i < keys.len() {
+            state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            keys[i] = z ^ (z >> 31);
+            i += 1;
+        }
+        keys
+    };
+
+    // This position as seen from White's side of the board,
+    // so that `own` holds the white pieces and squares are not flipped.
+    fn white_view(&self) -> Self {
+        if ((self.squares >> 19) & 1) == 1 {
+            self.flip_position()
+        } else {
+            *self
+        }
+    }
+
+    // The bitboards of the pawns, knights, bishops, rooks, queens and king of White,
+    // followed by those of Black, with squares as seen from White's side of the board.
+    fn colour_pieces(&self) -> [u64; 12] {
+        let pos = self.white_view();
+        let kings = (1 << (pos.squares & 0x3f)) | (1 << ((pos.squares >> 6) & 0x3f));
+        let knights = !(pos.pawn | pos.ortho | pos.diag | kings);
+        let types = [
+            pos.pawn,
+            knights,
+            pos.diag & !pos.ortho,
+            pos.ortho & !pos.diag,
+            pos.ortho & pos.diag,
+            kings,
+        ];
+        let mut pieces = [0; 12];
+        for (i, bb) in types.into_iter().enumerate() {
+            pieces[i] = bb & pos.own;
+            pieces[6 + i] = bb & pos.other;
+        }
+        pieces
+    }
+
+    // The combined keys of the side to move, castling rights and en passant file.
+    fn zobrist_state_key(&self) -> u64 {
+        let pos = self.white_view();
+        let mut key = 0;
+        if ((pos.squares >> 19) & 1) == 1 {
+            key ^= Self::ZOBRIST_KEYS[Self::ZOBRIST_SIDE];
+        }
+        for right in 0..4 {
+            if ((pos.squares >> (20 + right)) & 1) == 1 {
+                key ^= Self::ZOBRIST_KEYS[Self::ZOBRIST_CASTLING + right];
+            }
+        }
+        let ep_sq = (pos.squares >> 12) & 0x7f;
+        if ep_sq != NO_EN_PASSANT {
+            key ^= Self::ZOBRIST_KEYS[Self::ZOBRIST_EN_PASSANT + (ep_sq & 7) as usize];
+        }
+        key
+    }
+
+    // The combined keys of the pieces on the squares of each bitboard of `pieces`,
+    // indexed as in `colour_pieces`.
+    fn zobrist_pieces_key(pieces: [u64; 12]) -> u64 {
+        let mut key = 0;
+        for (i, mut bb) in pieces.into_iter().enumerate() {
+            while bb != 0 {
+                let square = log2(bb & (!bb + 1)) as usize;
+                key ^= Self::ZOBRIST_KEYS[Self::ZOBRIST_PIECE + 64 * i + square];
+                bb &= bb - 1;
+            }
+        }
+        key
+    }
+
+    // The Zobrist key of this position, computed from scratch.
+    // Since positions are stored from the perspective of the side to move,
+    // the keys are assigned to White and Black and to squares as seen from White,
+    // so that the same game state has the same key whichever side is to move.
+    // The move counters are not part of the key, so transpositions reached
+    // after different numbers of moves share a key.
+    // The castling rook files are also left out, since they are fixed for the whole game.
+    pub fn zobrist_key(&self) -> u64 {
+        Self::zobrist_pieces_key(self.colour_pieces()) ^ self.zobrist_state_key()
+    }
 }
 
 impl GamePosition for ChessPos {
@@ -705,7 +1014,11 @@ impl GamePosition for ChessPos {
             diag: 0x2c0000000000002c,
             own: 0x000000000000ffff,
             other: 0xffff000000000000,
-            squares: (0xf << 20) | (NO_EN_PASSANT << 12) | (60 << 6) | 4,
+            squares: (7 << KINGSIDE_ROOK_FILE_SHIFT)
+                | (0xf << 20)
+                | (NO_EN_PASSANT << 12)
+                | (60 << 6)
+                | 4,
             half_move: 0,
             full_move: 1,
         }
@@ -713,6 +1026,50 @@ impl GamePosition for ChessPos {
 
     fn play_move(&self, mv: Self::Move) -> Self {
         let mut pos = *self;
+        pos.apply_move(mv);
+        pos
+    }
+}
+
+impl ZobristKey for ChessPos {
+    fn key(&self) -> u64 {
+        self.zobrist_key()
+    }
+
+    // Plays a move and updates the Zobrist key `key` of this position to that of the new position,
+    // which only requires the keys of the squares whose pieces changed,
+    // and of the side to move, castling rights and en passant file before and after the move.
+    fn play_move_with_key(&self, key: u64, mv: u64) -> (Self, u64) {
+        let next = self.play_move(mv);
+        let (before, after) = (self.colour_pieces(), next.colour_pieces());
+        let mut changed = [0; 12];
+        for i in 0..12 {
+            changed[i] = before[i] ^ after[i];
+        }
+        let key = key
+            ^ Self::zobrist_pieces_key(changed)
+            ^ self.zobrist_state_key()
+            ^ next.zobrist_state_key();
+        (next, key)
+    }
+}
+
+// The state that `ChessPos::make_move` discards, so that `unmake_move` can restore it.
+// Everything else about the previous position can be recovered from the move itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChessUndo {
+    mv: u64,
+    squares: u64,
+    half_move: u64,
+    // The pawn, orthogonal and diagonal bits of a piece captured on the destination square,
+    // where a captured knight has none of them set.
+    captured: Option<(bool, bool, bool)>,
+}
+
+impl ChessPos {
+    // Plays a move in place, which is the implementation of `play_move`.
+    fn apply_move(&mut self, mv: u64) {
+        let pos = self;
 
         let (origin, destination) = (mv & 0x3f, (mv >> 6) & 0x3f);
 
@@ -722,24 +1079,27 @@ impl GamePosition for ChessPos {
 
         let mut ep_sq = NO_EN_PASSANT;
 
-        if (((pos.own | pos.other) >> destination) & 1) == 1 {
+        // Only the opponent's pieces are checked, since a Chess960 castling move
+        // may have the king's destination occupied by its own castling rook.
+        if ((pos.other >> destination) & 1) == 1 {
             pos.half_move = 0;
         }
 
-        match origin {
-            0 => pos.squares &= !(1 << 22),
-            4 => pos.squares &= !((1 << 22) | (1 << 20)),
-            7 => pos.squares &= !(1 << 20),
-            _ => {}
+        // Castling rights are lost when the king or a castling rook moves,
+        // or when the opponent's castling rook is captured on its original square.
+        let kingside_rook = (pos.squares >> KINGSIDE_ROOK_FILE_SHIFT) & 7;
+        let queenside_rook = (pos.squares >> QUEENSIDE_ROOK_FILE_SHIFT) & 7;
+        if origin == (pos.squares & 0x3f) {
+            pos.squares &= !((1 << 22) | (1 << 20));
+        } else if origin == kingside_rook {
+            pos.squares &= !(1 << 20);
+        } else if origin == queenside_rook {
+            pos.squares &= !(1 << 22);
         }
-        match destination {
-            0 => pos.squares &= !(1 << 22),
-            4 => pos.squares &= !((1 << 22) | (1 << 20)),
-            7 => pos.squares &= !(1 << 20),
-            56 => pos.squares &= !(1 << 23),
-            60 => pos.squares &= !((1 << 23) | (1 << 21)),
-            63 => pos.squares &= !(1 << 21),
-            _ => {}
+        if destination == 56 + kingside_rook {
+            pos.squares &= !(1 << 21);
+        } else if destination == 56 + queenside_rook {
+            pos.squares &= !(1 << 23);
         }
 
         let origin_bb = 1 << origin;
@@ -764,6 +1124,10 @@ impl GamePosition for ChessPos {
                     pos.pawn = (pos.pawn & !origin_bb) | destination_bb;
                     pos.ortho &= !destination_bb;
                     pos.diag &= !destination_bb;
+                    // The en passant square is only ever set within the pawn branch,
+                    // so other pieces moving two ranks forward (e.g. a rook from e2 to e4) do not set it.
+                    // Blocked double pushes are never generated, since `get_legal_moves` requires
+                    // both the intermediate and destination squares to be empty.
                     if destination - origin == 16 {
                         ep_sq = origin + 8;
                     }
@@ -818,23 +1182,22 @@ impl GamePosition for ChessPos {
                     _ => {}
                 }
             }
-            FLAG_CASTLE => match destination {
-                2 => {
-                    pos.squares &= !0x3f;
-                    pos.squares |= 2;
-                    pos.squares &= !((1 << 22) | (1 << 20));
-                    pos.ortho ^= 0x09;
-                    pos.own ^= 0x1d;
-                }
-                6 => {
-                    pos.squares &= !0x3f;
-                    pos.squares |= 6;
-                    pos.squares &= !((1 << 22) | (1 << 20));
-                    pos.ortho ^= 0xa0;
-                    pos.own ^= 0xf0;
-                }
-                _ => {}
-            },
+            FLAG_CASTLE => {
+                // The king always lands on the g-file or c-file, and the rook on the f-file or d-file,
+                // regardless of their origins. Squares are cleared before being set,
+                // since in Chess960 the origins and destinations may overlap.
+                let (rook_bb, rook_destination_bb) = if destination == 6 {
+                    (1 << kingside_rook, 1 << 5)
+                } else {
+                    (1 << queenside_rook, 1 << 3)
+                };
+                pos.squares &= !0x3f;
+                pos.squares |= destination;
+                pos.own &= !(origin_bb | rook_bb);
+                pos.own |= destination_bb | rook_destination_bb;
+                pos.ortho &= !rook_bb;
+                pos.ortho |= rook_destination_bb;
+            }
             FLAG_ENPASSANT => {
                 pos.half_move = 0;
                 pos.pawn ^= destination_bb | origin_bb | (destination_bb >> 8);
@@ -847,10 +1210,116 @@ impl GamePosition for ChessPos {
         pos.squares &= !0x7f000;
         pos.squares |= ep_sq << 12;
 
-        pos.flip_position()
+        *pos = pos.flip_position();
+    }
+}
+
+impl MakeUnmake for ChessPos {
+    type Undo = ChessUndo;
+
+    fn make_move(&mut self, mv: u64) -> ChessUndo {
+        let destination = (mv >> 6) & 0x3f;
+        let captured = if ((mv >> 12) & 3) != FLAG_CASTLE && ((self.other >> destination) & 1) == 1
+        {
+            Some((
+                ((self.pawn >> destination) & 1) == 1,
+                ((self.ortho >> destination) & 1) == 1,
+                ((self.diag >> destination) & 1) == 1,
+            ))
+        } else {
+            None
+        };
+        let undo = ChessUndo {
+            mv,
+            squares: self.squares,
+            half_move: self.half_move,
+            captured,
+        };
+        self.apply_move(mv);
+        undo
+    }
+
+    fn unmake_move(&mut self, undo: ChessUndo) {
+        // Return to the perspective of the side that made the move,
+        // whose king, castling rights and en passant square are all restored with `squares`.
+        let mut pos = self.flip_position();
+        pos.full_move -= (undo.squares >> 19) & 1;
+        pos.half_move = undo.half_move;
+        pos.squares = undo.squares;
+
+        let mv = undo.mv;
+        let origin_bb = 1 << (mv & 0x3f);
+        let destination_bb = 1 << ((mv >> 6) & 0x3f);
+
+        match (mv >> 12) & 3 {
+            FLAG_CASTLE => {
+                // The rook origins are restored together with `squares`.
+                let (rook_bb, rook_destination_bb) = if destination_bb == 1 << 6 {
+                    (1 << ((pos.squares >> KINGSIDE_ROOK_FILE_SHIFT) & 7), 1 << 5)
+                } else {
+                    (
+                        1 << ((pos.squares >> QUEENSIDE_ROOK_FILE_SHIFT) & 7),
+                        1 << 3,
+                    )
+                };
+                pos.own &= !(destination_bb | rook_destination_bb);
+                pos.own |= origin_bb | rook_bb;
+                pos.ortho &= !rook_destination_bb;
+                pos.ortho |= rook_bb;
+            }
+            FLAG_ENPASSANT => {
+                pos.pawn ^= destination_bb | origin_bb | (destination_bb >> 8);
+                pos.own = (pos.own & !destination_bb) | origin_bb;
+                pos.other |= destination_bb >> 8;
+            }
+            flag => {
+                // The moving piece keeps its kind, except for a promoted pawn.
+                pos.own = (pos.own & !destination_bb) | origin_bb;
+                if flag == FLAG_PROMOTE {
+                    pos.pawn |= origin_bb;
+                } else {
+                    for bb in [&mut pos.pawn, &mut pos.ortho, &mut pos.diag] {
+                        if (*bb & destination_bb) != 0 {
+                            *bb |= origin_bb;
+                        }
+                    }
+                }
+                pos.pawn &= !destination_bb;
+                pos.ortho &= !destination_bb;
+                pos.diag &= !destination_bb;
+                if let Some((pawn, ortho, diag)) = undo.captured {
+                    pos.other |= destination_bb;
+                    if pawn {
+                        pos.pawn |= destination_bb;
+                    }
+                    if ortho {
+                        pos.ortho |= destination_bb;
+                    }
+                    if diag {
+                        pos.diag |= destination_bb;
+                    }
+                }
+            }
+        }
+        *self = pos;
     }
 }
 
+// How a line of moves ends, as determined by `ChessHandler::classify_line`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineOutcome {
+    // The side to move at the end of the line is checkmated.
+    Checkmate,
+    // The side to move at the end of the line has no legal moves but is not in check.
+    Stalemate,
+    // The same position occurred for the third time, with the same side to move.
+    Repetition,
+    // One hundred plies have passed without a capture or pawn move.
+    FiftyMove,
+    // The line ends without the game being decided.
+    Inconclusive,
+}
+
 impl SMagic {
     fn empty() -> Self {
         Self {
@@ -863,6 +1332,102 @@ impl SMagic {
 }
 
 impl ChessHandler {
+    pub const PAWN_VALUE: i32 = 100;
+    pub const KNIGHT_VALUE: i32 = 320;
+    pub const BISHOP_VALUE: i32 = 330;
+    pub const ROOK_VALUE: i32 = 500;
+    pub const QUEEN_VALUE: i32 = 900;
+    pub const DEFAULT_WIN_PROB_SCALE: i32 = 400;
+
+    const DOUBLED_PAWN_PENALTY: i32 = 15;
+    const ISOLATED_PAWN_PENALTY: i32 = 10;
+    // Indexed by the rank of the passed pawn from the perspective of its owner.
+    const PASSED_PAWN_BONUS: [i32; 8] = [0, 10, 15, 25, 40, 60, 90, 0];
+
+    // Weights of each non-pawn piece towards the game phase,
+    // where the full complement of pieces at the start of the game sums to `PHASE_TOTAL`.
+    const KNIGHT_PHASE: i32 = 1;
+    const BISHOP_PHASE: i32 = 1;
+    const ROOK_PHASE: i32 = 2;
+    const QUEEN_PHASE: i32 = 4;
+    const PHASE_TOTAL: i32 = 24;
+
+    // Bonuses in centipawns for a piece standing on each square, from the simplified evaluation
+    // function of Tomasz Michniewski. Each table lists the ranks from rank 8 down to rank 1,
+    // as the board is drawn from the side of the piece's owner.
+    const PAWN_SQUARES: [[i32; 8]; 8] = [
+        [0, 0, 0, 0, 0, 0, 0, 0],
+        [50, 50, 50, 50, 50, 50, 50, 50],
+        [10, 10, 20, 30, 30, 20, 10, 10],
+        [5, 5, 10, 25, 25, 10, 5, 5],
+        [0, 0, 0, 20, 20, 0, 0, 0],
+        [5, -5, -10, 0, 0, -10, -5, 5],
+        [5, 10, 10, -20, -20, 10, 10, 5],
+        [0, 0, 0, 0, 0, 0, 0, 0],
+    ];
+    const KNIGHT_SQUARES: [[i32; 8]; 8] = [
+        [-50, -40, -30, -30, -30, -30, -40, -50],
+        [-40, -20, 0, 0, 0, 0, -20, -40],
+        [-30, 0, 10, 15, 15, 10, 0, -30],
+        [-30, 5, 15, 20, 20, 15, 5, -30],
+        [-30, 0, 15, 20, 20, 15, 0, -30],
+        [-30, 5, 10, 15, 15, 10, 5, -30],
+        [-40, -20, 0, 5, 5, 0, -20, -40],
+        [-50, -40, -30, -30, -30, -30, -40, -50],
+    ];
+    const BISHOP_SQUARES: [[i32; 8]; 8] = [
+        [-20, -10, -10, -10, -10, -10, -10, -20],
+        [-10, 0, 0, 0, 0, 0, 0, -10],
+        [-10, 0, 5, 10, 10, 5, 0, -10],
+        [-10, 5, 5, 10, 10, 5, 5, -10],
+        [-10, 0, 10, 10, 10, 10, 0, -10],
+        [-10, 10, 10, 10, 10, 10, 10, -10],
+        [-10, 5, 0, 0, 0, 0, 5, -10],
+        [-20, -10, -10, -10, -10, -10, -10, -20],
+    ];
+    const ROOK_SQUARES: [[i32; 8]; 8] = [
+        [0, 0, 0, 0, 0, 0, 0, 0],
+        [5, 10, 10, 10, 10, 10, 10, 5],
+        [-5, 0, 0, 0, 0, 0, 0, -5],
+        [-5, 0, 0, 0, 0, 0, 0, -5],
+        [-5, 0, 0, 0, 0, 0, 0, -5],
+        [-5, 0, 0, 0, 0, 0, 0, -5],
+        [-5, 0, 0, 0, 0, 0, 0, -5],
+        [0, 0, 0, 5, 5, 0, 0, 0],
+    ];
+    const QUEEN_SQUARES: [[i32; 8]; 8] = [
+        [-20, -10, -10, -5, -5, -10, -10, -20],
+        [-10, 0, 0, 0, 0, 0, 0, -10],
+        [-10, 0, 5, 5, 5, 5, 0, -10],
+        [-5, 0, 5, 5, 5, 5, 0, -5],
+        [0, 0, 5, 5, 5, 5, 0, -5],
+        [-10, 5, 5, 5, 5, 5, 0, -10],
+        [-10, 0, 5, 0, 0, 0, 0, -10],
+        [-20, -10, -10, -5, -5, -10, -10, -20],
+    ];
+    // The king shelters behind its pawns while there are pieces to attack it,
+    // and moves to the centre in the endgame, so its bonus is interpolated by `game_phase`.
+    const KING_MIDDLEGAME_SQUARES: [[i32; 8]; 8] = [
+        [-30, -40, -40, -50, -50, -40, -40, -30],
+        [-30, -40, -40, -50, -50, -40, -40, -30],
+        [-30, -40, -40, -50, -50, -40, -40, -30],
+        [-30, -40, -40, -50, -50, -40, -40, -30],
+        [-20, -30, -30, -40, -40, -30, -30, -20],
+        [-10, -20, -20, -20, -20, -20, -20, -10],
+        [20, 20, 0, 0, 0, 0, 20, 20],
+        [20, 30, 10, 0, 0, 10, 30, 20],
+    ];
+    const KING_ENDGAME_SQUARES: [[i32; 8]; 8] = [
+        [-50, -40, -30, -20, -20, -30, -40, -50],
+        [-30, -20, -10, 0, 0, -10, -20, -30],
+        [-30, -10, 20, 30, 30, 20, -10, -30],
+        [-30, -10, 30, 40, 40, 30, -10, -30],
+        [-30, -10, 30, 40, 40, 30, -10, -30],
+        [-30, -10, 20, 30, 30, 20, -10, -30],
+        [-30, -30, 0, 0, 0, 0, -30, -30],
+        [-50, -30, -30, -30, -30, -30, -30, -50],
+    ];
+
     pub fn square_to_string(&self, sq: u64) -> String {
         let f = ["a", "b", "c", "d", "e", "f", "g", "h"];
         let r = ["1", "2", "3", "4", "5", "6", "7", "8"];
@@ -903,13 +1468,235 @@ impl ChessHandler {
                     }
                 )
             }
-            2 => (if d == 2 { "e1c1" } else { "e1g1" }).to_string(),
+            2 => format!("{}{}", self.square_to_string(o), self.square_to_string(d)),
             3 => format!("{}{}ep", self.square_to_string(o), self.square_to_string(d)),
             _ => panic!("move flag invalid: {}", f),
         }
     }
 
-    fn bishop_unblocked_attack_rays(square: u64) -> u64 {
+    // The SAN letter of the piece belonging to the side to move on `square`.
+    // Pawns are given as `P`, though SAN omits this letter.
+    fn piece_letter(pos: ChessPos, square: u64) -> char {
+        if (pos.squares & 0x3f) == square {
+            'K'
+        } else if ((pos.pawn >> square) & 1) == 1 {
+            'P'
+        } else if (((pos.ortho & pos.diag) >> square) & 1) == 1 {
+            'Q'
+        } else if ((pos.ortho >> square) & 1) == 1 {
+            'R'
+        } else if ((pos.diag >> square) & 1) == 1 {
+            'B'
+        } else {
+            'N'
+        }
+    }
+
+    // Produces the Standard Algebraic Notation of a legal move `mv` played from `pos`,
+    // including disambiguation when two like pieces can reach the same square,
+    // and the `+` and `#` suffixes for check and checkmate.
+    pub fn move_san(&self, pos: ChessPos, mv: u64) -> String {
+        let side = (pos.squares >> 19) & 1;
+        let real_square = |sq: u64| {
+            if side == 1 {
+                self.square_to_string(flip_square(sq))
+            } else {
+                self.square_to_string(sq)
+            }
+        };
+        let (origin, destination) = (mv & 0x3f, (mv >> 6) & 0x3f);
+        let flag = (mv >> 12) & 0x3;
+        let capture = flag == FLAG_ENPASSANT || ((pos.other >> destination) & 1) == 1;
+        let piece = Self::piece_letter(pos, origin);
+
+        let mut san = if flag == FLAG_CASTLE {
+            (if destination == 6 { "O-O" } else { "O-O-O" }).to_string()
+        } else if piece == 'P' {
+            let mut san = if capture {
+                format!("{}x{}", &real_square(origin)[..1], real_square(destination))
+            } else {
+                real_square(destination)
+            };
+            if flag == FLAG_PROMOTE {
+                san.push('=');
+                san.push(['Q', 'R', 'B', 'N'][((mv >> 14) & 0x3) as usize]);
+            }
+            san
+        } else {
+            let rivals = self
+                .get_legal_moves(pos)
+                .filter(|&other| {
+                    (other & 0x3f) != origin
+                        && ((other >> 6) & 0x3f) == destination
+                        && ((other >> 12) & 0x3) != FLAG_CASTLE
+                        && Self::piece_letter(pos, other & 0x3f) == piece
+                })
+                .map(|other| other & 0x3f)
+                .collect::<Vec<_>>();
+            let origin_string = real_square(origin);
+            let disambiguation = if rivals.is_empty() {
+                ""
+            } else if rivals.iter().all(|&sq| (sq & 7) != (origin & 7)) {
+                &origin_string[..1]
+            } else if rivals.iter().all(|&sq| (sq >> 3) != (origin >> 3)) {
+                &origin_string[1..]
+            } else {
+                &origin_string[..]
+            };
+            format!(
+                "{}{}{}{}",
+                piece,
+                disambiguation,
+                if capture { "x" } else { "" },
+                real_square(destination)
+            )
+        };
+
+        let next_pos = pos.play_move(mv);
+        if self.in_check(next_pos) {
+            san.push(if self.get_legal_moves(next_pos).next().is_none() {
+                '#'
+            } else {
+                '+'
+            });
+        }
+        san
+    }
+
+    // The SAN of each move of `line` as it is played from `startpos`, up to its first `None`,
+    // such as for printing a principal variation returned by a search.
+    pub fn line_san(&self, startpos: ChessPos, line: &[Option<u64>]) -> Vec<String> {
+        let mut pos = startpos;
+        line.iter()
+            .map_while(|&mv| mv)
+            .map(|mv| {
+                let san = self.move_san(pos, mv);
+                pos = pos.play_move(mv);
+                san
+            })
+            .collect()
+    }
+
+    // Finds the legal move in `pos` described by the Standard Algebraic Notation string `san`.
+    // Check and annotation suffixes (`+`, `#`, `!`, `?`) are ignored, castling may be written
+    // with zeroes, and a move given with more disambiguation than it needs is still accepted.
+    // Returns `None` unless exactly one legal move matches.
+    pub fn move_from_san(&self, pos: ChessPos, san: &str) -> Option<u64> {
+        let san = san
+            .trim()
+            .trim_end_matches(['+', '#', '!', '?'])
+            .replace('0', "O");
+        let side = (pos.squares >> 19) & 1;
+        let real_square = |sq: u64| if side == 1 { flip_square(sq) } else { sq };
+
+        let candidates = if san == "O-O" || san == "O-O-O" {
+            let file = if san == "O-O" { 6 } else { 2 };
+            self.get_legal_moves(pos)
+                .filter(|&mv| ((mv >> 12) & 0x3) == FLAG_CASTLE && ((mv >> 6) & 7) == file)
+                .collect::<Vec<_>>()
+        } else {
+            let (body, promotion) = match san.split_once('=') {
+                Some((body, piece)) if piece.len() == 1 => (body, Some("QRBN".find(piece)? as u64)),
+                Some(_) => return None,
+                None => (&san[..], None),
+            };
+            let mut chars = body.chars().filter(|&c| c != 'x').collect::<Vec<_>>();
+            let piece = match chars.first() {
+                Some(&c) if "KQRBN".contains(c) => chars.remove(0),
+                _ => 'P',
+            };
+            if chars.len() < 2 {
+                return None;
+            }
+            let (hint, target) = chars.split_at(chars.len() - 2);
+            let coordinate = |c: char, first: char| {
+                let index = (c as u64).wrapping_sub(first as u64);
+                (index < 8).then_some(index)
+            };
+            let destination = coordinate(target[0], 'a')? | (coordinate(target[1], '1')? << 3);
+            let mut file = None;
+            let mut rank = None;
+            for &c in hint {
+                if let Some(index) = coordinate(c, 'a') {
+                    file = Some(index);
+                } else {
+                    rank = Some(coordinate(c, '1')?);
+                }
+            }
+            self.get_legal_moves(pos)
+                .filter(|&mv| {
+                    let origin = real_square(mv & 0x3f);
+                    let flag = (mv >> 12) & 0x3;
+                    flag != FLAG_CASTLE
+                        && Self::piece_letter(pos, mv & 0x3f) == piece
+                        && real_square((mv >> 6) & 0x3f) == destination
+                        && file.is_none_or(|file| (origin & 7) == file)
+                        && rank.is_none_or(|rank| (origin >> 3) == rank)
+                        && (flag == FLAG_PROMOTE).then_some((mv >> 14) & 0x3) == promotion
+                })
+                .collect::<Vec<_>>()
+        };
+        match candidates[..] {
+            [mv] => Some(mv),
+            _ => None,
+        }
+    }
+
+    // Finds the legal move in `pos` described by the UCI string `uci`, such as `e2e4` or `e7e8q`,
+    // which is the inverse of `move_string`. Castling is written as the move of the king,
+    // such as `e1g1`, and en passant captures may be written with or without the `ep` suffix
+    // that `move_string` adds. Squares are always named from White's side of the board,
+    // so the move returned can be played from `pos` even when Black is to move.
+    pub fn move_from_uci(&self, pos: ChessPos, uci: &str) -> Option<u64> {
+        let side = (pos.squares >> 19) & 1;
+        let strip = |s: &str| s.trim().trim_end_matches("ep").to_string();
+        let target = strip(uci);
+        self.get_legal_moves(pos)
+            .find(|&mv| strip(&self.move_string(mv, side)) == target)
+    }
+
+    // Replays `line` from `startpos` up to its first `None`, such as a principal variation
+    // returned by a search, and reports the first way in which the game ended along it.
+    // Positions are repeated if their pieces, side to move, castling rights
+    // and en passant square are the same, regardless of the move counters.
+    pub fn classify_line(&self, startpos: ChessPos, line: &[Option<u64>]) -> LineOutcome {
+        let placement = |pos: ChessPos| {
+            (
+                pos.pawn,
+                pos.ortho,
+                pos.diag,
+                pos.own,
+                pos.other,
+                pos.squares,
+            )
+        };
+        let mut history = vec![placement(startpos)];
+        let mut pos = startpos;
+        for mv in line.iter().map_while(|&mv| mv) {
+            pos = pos.play_move(mv);
+            if self.get_legal_moves(pos).next().is_none() {
+                return if self.in_check(pos) {
+                    LineOutcome::Checkmate
+                } else {
+                    LineOutcome::Stalemate
+                };
+            }
+            let current = placement(pos);
+            history.push(current);
+            if history.iter().filter(|&&seen| seen == current).count() >= 3 {
+                return LineOutcome::Repetition;
+            }
+            if pos.half_move >= 100 {
+                return LineOutcome::FiftyMove;
+            }
+        }
+        LineOutcome::Inconclusive
+    }
+
+    // The squares a bishop on `square` could attack on an empty board, excluding the edges
+    // of the board, which are the only squares whose occupancy can block its attacks.
+    // This is the mask of the bishop magic lookup on `square`.
+    pub fn bishop_unblocked_attack_rays(square: u64) -> u64 {
         let rank = square >> 3;
         let file = square & 7;
         let mut major: u64;
@@ -943,7 +1730,10 @@ impl ChessHandler {
         (major | minor) & !(EDGES | (1 << square))
     }
 
-    fn rook_unblocked_attack_rays(square: u64) -> u64 {
+    // The squares whose occupancy can block the attacks of a rook on `square`,
+    // which are the squares of its rank and file other than those at the ends of each ray.
+    // This is the mask of the rook magic lookup on `square`.
+    pub fn rook_unblocked_attack_rays(square: u64) -> u64 {
         let rank = square >> 3;
         let file = square & 7;
         let mut rays = (FILE_A << file) | (RANK_1 << (rank << 3));
@@ -962,7 +1752,10 @@ impl ChessHandler {
         rays & !(1 << square)
     }
 
-    fn bishop_blocked_attack_rays(square: u64, blockers: u64) -> u64 {
+    // The squares attacked by a bishop on `square`, found by walking each ray until a blocker.
+    // This is only used to fill the magic lookup tables, and is much slower than `bishop_attacks`,
+    // but it serves as a reference implementation when checking the lookups.
+    pub fn bishop_blocked_attack_rays(square: u64, blockers: u64) -> u64 {
         let rank = square >> 3;
         let file = square & 7;
         let mut attack_bb = 0u64;
@@ -1016,7 +1809,8 @@ impl ChessHandler {
         attack_bb
     }
 
-    fn rook_blocked_attack_rays(square: u64, blockers: u64) -> u64 {
+    // The squares attacked by a rook on `square`, the slow reference for `rook_attacks`.
+    pub fn rook_blocked_attack_rays(square: u64, blockers: u64) -> u64 {
         let rank = square >> 3;
         let file = square & 7;
         let mut attack_bb = 0u64;
@@ -1075,11 +1869,14 @@ impl ChessHandler {
         count
     }
 
-    fn bit_permutations(mut bb: u64) -> Vec<u64> {
+    // Every subset of the set bits of `bb`, which are the blocker patterns
+    // the magic lookup for a mask of `bb` must distinguish.
+    // There are `2^n` subsets of a bitboard with `n` bits set, starting with `0`.
+    pub fn bit_permutations(mut bb: u64) -> Vec<u64> {
         let mut permutations: Vec<u64> = Vec::new();
         let mut digits: Vec<u64> = Vec::new();
         while bb != 0 {
-            digits.push(LOG_2_TABLE[(((bb & (!bb + 1)) * LOG_2_DE_BRUIJN) >> 58) as usize]);
+            digits.push(log2(bb & (!bb + 1)));
             bb &= bb - 1;
         }
         for perm_number in 0..1 << digits.len() {
@@ -1099,7 +1896,7 @@ impl ChessHandler {
         let shift = 64 - Self::popcount(mask);
         let mut vision_table = [0u64; 4096];
         for blocker_pattern in Self::bit_permutations(mask) {
-            let index = (blocker_pattern * magic) >> shift;
+            let index = blocker_pattern.wrapping_mul(magic) >> shift;
             if vision_table[index as usize] == 0 {
                 vision_table[index as usize] =
                     Self::bishop_blocked_attack_rays(square, blocker_pattern);
@@ -1115,7 +1912,7 @@ impl ChessHandler {
         let shift = 64 - Self::popcount(mask);
         let mut vision_table = [0u64; 4096];
         for blocker_pattern in Self::bit_permutations(mask) {
-            let index = (blocker_pattern * magic) >> shift;
+            let index = blocker_pattern.wrapping_mul(magic) >> shift;
             if vision_table[index as usize] == 0 {
                 vision_table[index as usize] =
                     Self::rook_blocked_attack_rays(square, blocker_pattern);
@@ -1126,30 +1923,44 @@ impl ChessHandler {
         Some((vision_table, shift))
     }
 
-    fn square_is_attacked(&self, square: u64, pos: ChessPos) -> bool {
+    // The squares attacked by a bishop on `square`, given the occupied squares `blockers`.
+    // The magic multiplication is intended to overflow, so it wraps explicitly.
+    #[inline]
+    pub fn bishop_attacks(&self, square: u64, blockers: u64) -> u64 {
+        let m_bishop = &self.bishop_magics[square as usize];
+        m_bishop.attack_table
+            [((blockers & m_bishop.mask).wrapping_mul(m_bishop.magic) >> m_bishop.shift) as usize]
+    }
+
+    // The squares attacked by a rook on `square`, given the occupied squares `blockers`.
+    #[inline]
+    pub fn rook_attacks(&self, square: u64, blockers: u64) -> u64 {
+        let m_rook = &self.rook_magics[square as usize];
+        m_rook.attack_table
+            [((blockers & m_rook.mask).wrapping_mul(m_rook.magic) >> m_rook.shift) as usize]
+    }
+
+    // The squares attacked by a queen on `square`, given the occupied squares `blockers`.
+    #[inline]
+    pub fn queen_attacks(&self, square: u64, blockers: u64) -> u64 {
+        self.bishop_attacks(square, blockers) | self.rook_attacks(square, blockers)
+    }
+
+    // Determines whether `square` is attacked by any of the opponent's pieces.
+    // As with all internal board representations, `square` is given from the perspective
+    // of the side to move (i.e. the board is flipped when it is Black's turn).
+    pub fn is_square_attacked(&self, pos: ChessPos, square: u64) -> bool {
         let blockers = pos.own | pos.other;
 
-        if (KING_ATTACKS[(pos.squares & 0x3f) as usize] & (1 << ((pos.squares >> 6) & 0x3f))) != 0 {
+        if (KING_ATTACKS[square as usize] & (1 << ((pos.squares >> 6) & 0x3f))) != 0 {
             return true;
         }
 
-        let m_rook = &self.rook_magics[square as usize];
-        if (pos.other
-            & pos.ortho
-            & (m_rook.attack_table
-                [(((blockers & m_rook.mask) * m_rook.magic) >> m_rook.shift) as usize]))
-            != 0
-        {
+        if (pos.other & pos.ortho & self.rook_attacks(square, blockers)) != 0 {
             return true;
         }
 
-        let m_bishop = &self.bishop_magics[square as usize];
-        if (pos.other
-            & pos.diag
-            & (m_bishop.attack_table
-                [(((blockers & m_bishop.mask) * m_bishop.magic) >> m_bishop.shift) as usize]))
-            != 0
-        {
+        if (pos.other & pos.diag & self.bishop_attacks(square, blockers)) != 0 {
             return true;
         }
 
@@ -1165,64 +1976,129 @@ impl ChessHandler {
 
         false
     }
-}
-
-impl GameHandler<ChessPos> for ChessHandler {
-    type Eval = i32;
-    type Params = ();
-
-    const EVAL_MINIMUM: i32 = -100000000;
-    const EVAL_MAXIMUM: i32 = 100000000;
-    const EVAL_EPSILON: i32 = 1;
 
-    fn new(_: ()) -> Self {
-        let mut bishop_table: [SMagic; 64] = std::array::from_fn(|_| SMagic::empty());
-        let mut rook_table: [SMagic; 64] = std::array::from_fn(|_| SMagic::empty());
-        for square in 0u64..64u64 {
-            if let Some((vision_table, shift)) =
-                Self::test_bishop_magic(square, BISHOP_MAGICS[square as usize])
-            {
-                bishop_table[square as usize] = SMagic {
-                    attack_table: vision_table
-                        .into_iter()
-                        .take((1 << (64 - shift)) as usize)
-                        .collect(),
-                    mask: Self::bishop_unblocked_attack_rays(square),
-                    magic: BISHOP_MAGICS[square as usize],
-                    shift,
-                };
+    // Parses a position in Forsyth-Edwards Notation as `ChessPos::from_fen` does.
+    // Outside Chess960 mode, castling rights are only accepted with the king on the e-file
+    // and the castling rook on its original corner, since the standard castling rules
+    // assume those squares. Any other castling right, such as an X-FEN or Shredder-FEN letter
+    // naming an inner rook, makes the position invalid rather than castling with the wrong rook.
+    pub fn position_from_fen(&self, fen: &str) -> Option<ChessPos> {
+        let pos = ChessPos::from_fen(fen)?;
+        if self.chess960 {
+            return Some(pos);
+        }
+        let white = pos.white_view();
+        let rooks = white.ortho & !white.diag;
+        let kingside_file = (white.squares >> KINGSIDE_ROOK_FILE_SHIFT) & 7;
+        let queenside_file = (white.squares >> QUEENSIDE_ROOK_FILE_SHIFT) & 7;
+        let valid = [
+            (20, white.own, white.squares & 0x3f, 0, 7),
+            (21, white.other, (white.squares >> 6) & 0x3f, 56, 7),
+            (22, white.own, white.squares & 0x3f, 0, 0),
+            (23, white.other, (white.squares >> 6) & 0x3f, 56, 0),
+        ]
+        .into_iter()
+        .all(|(right, side_bb, king_sq, rank, file)| {
+            let rook_file = if file == 7 {
+                kingside_file
             } else {
-                panic!(
-                    "BISHOP PANIC: Square {}: Magic {} is invalid.",
-                    square, BISHOP_MAGICS[square as usize]
-                );
+                queenside_file
+            };
+            ((white.squares >> right) & 1) == 0
+                || (king_sq == rank + 4
+                    && rook_file == file
+                    && ((side_bb & rooks) >> (rank + file)) & 1 == 1)
+        });
+        valid.then_some(pos)
+    }
+
+    // Generates castling moves under Chess960 rules, where the king and castling rook may start anywhere
+    // on the first rank. All squares between the king and its destination and between the rook
+    // and its destination must be empty (apart from the king and rook themselves),
+    // and the king may not pass through or start on an attacked square.
+    fn chess960_castling_moves(&self, pos: ChessPos) -> Vec<u64> {
+        // The squares from `a` to `b` inclusive, where both are on the first rank.
+        let span = |a: u64, b: u64| {
+            let (lo, hi) = (std::cmp::min(a, b), std::cmp::max(a, b));
+            (0xff >> (7 - hi)) & !((1 << lo) - 1)
+        };
+        let king_sq = pos.squares & 0x3f;
+        let blockers = pos.own | pos.other;
+        let rooks = pos.own & pos.ortho & !pos.diag;
+        let mut moves: Vec<u64> = Vec::new();
+        if king_sq > 7 {
+            return moves;
+        }
+        for (right, rook_sq, king_dest, rook_dest) in [
+            (20, (pos.squares >> KINGSIDE_ROOK_FILE_SHIFT) & 7, 6, 5),
+            (22, (pos.squares >> QUEENSIDE_ROOK_FILE_SHIFT) & 7, 2, 3),
+        ] {
+            if ((pos.squares >> right) & 1) == 0 || ((rooks >> rook_sq) & 1) == 0 {
+                continue;
             }
-            if let Some((vision_table, shift)) =
-                Self::test_rook_magic(square, ROOK_MAGICS[square as usize])
-            {
-                rook_table[square as usize] = SMagic {
-                    attack_table: vision_table
-                        .into_iter()
-                        .take((1 << (64 - shift)) as usize)
-                        .collect(),
-                    mask: Self::rook_unblocked_attack_rays(square),
-                    magic: ROOK_MAGICS[square as usize],
-                    shift,
-                };
-            } else {
-                panic!(
-                    "ROOK PANIC: Square {}: Magic {} is invalid.",
-                    square, ROOK_MAGICS[square as usize]
-                );
+            let clearance = (span(king_sq, king_dest) | span(rook_sq, rook_dest))
+                & !((1 << king_sq) | (1 << rook_sq));
+            if (blockers & clearance) != 0 {
+                continue;
+            }
+            let mut bb = span(king_sq, king_dest);
+            let mut can_cross = true;
+            while bb != 0 && can_cross {
+                can_cross = !self.is_square_attacked(pos, log2(bb & (!bb + 1)));
+                bb &= bb - 1;
+            }
+            if can_cross {
+                moves.push(make_move(king_sq, king_dest, FLAG_CASTLE, 0));
             }
         }
-        Self {
-            bishop_magics: bishop_table,
-            rook_magics: rook_table,
-        }
+        moves
     }
 
-    fn get_legal_moves(&self, pos: ChessPos) -> impl Iterator<Item = u64> {
+    // The bitboards of `pos` and the squares each side attacks, each drawn by `format_bitboard`
+    // under its name, for diagnosing move generation errors.
+    // Positions are stored from the perspective of the side to move,
+    // so with Black to move these are mirrored vertically relative to the board.
+    pub fn debug_bitboards(&self, pos: ChessPos) -> String {
+        let kings = (1 << (pos.squares & 0x3f)) | (1 << ((pos.squares >> 6) & 0x3f));
+        let flipped = pos.flip_position();
+        let attacks = |attacked: &dyn Fn(u64) -> bool| {
+            (0..64)
+                .filter(|&sq| attacked(sq))
+                .fold(0u64, |bb, sq| bb | (1 << sq))
+        };
+        let own_attacks = attacks(&|sq| self.is_square_attacked(flipped, flip_square(sq)));
+        let other_attacks = attacks(&|sq| self.is_square_attacked(pos, sq));
+        [
+            ("pawn", pos.pawn),
+            ("ortho", pos.ortho),
+            ("diag", pos.diag),
+            ("kings", kings),
+            ("own", pos.own),
+            ("other", pos.other),
+            ("attacked by own", own_attacks),
+            ("attacked by other", other_attacks),
+        ]
+        .iter()
+        .map(|&(name, bb)| format!("{}:\n{}", name, format_bitboard(bb)))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+    }
+
+    // Determines whether the king of the side to move is currently in check.
+    pub fn in_check(&self, pos: ChessPos) -> bool {
+        self.is_square_attacked(pos, pos.squares & 0x3f)
+    }
+
+    // Generates all moves that obey piece movement rules, without checking
+    // whether they leave the king of the side to move in check.
+    fn pseudo_legal_moves(&self, pos: ChessPos) -> Vec<u64> {
+        let mut moves = Vec::new();
+        self.pseudo_legal_moves_into(pos, &mut moves);
+        moves
+    }
+
+    // Appends the pseudo-legal moves of `pos` to `moves`.
+    fn pseudo_legal_moves_into(&self, pos: ChessPos, moves: &mut Vec<u64>) {
         let pawn = pos.own & pos.pawn;
         let knight = pos.own & !(pos.ortho | pos.diag | pos.pawn | (1 << (pos.squares & 0x3f)));
         let bishop = pos.own & pos.diag & !pos.ortho;
@@ -1230,25 +2106,35 @@ impl GameHandler<ChessPos> for ChessHandler {
         let queen = pos.own & pos.diag & pos.ortho;
 
         let blockers = pos.own | pos.other;
-        let mut moves: Vec<u64> = Vec::new();
         let mut bb: u64;
 
-        if ((pos.squares >> 20) & 1) == 1 && (blockers & KINGSIDE_CASTLE_CLEARANCE_MASK) == 0 {
+        // The standard castling masks assume the king starts on the e-file
+        // and the rooks on the a-file and h-file, which does not hold in Chess960.
+        if self.chess960 {
+            moves.extend(self.chess960_castling_moves(pos));
+        }
+        if !self.chess960
+            && ((pos.squares >> 20) & 1) == 1
+            && (blockers & KINGSIDE_CASTLE_CLEARANCE_MASK) == 0
+        {
             bb = KINGSIDE_CASTLE_CHECK_MASK;
             let mut can_cross = true;
             while bb != 0 && can_cross {
-                can_cross = !self.square_is_attacked(log2(bb & (!bb + 1)), pos);
+                can_cross = !self.is_square_attacked(pos, log2(bb & (!bb + 1)));
                 bb &= bb - 1;
             }
             if can_cross {
                 moves.push(make_move(4, 6, FLAG_CASTLE, 0));
             }
         }
-        if ((pos.squares >> 22) & 1) == 1 && (blockers & QUEENSIDE_CASTLE_CLEARANCE_MASK) == 0 {
+        if !self.chess960
+            && ((pos.squares >> 22) & 1) == 1
+            && (blockers & QUEENSIDE_CASTLE_CLEARANCE_MASK) == 0
+        {
             bb = QUEENSIDE_CASTLE_CHECK_MASK;
             let mut can_cross = true;
             while bb != 0 && can_cross {
-                can_cross = !self.square_is_attacked(log2(bb & (!bb + 1)), pos);
+                can_cross = !self.is_square_attacked(pos, log2(bb & (!bb + 1)));
                 bb &= bb - 1;
             }
             if can_cross {
@@ -1309,7 +2195,13 @@ impl GameHandler<ChessPos> for ChessHandler {
                         }
                         let ep_square = (pos.squares >> 12) & 0x7f;
 
-                        if (PAWN_ATTACKS[square as usize] & (1 << ep_square)) != 0 {
+                        // En passant removes two pawns from the same rank, which can expose
+                        // a horizontal rook or queen attack on the king (e.g. `8/8/8/KPp4r/8/8/8/7k w - c6`).
+                        // No special handling is needed here, since the legality filter below
+                        // tests for check on the position after `play_move` has removed both pawns.
+                        if ep_square != NO_EN_PASSANT
+                            && (PAWN_ATTACKS[square as usize] & (1 << ep_square)) != 0
+                        {
                             moves.push(make_move(square, ep_square, FLAG_ENPASSANT, 0));
                         }
                     }
@@ -1321,33 +2213,22 @@ impl GameHandler<ChessPos> for ChessHandler {
                     bb &= bb - 1;
                 }
             } else if ((bishop >> square) & 1) == 1 {
-                let m_bishop = &self.bishop_magics[square as usize];
-                bb = !pos.own
-                    & m_bishop.attack_table[(((blockers & m_bishop.mask) * m_bishop.magic)
-                        >> m_bishop.shift) as usize];
+                bb = !pos.own & self.bishop_attacks(square, blockers);
 
                 while bb != 0 {
                     moves.push(make_move(square, log2(bb & (!bb + 1)), FLAG_NONE, 0));
                     bb &= bb - 1;
                 }
             } else if ((rook >> square) & 1) == 1 {
-                let m_rook = &self.rook_magics[square as usize];
-                bb = !pos.own
-                    & m_rook.attack_table
-                        [(((blockers & m_rook.mask) * m_rook.magic) >> m_rook.shift) as usize];
+                bb = !pos.own & self.rook_attacks(square, blockers);
 
                 while bb != 0 {
                     moves.push(make_move(square, log2(bb & (!bb + 1)), FLAG_NONE, 0));
                     bb &= bb - 1;
                 }
             } else if ((queen >> square) & 1) == 1 {
-                let m_bishop = &self.bishop_magics[square as usize];
-                let m_rook = &self.rook_magics[square as usize];
                 bb = !pos.own
-                    & (m_bishop.attack_table[(((blockers & m_bishop.mask) * m_bishop.magic)
-                        >> m_bishop.shift) as usize]
-                        | m_rook.attack_table
-                            [(((blockers & m_rook.mask) * m_rook.magic) >> m_rook.shift) as usize]);
+                    & (self.bishop_attacks(square, blockers) | self.rook_attacks(square, blockers));
 
                 while bb != 0 {
                     moves.push(make_move(square, log2(bb & (!bb + 1)), FLAG_NONE, 0));
@@ -1355,14 +2236,1602 @@ impl GameHandler<ChessPos> for ChessHandler {
                 }
             }
         }
-        moves.into_iter().filter(move |&mv| {
-            let new_pos = pos.play_move(mv).flip_position();
-            let king_sq = new_pos.squares & 0x3f;
-            !self.square_is_attacked(king_sq, new_pos)
+    }
+
+    // Whether a pseudo-legal move does not leave the king of the side to move in check.
+    fn leaves_king_safe(&self, pos: ChessPos, mv: u64) -> bool {
+        let new_pos = pos.play_move(mv).flip_position();
+        let king_sq = new_pos.squares & 0x3f;
+        !self.is_square_attacked(new_pos, king_sq)
+    }
+
+    // Sums the material of the given side's pieces (excluding the king),
+    // where `side` is a bitboard of all the pieces belonging to that side.
+    fn side_material(pos: ChessPos, side: u64) -> i32 {
+        let kings = (1 << (pos.squares & 0x3f)) | (1 << ((pos.squares >> 6) & 0x3f));
+        let pawns = side & pos.pawn;
+        let knights = side & !(pos.ortho | pos.diag | pos.pawn | kings);
+        let bishops = side & pos.diag & !pos.ortho;
+        let rooks = side & pos.ortho & !pos.diag;
+        let queens = side & pos.diag & pos.ortho;
+        Self::PAWN_VALUE * Self::popcount(pawns) as i32
+            + Self::KNIGHT_VALUE * Self::popcount(knights) as i32
+            + Self::BISHOP_VALUE * Self::popcount(bishops) as i32
+            + Self::ROOK_VALUE * Self::popcount(rooks) as i32
+            + Self::QUEEN_VALUE * Self::popcount(queens) as i32
+    }
+
+    // Returns the material (in centipawns) of the side to move and the opponent respectively.
+    // Kings are not counted, since both sides always have exactly one.
+    pub fn material_count(&self, pos: ChessPos) -> (i32, i32) {
+        (
+            Self::side_material(pos, pos.own),
+            Self::side_material(pos, pos.other),
+        )
+    }
+
+    // Returns how far the game has progressed based on the remaining non-pawn material,
+    // ranging from `0.0` (all pieces on the board, opening) to `1.0` (only kings and pawns, endgame).
+    // Positions with more non-pawn material than the starting position (from promotions) are clamped to `0.0`.
+    pub fn game_phase(&self, pos: ChessPos) -> f32 {
+        let kings = (1 << (pos.squares & 0x3f)) | (1 << ((pos.squares >> 6) & 0x3f));
+        let knights = (pos.own | pos.other) & !(pos.ortho | pos.diag | pos.pawn | kings);
+        let bishops = pos.diag & !pos.ortho;
+        let rooks = pos.ortho & !pos.diag;
+        let queens = pos.diag & pos.ortho;
+        let phase = Self::KNIGHT_PHASE * Self::popcount(knights) as i32
+            + Self::BISHOP_PHASE * Self::popcount(bishops) as i32
+            + Self::ROOK_PHASE * Self::popcount(rooks) as i32
+            + Self::QUEEN_PHASE * Self::popcount(queens) as i32;
+        1.0 - (std::cmp::min(phase, Self::PHASE_TOTAL) as f32) / (Self::PHASE_TOTAL as f32)
+    }
+
+    // The number of squares the knights, bishops, rooks and queens of the side to move
+    // can move to, ignoring pins and checks.
+    fn side_mobility(&self, pos: ChessPos) -> i32 {
+        let blockers = pos.own | pos.other;
+        let kings = (1 << (pos.squares & 0x3f)) | (1 << ((pos.squares >> 6) & 0x3f));
+        let mut pieces = pos.own & !pos.pawn & !kings;
+        let mut mobility = 0;
+        while pieces != 0 {
+            let square = log2(pieces & (!pieces + 1));
+            let mut attacks = 0;
+            if ((pos.diag >> square) & 1) == 1 {
+                attacks |= self.bishop_attacks(square, blockers);
+            }
+            if ((pos.ortho >> square) & 1) == 1 {
+                attacks |= self.rook_attacks(square, blockers);
+            }
+            if (((pos.diag | pos.ortho) >> square) & 1) == 0 {
+                attacks = KNIGHT_ATTACKS[square as usize];
+            }
+            mobility += Self::popcount(attacks & !pos.own) as i32;
+            pieces &= pieces - 1;
+        }
+        mobility
+    }
+
+    // Returns the mobility of the side to move and the opponent respectively,
+    // as the number of squares their knights, bishops, rooks and queens can move to.
+    pub fn mobility(&self, pos: ChessPos) -> (i32, i32) {
+        (
+            self.side_mobility(pos),
+            self.side_mobility(pos.flip_position()),
+        )
+    }
+
+    // The number of squares around the king of the side to move (including its own square)
+    // that are attacked by the opponent.
+    fn side_king_danger(&self, pos: ChessPos) -> i32 {
+        let king = pos.squares & 0x3f;
+        let mut zone = KING_ATTACKS[king as usize] | (1 << king);
+        let mut danger = 0;
+        while zone != 0 {
+            if self.is_square_attacked(pos, log2(zone & (!zone + 1))) {
+                danger += 1;
+            }
+            zone &= zone - 1;
+        }
+        danger
+    }
+
+    // Returns the number of attacked squares around the king of the side to move
+    // and the opponent respectively, where higher values indicate a less safe king.
+    pub fn king_danger(&self, pos: ChessPos) -> (i32, i32) {
+        (
+            self.side_king_danger(pos),
+            self.side_king_danger(pos.flip_position()),
+        )
+    }
+
+    // The pawn structure score of the pawns `own` moving up the board, against the pawns `other` moving down.
+    fn side_pawn_structure(own: u64, other: u64) -> i32 {
+        let mut score = 0;
+        for file in 0..8 {
+            let file_mask = FILE_A << file;
+            let count = Self::popcount(own & file_mask) as i32;
+            if count == 0 {
+                continue;
+            }
+            if count > 1 {
+                score -= Self::DOUBLED_PAWN_PENALTY * (count - 1);
+            }
+            let adjacent_files = ((file_mask & !(FILE_A << 7)) << 1) | ((file_mask & !FILE_A) >> 1);
+            if (own & adjacent_files) == 0 {
+                score -= Self::ISOLATED_PAWN_PENALTY * count;
+            }
+        }
+
+        // Every square in front of (or diagonally in front of) an opposing pawn,
+        // from the perspective of the pawns `own`, which no passed pawn may stand on.
+        let mut front_span = other >> 8;
+        front_span |= front_span >> 8;
+        front_span |= front_span >> 16;
+        front_span |= front_span >> 32;
+        let blocked =
+            front_span | ((front_span & !(FILE_A << 7)) << 1) | ((front_span & !FILE_A) >> 1);
+        let mut passed = own & !blocked;
+        while passed != 0 {
+            score += Self::PASSED_PAWN_BONUS[(log2(passed & (!passed + 1)) >> 3) as usize];
+            passed &= passed - 1;
+        }
+        score
+    }
+
+    // Scores doubled, isolated and passed pawns from the perspective of the side to move.
+    pub fn pawn_structure_eval(&self, pos: ChessPos) -> i32 {
+        let own = pos.own & pos.pawn;
+        let other = pos.other & pos.pawn;
+        Self::side_pawn_structure(own, other)
+            - Self::side_pawn_structure(flip_bb(other), flip_bb(own))
+    }
+
+    // The sum of the bonuses of a piece-square table over the squares of `pieces`,
+    // which are from the perspective of the owner of the pieces.
+    fn piece_square_sum(table: &[[i32; 8]; 8], mut pieces: u64) -> i32 {
+        let mut score = 0;
+        while pieces != 0 {
+            let square = log2(pieces & (!pieces + 1));
+            score += table[7 - (square >> 3) as usize][(square & 7) as usize];
+            pieces &= pieces - 1;
+        }
+        score
+    }
+
+    // The piece-square bonuses of the pieces of the side to move.
+    fn side_piece_squares(pos: ChessPos, phase: f32) -> i32 {
+        let king = pos.squares & 0x3f;
+        let own = pos.own & !(1 << king);
+        let knights = own & !(pos.ortho | pos.diag | pos.pawn);
+        let middlegame = Self::piece_square_sum(&Self::KING_MIDDLEGAME_SQUARES, 1 << king);
+        let endgame = Self::piece_square_sum(&Self::KING_ENDGAME_SQUARES, 1 << king);
+        Self::piece_square_sum(&Self::PAWN_SQUARES, own & pos.pawn)
+            + Self::piece_square_sum(&Self::KNIGHT_SQUARES, knights)
+            + Self::piece_square_sum(&Self::BISHOP_SQUARES, own & pos.diag & !pos.ortho)
+            + Self::piece_square_sum(&Self::ROOK_SQUARES, own & pos.ortho & !pos.diag)
+            + Self::piece_square_sum(&Self::QUEEN_SQUARES, own & pos.ortho & pos.diag)
+            + ((1.0 - phase) * middlegame as f32 + phase * endgame as f32).round() as i32
+    }
+
+    // Scores the placement of every piece by the piece-square tables,
+    // from the perspective of the side to move.
+    // The opponent's pieces are scored on the mirrored board, where they are the side to move,
+    // so a position and its colour-reversed counterpart score the same for the side to move.
+    pub fn piece_square_eval(&self, pos: ChessPos) -> i32 {
+        let phase = self.game_phase(pos);
+        Self::side_piece_squares(pos, phase) - Self::side_piece_squares(pos.flip_position(), phase)
+    }
+
+    // The material value of the piece of either side on `square`, or `0` if it is empty.
+    // Kings are given a value above all other pieces so that they are ordered last as attackers.
+    fn piece_value_at(pos: ChessPos, square: u64) -> i32 {
+        let bit = 1 << square;
+        if ((pos.own | pos.other) & bit) == 0 {
+            0
+        } else if (pos.squares & 0x3f) == square || ((pos.squares >> 6) & 0x3f) == square {
+            Self::QUEEN_VALUE * 2
+        } else if (pos.pawn & bit) != 0 {
+            Self::PAWN_VALUE
+        } else if (pos.ortho & pos.diag & bit) != 0 {
+            Self::QUEEN_VALUE
+        } else if (pos.ortho & bit) != 0 {
+            Self::ROOK_VALUE
+        } else if (pos.diag & bit) != 0 {
+            Self::BISHOP_VALUE
+        } else {
+            Self::KNIGHT_VALUE
+        }
+    }
+
+    // Whether a move captures a piece, including en passant captures.
+    pub fn is_capture(&self, pos: ChessPos, mv: u64) -> bool {
+        ((mv >> 12) & 0x3) == FLAG_ENPASSANT || ((pos.other >> ((mv >> 6) & 0x3f)) & 1) == 1
+    }
+
+    // Whether a move promotes a pawn.
+    pub fn is_promotion(&self, mv: u64) -> bool {
+        ((mv >> 12) & 0x3) == FLAG_PROMOTE
+    }
+
+    // Most Valuable Victim - Least Valuable Attacker ordering score of a capture,
+    // where higher scores should be searched first. Non-captures score `0`.
+    // The value of the victim dominates, and captures of equally valuable victims
+    // prefer the less valuable attacker, so a pawn taking a queen is searched
+    // before a queen taking a queen. Every capture scores above `0`,
+    // even a king taking a pawn, so the score also distinguishes captures.
+    pub fn mvv_lva(&self, pos: ChessPos, mv: u64) -> i32 {
+        let victim = if ((mv >> 12) & 0x3) == FLAG_ENPASSANT {
+            Self::PAWN_VALUE
+        } else {
+            Self::piece_value_at(pos, (mv >> 6) & 0x3f)
+        };
+        if victim == 0 {
+            0
+        } else {
+            victim * 16 - Self::piece_value_at(pos, mv & 0x3f) / 100
+        }
+    }
+
+    // Generates all legal captures, without any particular ordering.
+    pub fn get_capture_moves(&self, pos: ChessPos) -> Vec<u64> {
+        self.pseudo_legal_moves(pos)
+            .into_iter()
+            .filter(|&mv| self.is_capture(pos, mv) && self.leaves_king_safe(pos, mv))
+            .collect()
+    }
+
+    // Whether a position is quiet, meaning the side to move is not in check
+    // and has no legal capture or promotion, so its static evaluation can be trusted
+    // without first resolving the pending tactics in a quiescence search.
+    pub fn is_quiet(&self, pos: ChessPos) -> bool {
+        !self.in_check(pos)
+            && !self.pseudo_legal_moves(pos).into_iter().any(|mv| {
+                (self.is_capture(pos, mv) || self.is_promotion(mv))
+                    && self.leaves_king_safe(pos, mv)
+            })
+    }
+
+    // Generates all legal moves that give check, capture or promote, which are the moves
+    // a tactical search considers, in the same order as `get_legal_moves`.
+    pub fn get_forcing_moves(&self, pos: ChessPos) -> Vec<u64> {
+        self.get_legal_moves(pos)
+            .filter(|&mv| {
+                self.is_capture(pos, mv)
+                    || self.is_promotion(mv)
+                    || self.in_check(pos.play_move(mv))
+            })
+            .collect()
+    }
+
+    // Creates a generator yielding the legal moves of `pos` in the stages described by `MoveStage`.
+    pub fn staged_moves(
+        &self,
+        pos: ChessPos,
+        hash_move: Option<u64>,
+        killers: [Option<u64>; 2],
+    ) -> ChessMoveStage<'_> {
+        let pseudo_legal = self.pseudo_legal_moves(pos);
+        let buffer = hash_move
+            .filter(|&mv| pseudo_legal.contains(&mv) && self.leaves_king_safe(pos, mv))
+            .into_iter()
+            .collect();
+        ChessMoveStage {
+            handler: self,
+            pos,
+            stage: MoveStage::HashMove,
+            hash_move,
+            killers,
+            pseudo_legal,
+            buffer,
+        }
+    }
+
+    // Generates the legal moves of `pos` with the captures first, ordered by `mvv_lva`,
+    // followed by the other moves in generation order, which is `staged_moves`
+    // without a hash move or killer moves. A search that cuts off on a capture
+    // never checks the legality of the quiet moves.
+    pub fn get_legal_moves_staged(&self, pos: ChessPos) -> ChessMoveStage<'_> {
+        self.staged_moves(pos, None, [None; 2])
+    }
+}
+
+// The phases of `ChessMoveStage`, in the order they are yielded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveStage {
+    // The best move previously found in this position, if it is legal.
+    HashMove,
+    // All captures, ordered by `mvv_lva`.
+    Captures,
+    // Legal quiet moves that caused cutoffs in sibling nodes.
+    Killers,
+    // All remaining moves, in generation order.
+    Quiets,
+    Done,
+}
+
+// A move generator that yields legal moves in stages, so that a search which cuts off early
+// never pays for sorting captures or checking the legality of quiet moves it does not reach.
+// Every legal move is yielded exactly once, so fully expanding it gives the same set as `get_legal_moves`.
+pub struct ChessMoveStage<'a> {
+    handler: &'a ChessHandler,
+    pos: ChessPos,
+    stage: MoveStage,
+    hash_move: Option<u64>,
+    killers: [Option<u64>; 2],
+    pseudo_legal: Vec<u64>,
+    // The remaining moves of the current stage, stored in reverse order.
+    buffer: Vec<u64>,
+}
+
+impl ChessMoveStage<'_> {
+    // The stage that the most recently yielded move belongs to.
+    pub fn stage(&self) -> MoveStage {
+        self.stage
+    }
+
+    fn is_killer(&self, mv: u64) -> bool {
+        self.killers.contains(&Some(mv))
+    }
+}
+
+impl Iterator for ChessMoveStage<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        loop {
+            if let Some(mv) = self.buffer.pop() {
+                return Some(mv);
+            }
+            let (handler, pos) = (self.handler, self.pos);
+            self.stage = match self.stage {
+                MoveStage::HashMove => {
+                    self.buffer = self
+                        .pseudo_legal
+                        .iter()
+                        .copied()
+                        .filter(|&mv| {
+                            Some(mv) != self.hash_move
+                                && handler.is_capture(pos, mv)
+                                && handler.leaves_king_safe(pos, mv)
+                        })
+                        .collect();
+                    self.buffer.sort_by_key(|&mv| handler.mvv_lva(pos, mv));
+                    MoveStage::Captures
+                }
+                MoveStage::Captures => {
+                    let mut killers = Vec::new();
+                    for mv in self.killers.into_iter().flatten() {
+                        if Some(mv) != self.hash_move
+                            && !killers.contains(&mv)
+                            && self.pseudo_legal.contains(&mv)
+                            && !handler.is_capture(pos, mv)
+                            && handler.leaves_king_safe(pos, mv)
+                        {
+                            killers.push(mv);
+                        }
+                    }
+                    killers.reverse();
+                    self.buffer = killers;
+                    MoveStage::Killers
+                }
+                MoveStage::Killers => {
+                    self.buffer = self
+                        .pseudo_legal
+                        .iter()
+                        .rev()
+                        .copied()
+                        .filter(|&mv| {
+                            Some(mv) != self.hash_move
+                                && !self.is_killer(mv)
+                                && !handler.is_capture(pos, mv)
+                                && handler.leaves_king_safe(pos, mv)
+                        })
+                        .collect();
+                    MoveStage::Quiets
+                }
+                MoveStage::Quiets | MoveStage::Done => {
+                    self.stage = MoveStage::Done;
+                    return None;
+                }
+            };
+        }
+    }
+}
+
+impl GameHandler<ChessPos> for ChessHandler {
+    type Eval = Centipawn;
+    type Params = ChessParams;
+
+    const EVAL_MINIMUM: Centipawn = Centipawn(-100000000);
+    const EVAL_MAXIMUM: Centipawn = Centipawn(100000000);
+    const EVAL_EPSILON: Centipawn = Centipawn(1);
+
+    fn new(params: ChessParams) -> Self {
+        let mut bishop_table: [SMagic; 64] = std::array::from_fn(|_| SMagic::empty());
+        let mut rook_table: [SMagic; 64] = std::array::from_fn(|_| SMagic::empty());
+        for square in 0u64..64u64 {
+            if let Some((vision_table, shift)) =
+                Self::test_bishop_magic(square, BISHOP_MAGICS[square as usize])
+            {
+                bishop_table[square as usize] = SMagic {
+                    attack_table: vision_table
+                        .into_iter()
+                        .take((1 << (64 - shift)) as usize)
+                        .collect(),
+                    mask: Self::bishop_unblocked_attack_rays(square),
+                    magic: BISHOP_MAGICS[square as usize],
+                    shift,
+                };
+            } else {
+                panic!(
+                    "BISHOP PANIC: Square {}: Magic {} is invalid.",
+                    square, BISHOP_MAGICS[square as usize]
+                );
+            }
+            if let Some((vision_table, shift)) =
+                Self::test_rook_magic(square, ROOK_MAGICS[square as usize])
+            {
+                rook_table[square as usize] = SMagic {
+                    attack_table: vision_table
+                        .into_iter()
+                        .take((1 << (64 - shift)) as usize)
+                        .collect(),
+                    mask: Self::rook_unblocked_attack_rays(square),
+                    magic: ROOK_MAGICS[square as usize],
+                    shift,
+                };
+            } else {
+                panic!(
+                    "ROOK PANIC: Square {}: Magic {} is invalid.",
+                    square, ROOK_MAGICS[square as usize]
+                );
+            }
+        }
+        Self {
+            bishop_magics: bishop_table,
+            rook_magics: rook_table,
+            chess960: params.chess960,
+            mobility_weight: params.mobility_weight,
+            king_safety_weight: params.king_safety_weight,
+            pawn_structure: params.pawn_structure,
+            piece_square_tables: params.piece_square_tables,
+            win_prob_scale: if params.win_prob_scale == 0 {
+                Self::DEFAULT_WIN_PROB_SCALE
+            } else {
+                params.win_prob_scale
+            },
+            contempt: params.contempt,
+        }
+    }
+
+    fn get_legal_moves(&self, pos: ChessPos) -> impl Iterator<Item = u64> {
+        self.pseudo_legal_moves(pos)
+            .into_iter()
+            .filter(move |&mv| self.leaves_king_safe(pos, mv))
+    }
+
+    // Generates the pseudo-legal moves directly into `buf`,
+    // then removes those that leave the king in check without another allocation.
+    fn legal_moves_into(&self, pos: ChessPos, buf: &mut Vec<u64>) {
+        let start = buf.len();
+        self.pseudo_legal_moves_into(pos, buf);
+        let mut legal = start;
+        for i in start..buf.len() {
+            if self.leaves_king_safe(pos, buf[i]) {
+                buf[legal] = buf[i];
+                legal += 1;
+            }
+        }
+        buf.truncate(legal);
+    }
+
+    // Captures and promotions, with captures of the most valuable pieces
+    // by the least valuable pieces first, as ordered by `mvv_lva`.
+    fn get_loud_moves(&self, pos: ChessPos) -> impl Iterator<Item = u64> {
+        let mut moves: Vec<u64> = self
+            .pseudo_legal_moves(pos)
+            .into_iter()
+            .filter(|&mv| {
+                (self.is_capture(pos, mv) || self.is_promotion(mv))
+                    && self.leaves_king_safe(pos, mv)
+            })
+            .collect();
+        moves.sort_by_key(|&mv| -self.mvv_lva(pos, mv));
+        moves.into_iter()
+    }
+
+    // Only the given move is checked for leaving the king in check,
+    // rather than every pseudo-legal move as in `get_legal_moves`.
+    fn is_legal(&self, pos: ChessPos, mv: u64) -> bool {
+        self.pseudo_legal_moves(pos).contains(&mv) && self.leaves_king_safe(pos, mv)
+    }
+
+    // Checkmate is scored relative to the root, so that quicker mates are preferred.
+    // Otherwise, the evaluation is the material balance together with
+    // the mobility and king safety terms, weighted as given in `ChessParams`.
+    fn evaluate(&self, pos: ChessPos, depth: usize, max_depth: usize) -> Self::Eval {
+        if self.get_legal_moves(pos).next().is_none() {
+            return self.terminal_eval(pos, depth, max_depth);
+        }
+        let (own_material, other_material) = self.material_count(pos);
+        let mut eval = Centipawn(own_material) - Centipawn(other_material);
+        if self.mobility_weight != 0 {
+            let (own_mobility, other_mobility) = self.mobility(pos);
+            eval += Centipawn(
+                self.mobility_weight
+                    .saturating_mul(own_mobility - other_mobility),
+            );
+        }
+        if self.king_safety_weight != 0 {
+            let (own_danger, other_danger) = self.king_danger(pos);
+            eval -= Centipawn(
+                self.king_safety_weight
+                    .saturating_mul(own_danger - other_danger),
+            );
+        }
+        if self.pawn_structure {
+            eval += Centipawn(self.pawn_structure_eval(pos));
+        }
+        if self.piece_square_tables {
+            eval += Centipawn(self.piece_square_eval(pos));
+        }
+        eval
+    }
+
+    // With no legal moves, the side to move is checkmated if in check, and stalemated otherwise.
+    fn terminal_eval(&self, pos: ChessPos, depth: usize, max_depth: usize) -> Self::Eval {
+        if self.in_check(pos) {
+            Centipawn::mated(max_depth - depth)
+        } else {
+            self.draw_eval(pos, depth, max_depth)
+        }
+    }
+
+    // A position is drawn once it occurs for the third time, as in `classify_line`,
+    // though here positions are compared by their Zobrist keys.
+    fn is_repetition_draw(&self, _pos: ChessPos, occurrences: usize) -> bool {
+        occurrences >= 3
+    }
+
+    // A draw is worth `-contempt` to the player to move at the root,
+    // which is the player to move after an even number of plies.
+    fn draw_eval(&self, _pos: ChessPos, depth: usize, max_depth: usize) -> Self::Eval {
+        if ((max_depth - depth) & 1) == 0 {
+            Centipawn(-self.contempt)
+        } else {
+            Centipawn(self.contempt)
+        }
+    }
+
+    // Mate scores are far beyond any material advantage, so they map to `1.0` and `0.0`.
+    fn eval_to_win_prob(&self, eval: Centipawn) -> f64 {
+        logistic(eval.0 as f64, self.win_prob_scale as f64)
+    }
+
+    fn should_extend(&self, pos: ChessPos) -> bool {
+        self.in_check(pos)
+    }
+
+    fn is_quiet_move(&self, pos: ChessPos, mv: u64) -> bool {
+        !self.is_capture(pos, mv) && !self.is_promotion(mv)
+    }
+
+    // Captures come first, ordered by MVV-LVA, followed by the other moves in generation order.
+    fn order_moves(&self, pos: ChessPos, moves: &mut Vec<u64>) {
+        moves.sort_by_key(|&mv| -self.mvv_lva(pos, mv));
+    }
+
+    // Moves are stored relative to the side to move, so the side is taken from `pos`.
+    fn format_move(&self, pos: ChessPos, mv: u64) -> String {
+        self.move_string(mv, self.current_player(pos) as u64)
+    }
+
+    fn current_player(&self, pos: ChessPos) -> usize {
+        // White is always player `0`, so `evaluate_absolute` is White-positive
+        // even for positions set up with Black to move.
+        ((pos.squares >> 19) & 1) as usize
+    }
+}
+
+// Moves are packed into a `u64` as `(promote << 14) | (flag << 12) | (to << 6) | from`.
+// For readability when persisted, this module (de)serialises a packed move as an object
+// with these four components, and can be used through `#[serde(with = "serde_move")]`.
+#[cfg(feature = "serde")]
+pub mod serde_move {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct UnpackedMove {
+        from: u64,
+        to: u64,
+        flag: u64,
+        promote: u64,
+    }
+
+    pub fn serialize<S: Serializer>(mv: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        UnpackedMove {
+            from: mv & 0x3f,
+            to: (mv >> 6) & 0x3f,
+            flag: (mv >> 12) & 0x3,
+            promote: (mv >> 14) & 0x3,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let UnpackedMove {
+            from,
+            to,
+            flag,
+            promote,
+        } = UnpackedMove::deserialize(deserializer)?;
+        if from > 63 || to > 63 || flag > 3 || promote > 3 {
+            return Err(serde::de::Error::custom("move component out of range"));
+        }
+        Ok((promote << 14) | (flag << 12) | (to << 6) | from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::*;
+
+    fn handler() -> ChessHandler {
+        ChessHandler::new(ChessParams::default())
+    }
+
+    fn pos(fen: &str) -> ChessPos {
+        ChessPos::from_fen(fen).unwrap()
+    }
+
+    #[test]
+    fn material_and_phase_of_start_position() {
+        let handler = handler();
+        let start = ChessPos::startpos(());
+        assert_eq!(handler.material_count(start), (4000, 4000));
+        assert_eq!(handler.game_phase(start), 0.0);
+    }
+
+    #[test]
+    fn material_and_phase_of_endgame() {
+        let handler = handler();
+        // A rook and two pawns against a knight and two pawns.
+        let white = pos("8/5k2/4pp2/8/3n4/6P1/5PK1/3R4 w - - 0 1");
+        assert_eq!(handler.material_count(white), (700, 520));
+        assert_eq!(handler.game_phase(white), 0.875);
+        let black = pos("8/5k2/4pp2/8/3n4/6P1/5PK1/3R4 b - - 0 1");
+        assert_eq!(handler.material_count(black), (520, 700));
+        // Only kings and pawns remain.
+        assert_eq!(handler.game_phase(pos("8/5k2/4p3/8/8/6P1/6K1/8 w - - 0 1")), 1.0);
+    }
+
+    fn square(name: &str) -> u64 {
+        ChessPos::string_to_square(name).unwrap()
+    }
+
+    #[test]
+    fn knight_and_pawn_attacks() {
+        let handler = handler();
+        let p = pos("4k3/8/8/3p4/8/4n3/8/4K3 w - - 0 1");
+        // The knight on e3 and the pawn on d5.
+        assert!(handler.is_square_attacked(p, square("d1")));
+        assert!(handler.is_square_attacked(p, square("g4")));
+        assert!(handler.is_square_attacked(p, square("c4")));
+        assert!(handler.is_square_attacked(p, square("e4")));
+        assert!(!handler.is_square_attacked(p, square("d4")));
+        assert!(!handler.is_square_attacked(p, square("e2")));
+        assert!(handler.in_check(pos("4k3/8/8/8/8/3n4/8/4K3 w - - 0 1")));
+        assert!(handler.in_check(pos("4k3/8/8/8/8/8/3p4/4K3 w - - 0 1")));
+        assert!(!handler.in_check(pos("4k3/8/8/8/8/8/4p3/4K3 w - - 0 1")));
+    }
+
+    #[test]
+    fn sliding_attacks_stop_at_blockers() {
+        let handler = handler();
+        let p = pos("4k3/8/8/8/1b6/8/3P4/r3K3 w - - 0 1");
+        assert!(handler.in_check(p));
+        assert!(handler.is_square_attacked(p, square("c3")));
+        assert!(!handler.is_square_attacked(p, square("b2")));
+        // The pawn on d2 blocks the bishop's diagonal.
+        let p = pos("4k3/8/8/8/1b6/8/3P4/4K3 w - - 0 1");
+        assert!(handler.is_square_attacked(p, square("d2")));
+        assert!(!handler.in_check(p));
+        // The knight on d1 blocks the rook's rank.
+        let p = pos("4k3/8/8/8/8/8/8/r2NK3 w - - 0 1");
+        assert!(handler.is_square_attacked(p, square("c1")));
+        assert!(!handler.in_check(p));
+        assert!(handler.in_check(pos("4k3/8/8/8/7q/8/8/4K3 w - - 0 1")));
+    }
+
+    #[test]
+    fn adjacent_king_attacks_only_neighbouring_squares() {
+        let handler = handler();
+        let p = pos("8/8/8/8/8/3k4/8/4K3 w - - 0 1");
+        assert!(handler.is_square_attacked(p, square("e2")));
+        assert!(handler.is_square_attacked(p, square("c4")));
+        assert!(!handler.is_square_attacked(p, square("e1")));
+        assert!(!handler.is_square_attacked(p, square("f2")));
+        assert!(!handler.is_square_attacked(p, square("a8")));
+    }
+
+    #[test]
+    fn attacks_from_black_perspective() {
+        let handler = handler();
+        // With Black to move, squares are given from Black's side of the board.
+        let p = pos("4k3/8/8/8/8/8/8/R3K3 b - - 0 1");
+        assert!(handler.is_square_attacked(p, flip_square(square("a8"))));
+        assert!(!handler.is_square_attacked(p, square("a8")));
+        assert!(!handler.in_check(p));
+        assert!(handler.in_check(pos("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1")));
+    }
+
+    fn legal_move_strings(handler: &ChessHandler, p: ChessPos) -> Vec<String> {
+        handler
+            .get_legal_moves(p)
+            .map(|mv| handler.move_string(mv, (p.squares >> 19) & 1))
+            .collect()
+    }
+
+    #[test]
+    fn en_passant_exposing_rank_check_is_illegal() {
+        let handler = handler();
+        let white = legal_move_strings(&handler, pos("8/8/8/KPp4r/8/8/8/7k w - c6 0 1"));
+        assert!(!white.contains(&"b5c6ep".to_string()));
+        assert!(white.contains(&"b5b6".to_string()));
+        let black = legal_move_strings(&handler, pos("7K/8/8/8/kpP4R/8/8/8 b - c3 0 1"));
+        assert!(!black.contains(&"b4c3ep".to_string()));
+        assert!(black.contains(&"b4b3".to_string()));
+        // Without the rook, the same captures are legal.
+        let white = legal_move_strings(&handler, pos("8/8/8/KPp5/8/8/8/7k w - c6 0 1"));
+        assert!(white.contains(&"b5c6ep".to_string()));
+    }
+
+    fn play_uci(handler: &ChessHandler, p: ChessPos, uci: &str) -> ChessPos {
+        let side = (p.squares >> 19) & 1;
+        let mv = handler
+            .get_legal_moves(p)
+            .find(|&mv| handler.move_string(mv, side) == uci)
+            .unwrap();
+        p.play_move(mv)
+    }
+
+    #[test]
+    fn double_push_sets_en_passant_square() {
+        let handler = handler();
+        let start = ChessPos::startpos(());
+        let p = play_uci(&handler, start, "e2e4");
+        assert_eq!(p.to_fen().split(' ').nth(3), Some("e3"));
+        let p = play_uci(&handler, p, "c7c5");
+        assert_eq!(p.to_fen().split(' ').nth(3), Some("c6"));
+        // The square is cleared by the next move, and not set by single pushes.
+        let p = play_uci(&handler, p, "d2d3");
+        assert_eq!(p.to_fen().split(' ').nth(3), Some("-"));
+    }
+
+    #[test]
+    fn only_pawn_double_pushes_set_en_passant_square() {
+        let handler = handler();
+        // A rook moving two ranks up from the second rank is not a double push.
+        let p = play_uci(&handler, pos("4k3/8/8/8/8/8/4R3/4K3 w - - 0 1"), "e2e4");
+        assert_eq!(p.to_fen().split(' ').nth(3), Some("-"));
+        let p = play_uci(&handler, pos("4k3/4r3/8/8/8/8/8/4K3 b - - 0 1"), "e7e5");
+        assert_eq!(p.to_fen().split(' ').nth(3), Some("-"));
+    }
+
+    #[test]
+    fn blocked_double_push_is_not_generated() {
+        let handler = handler();
+        for fen in [
+            "4k3/8/8/8/8/4n3/4P3/4K3 w - - 0 1",
+            "4k3/8/8/8/4n3/8/4P3/4K3 w - - 0 1",
+            "4k3/4p3/4N3/8/8/8/8/4K3 b - - 0 1",
+            "4k3/4p3/8/4N3/8/8/8/4K3 b - - 0 1",
+        ] {
+            let p = pos(fen);
+            let moves = legal_move_strings(&handler, p);
+            assert!(!moves.iter().any(|m| m == "e2e4" || m == "e7e5"), "{}", fen);
+            for mv in handler.get_legal_moves(p) {
+                assert_eq!(p.play_move(mv).to_fen().split(' ').nth(3), Some("-"), "{}", fen);
+            }
+        }
+    }
+
+    #[test]
+    fn chess960_perft() {
+        let handler = ChessHandler::new(ChessParams {
+            chess960: true,
+            ..Default::default()
+        });
+        for (fen, expected) in [
+            (
+                "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+                [21, 528, 12189],
+            ),
+            (
+                "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+                [21, 807, 18002],
+            ),
+            (
+                "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+                [20, 479, 10471],
+            ),
+            (
+                "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+                [22, 593, 13440],
+            ),
+            (
+                "1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9",
+                [28, 1120, 31058],
+            ),
+        ] {
+            let p = handler.position_from_fen(fen).unwrap();
+            for (depth, &count) in expected.iter().enumerate() {
+                assert_eq!(
+                    perft(depth + 1, p, &handler),
+                    count,
+                    "{} at depth {}",
+                    fen,
+                    depth + 1
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn castling_rights_outside_chess960_need_standard_squares() {
+        let handler = handler();
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R b Kq - 0 1",
+        ] {
+            assert!(handler.position_from_fen(fen).is_some(), "{}", fen);
+        }
+        for fen in [
+            // The outermost kingside rook is on the g-file.
+            "4k3/8/8/8/8/8/8/R3K1R1 w K - 0 1",
+            "4k3/8/8/8/8/8/8/R3K2R w G - 0 1",
+            "r3k1r1/8/8/8/8/8/8/4K3 b k - 0 1",
+            "4k3/8/8/8/8/8/8/RK5R w KQ - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w Q - 0 1",
+        ] {
+            assert!(handler.position_from_fen(fen).is_none(), "{}", fen);
+        }
+        let chess960 = ChessHandler::new(ChessParams {
+            chess960: true,
+            ..Default::default()
+        });
+        assert!(chess960
+            .position_from_fen("4k3/8/8/8/8/8/8/R3K1R1 w K - 0 1")
+            .is_some());
+    }
+
+    fn full_eval_handler() -> ChessHandler {
+        ChessHandler::new(ChessParams {
+            mobility_weight: 4,
+            king_safety_weight: 8,
+            pawn_structure: true,
+            piece_square_tables: true,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn absolute_eval_of_symmetric_positions_is_zero() {
+        let handler = full_eval_handler();
+        let start = ChessPos::startpos(());
+        assert_eq!(handler.evaluate_absolute(start, 0, 0), Centipawn(0));
+        let p = play_uci(&handler, play_uci(&handler, start, "e2e4"), "e7e5");
+        assert_eq!(handler.evaluate_absolute(p, 0, 0), Centipawn(0));
+        // With Black to move, the absolute evaluation is the negated side-to-move evaluation.
+        let p = play_uci(&handler, start, "e2e4");
+        assert_eq!(
+            handler.evaluate_absolute(p, 0, 0),
+            -handler.evaluate(p, 0, 0)
+        );
+        let mirrored = pos("rnbqkbnr/pppp1ppp/8/4p3/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(
+            handler.evaluate_absolute(mirrored, 0, 0),
+            -handler.evaluate_absolute(p, 0, 0)
+        );
+    }
+
+    const MOVE_GENERATION_FENS: [&str; 5] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1Pq2/b4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    ];
+
+    #[test]
+    fn staged_moves_expand_to_legal_moves() {
+        let handler = handler();
+        for fen in MOVE_GENERATION_FENS {
+            let p = pos(fen);
+            let mut legal = handler.get_legal_moves(p).collect::<Vec<_>>();
+            legal.sort();
+            // A legal hash move and killer, an illegal killer and no killer at all.
+            let hash_move = legal.last().copied();
+            let killers = [legal.first().copied(), Some(make_move(0, 63, FLAG_NONE, 0))];
+            for (hash_move, killers) in [(hash_move, killers), (None, [None; 2])] {
+                let staged = handler
+                    .staged_moves(p, hash_move, killers)
+                    .collect::<Vec<_>>();
+                if let Some(mv) = hash_move {
+                    assert_eq!(staged[0], mv);
+                }
+                let mut sorted = staged.clone();
+                sorted.sort();
+                assert_eq!(sorted, legal, "{}", fen);
+            }
+        }
+    }
+
+    #[test]
+    fn exposed_king_scores_worse_than_mirror() {
+        let handler = ChessHandler::new(ChessParams {
+            king_safety_weight: 8,
+            ..Default::default()
+        });
+        // The rooks on the d-file and f-file attack four squares around the White king.
+        let exposed = pos("3rkr2/8/8/8/8/8/8/R3K2R w - - 0 1");
+        let mirrored = pos("r3k2r/8/8/8/8/8/8/3RKR2 w - - 0 1");
+        assert_eq!(handler.king_danger(exposed), (4, 0));
+        assert_eq!(handler.king_danger(mirrored), (0, 4));
+        assert_eq!(handler.evaluate(exposed, 0, 0), Centipawn(-32));
+        assert!(handler.evaluate(exposed, 0, 0) < handler.evaluate(mirrored, 0, 0));
+    }
+
+    #[test]
+    fn pawn_structure_terms() {
+        let handler = handler();
+        // The a-pawn is isolated, and neither side has a passed pawn.
+        let isolated = pos("4k3/pp4pp/8/8/8/8/P5PP/4K3 w - - 0 1");
+        assert_eq!(handler.pawn_structure_eval(isolated), -10);
+        // The doubled e-pawns are blocked by the Black pawns.
+        let doubled = pos("4k3/3ppp2/8/8/8/4P3/3PP3/4K3 w - - 0 1");
+        assert_eq!(handler.pawn_structure_eval(doubled), -15);
+        // The isolated passed e-pawn on the fifth rank against an isolated a-pawn
+        // that has passed on the second rank from Black's side.
+        let passed = pos("4k3/p7/8/4P3/8/8/8/4K3 w - - 0 1");
+        assert_eq!(handler.pawn_structure_eval(passed), 30);
+        let passed = pos("4k3/p7/8/4P3/8/8/8/4K3 b - - 0 1");
+        assert_eq!(handler.pawn_structure_eval(passed), -30);
+    }
+
+    fn uci_line(handler: &ChessHandler, start: ChessPos, moves: &str) -> Vec<Option<u64>> {
+        let mut p = start;
+        moves
+            .split_whitespace()
+            .map(|uci| {
+                let mv = handler.move_from_uci(p, uci).unwrap();
+                p = p.play_move(mv);
+                Some(mv)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn classify_mating_and_drawn_lines() {
+        let handler = handler();
+        let start = ChessPos::startpos(());
+        let mate = uci_line(&handler, start, "e2e4 e7e5 f1c4 b8c6 d1h5 g8f6 h5f7");
+        assert_eq!(handler.classify_line(start, &mate), LineOutcome::Checkmate);
+        assert_eq!(
+            handler.classify_line(start, &mate[..6]),
+            LineOutcome::Inconclusive
+        );
+        // The queen checks from e4 and e8 while the king shuffles between h7 and h8.
+        let checks = pos("8/6pk/8/8/8/8/8/K3Q3 w - - 0 1");
+        let perpetual = uci_line(
+            &handler,
+            checks,
+            "e1e4 h7h8 e4e8 h8h7 e8e4 h7h8 e4e8 h8h7 e8e4",
+        );
+        assert_eq!(
+            handler.classify_line(checks, &perpetual[..8]),
+            LineOutcome::Inconclusive
+        );
+        assert_eq!(
+            handler.classify_line(checks, &perpetual),
+            LineOutcome::Repetition
+        );
+        let stalemate = pos("7k/8/6Q1/8/8/8/8/K7 w - - 0 1");
+        let line = uci_line(&handler, stalemate, "g6f7");
+        assert_eq!(
+            handler.classify_line(stalemate, &line),
+            LineOutcome::Stalemate
+        );
+        let quiet = pos("4k3/8/8/8/8/8/8/R3K3 w - - 99 80");
+        let line = uci_line(&handler, quiet, "a1a2");
+        assert_eq!(handler.classify_line(quiet, &line), LineOutcome::FiftyMove);
+    }
+
+    #[test]
+    fn stalemate_scores_as_draw() {
+        let handler = handler();
+        let stalemate = pos("7k/5Q2/8/8/8/8/8/K7 b - - 0 1");
+        assert_eq!(handler.terminal_eval(stalemate, 0, 2), Centipawn(0));
+        assert_eq!(handler.evaluate(stalemate, 0, 2), Centipawn(0));
+        let (eval, _) = Searcher::new().alpha_beta::<ChessHandler, ChessPos, 2>(
+            &handler,
+            stalemate,
+            2,
+            ChessHandler::EVAL_MINIMUM,
+            ChessHandler::EVAL_MAXIMUM,
+        );
+        assert_eq!(eval, Centipawn(0));
+        let mated = pos("6Qk/8/6K1/8/8/8/8/8 b - - 0 1");
+        assert_eq!(handler.terminal_eval(mated, 0, 2), Centipawn::mated(2));
+        assert!(handler.terminal_eval(mated, 0, 2) < Centipawn(0));
+    }
+
+    #[test]
+    fn random_make_unmake_restores_position() {
+        use rand::seq::IteratorRandom;
+        use rand_chacha::rand_core::SeedableRng;
+        use rand_chacha::ChaChaRng;
+
+        let handler = handler();
+        let mut rng = ChaChaRng::seed_from_u64(5);
+        for fen in MOVE_GENERATION_FENS {
+            for _ in 0..20 {
+                let mut p = pos(fen);
+                let mut history = Vec::new();
+                for _ in 0..40 {
+                    let Some(mv) = handler.get_legal_moves(p).choose(&mut rng) else {
+                        break;
+                    };
+                    let before = p;
+                    let undo = p.make_move(mv);
+                    assert_eq!(p, before.play_move(mv));
+                    history.push((before, undo));
+                }
+                while let Some((before, undo)) = history.pop() {
+                    p.unmake_move(undo);
+                    assert_eq!(p, before);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn alpha_beta_inplace_matches_alpha_beta() {
+        let handler = handler();
+        let mut searcher = Searcher::new();
+        for fen in MOVE_GENERATION_FENS {
+            let mut p = pos(fen);
+            let (alpha, beta) = (ChessHandler::EVAL_MINIMUM, ChessHandler::EVAL_MAXIMUM);
+            let expected =
+                searcher.alpha_beta::<ChessHandler, ChessPos, 2>(&handler, p, 2, alpha, beta);
+            let result = searcher
+                .alpha_beta_inplace::<ChessHandler, ChessPos, 2>(&handler, &mut p, 2, alpha, beta);
+            assert_eq!(result, expected);
+            assert_eq!(p, pos(fen));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Record {
+            pos: ChessPos,
+            #[serde(with = "serde_move")]
+            mv: u64,
+        }
+        let handler = handler();
+        let p = pos("r3k2r/8/8/3pP3/8/8/8/R3K2R w Kq d6 0 1");
+        for mv in handler.get_legal_moves(p) {
+            let bytes = bincode::serialize(&Record { pos: p, mv }).unwrap();
+            let record: Record = bincode::deserialize(&bytes).unwrap();
+            assert_eq!(record.pos, p);
+            assert_eq!(record.mv, mv);
+        }
+        // A move whose square is off the board is rejected.
+        let bytes = bincode::serialize(&(p, 64u64, 0u64, 0u64, 0u64)).unwrap();
+        assert!(bincode::deserialize::<Record>(&bytes).is_err());
+        let bytes = bincode::serialize(&(p, 12u64, 28u64, 0u64, 0u64)).unwrap();
+        assert_eq!(
+            bincode::deserialize::<Record>(&bytes).unwrap().mv,
+            (28 << 6) | 12
+        );
+    }
+
+    #[test]
+    fn lines_from_black_to_move_verify() {
+        let handler = handler();
+        let (min, max) = (ChessHandler::EVAL_MINIMUM, ChessHandler::EVAL_MAXIMUM);
+        let italian = pos("r1bqkbnr/pppp1ppp/2n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R b KQkq - 3 3");
+        let mut searcher = Searcher::new();
+        let odd = searcher.alpha_beta::<ChessHandler, ChessPos, 3>(&handler, italian, 3, min, max);
+        assert_eq!(verify_line(&handler, italian, odd), Ok(()));
+        let even = searcher.alpha_beta::<ChessHandler, ChessPos, 2>(&handler, italian, 2, min, max);
+        assert_eq!(verify_line(&handler, italian, even), Ok(()));
+
+        // Black mates at once, which is a win from the perspective of Black at the root.
+        let back_rank = pos("r5k1/8/8/8/8/8/5PPP/6K1 b - - 0 1");
+        let mate = handler.move_from_uci(back_rank, "a8a1").unwrap();
+        assert_eq!(
+            eval_from_line(&handler, back_rank, [Some(mate), None]),
+            Centipawn::mate(1)
+        );
+        let result =
+            searcher.alpha_beta::<ChessHandler, ChessPos, 2>(&handler, back_rank, 2, min, max);
+        assert_eq!(result, (Centipawn::mate(1), [Some(mate), None]));
+        assert_eq!(verify_line(&handler, back_rank, result), Ok(()));
+    }
+
+    #[test]
+    fn contempt_decides_between_repetition_and_playing_on() {
+        // Black is a pawn up, and the White king returning to e1 repeats a position a third time.
+        let start = pos("4k3/p7/8/8/8/8/8/4K3 w - - 0 1");
+        let cycle = "e1d1 e8d8 d1e1 d8e8 e1d1 e8d8 d1e1 d8e8 e1d1 e8d8";
+        let moves = uci_line(&handler(), start, cycle)
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        let (min, max) = (ChessHandler::EVAL_MINIMUM, ChessHandler::EVAL_MAXIMUM);
+
+        let mut chosen = Vec::new();
+        for contempt in [0, 200] {
+            let handler = ChessHandler::new(ChessParams {
+                contempt,
+                ..ChessParams::default()
+            });
+            let mut searcher = Searcher::new();
+            let root = searcher.set_history(start, &moves);
+            let repeat = handler.move_from_uci(root, "d1e1").unwrap();
+            let (eval, line) = searcher
+                .alpha_beta_history::<ChessHandler, ChessPos, 1>(&handler, root, 1, min, max);
+            chosen.push((eval, line[0] == Some(repeat)));
+        }
+        // Without contempt the draw is preferred to being a pawn down,
+        // but a contempt of two pawns makes White play on instead.
+        assert_eq!(chosen, vec![(Centipawn(0), true), (Centipawn(-100), false)]);
+    }
+
+    #[test]
+    fn magic_attacks_match_slow_rays() {
+        use rand::Rng;
+        use rand_chacha::rand_core::SeedableRng;
+        use rand_chacha::ChaChaRng;
+
+        let handler = handler();
+        let mut rng = ChaChaRng::seed_from_u64(2122);
+        for square in 0..64 {
+            // Sparse and dense occupancies, which may include the attacking square itself.
+            for _ in 0..50 {
+                let blockers =
+                    (rng.gen::<u64>() & rng.gen::<u64>()) | (rng.gen::<u64>() & (1 << square));
+                let bishop = ChessHandler::bishop_blocked_attack_rays(square, blockers);
+                let rook = ChessHandler::rook_blocked_attack_rays(square, blockers);
+                assert_eq!(handler.bishop_attacks(square, blockers), bishop);
+                assert_eq!(handler.rook_attacks(square, blockers), rook);
+                assert_eq!(handler.queen_attacks(square, blockers), bishop | rook);
+                let dense = blockers | rng.gen::<u64>();
+                assert_eq!(
+                    handler.bishop_attacks(square, dense),
+                    ChessHandler::bishop_blocked_attack_rays(square, dense)
+                );
+                assert_eq!(
+                    handler.rook_attacks(square, dense),
+                    ChessHandler::rook_blocked_attack_rays(square, dense)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn is_quiet_detects_pending_tactics() {
+        let handler = handler();
+        assert!(handler.is_quiet(ChessPos::startpos(())));
+        // Locked pawn chains with nothing to capture.
+        assert!(handler.is_quiet(pos("4k3/8/8/2p1p3/2P1P3/8/8/4K3 w - - 0 1")));
+        // The only capture is by a pinned bishop, and so is illegal.
+        assert!(handler.is_quiet(pos("4k3/4r3/8/8/8/3p4/4B3/4K3 w - - 0 1")));
+        // A hanging queen.
+        assert!(!handler.is_quiet(pos("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1")));
+        // In check, with no capture available.
+        assert!(!handler.is_quiet(pos("4k3/4r3/8/8/8/8/8/4K3 w - - 0 1")));
+        // A pawn about to promote.
+        assert!(!handler.is_quiet(pos("4k3/P7/8/8/8/8/8/4K3 w - - 0 1")));
+    }
+
+    #[test]
+    fn centipawn_arithmetic_saturates() {
+        let (max, min) = (Centipawn(i32::MAX), Centipawn(i32::MIN));
+        assert_eq!(max + Centipawn(1), max);
+        assert_eq!(min - Centipawn(1), min);
+        assert_eq!(min + min, min);
+        assert_eq!(-min, max);
+        assert_eq!(-max, Centipawn(-i32::MAX));
+        let mut eval = max;
+        eval += Centipawn(100);
+        assert_eq!(eval, max);
+        eval -= max;
+        eval -= max;
+        assert_eq!(eval, Centipawn(-i32::MAX));
+        // Window adjustments at the limits of the evaluation stay ordered.
+        let (lowest, highest) = (ChessHandler::EVAL_MINIMUM, ChessHandler::EVAL_MAXIMUM);
+        assert!(lowest - ChessHandler::EVAL_EPSILON < lowest);
+        assert!(highest + ChessHandler::EVAL_EPSILON > highest);
+        assert!(min - ChessHandler::EVAL_EPSILON <= lowest);
+    }
+
+    #[test]
+    fn mate_scores_round_trip() {
+        for plies in [0, 1, 2, 7, 100, Centipawn::MAX_MATE_PLIES as usize] {
+            let (mate, mated) = (Centipawn::mate(plies), Centipawn::mated(plies));
+            assert!(mate.is_mate() && mated.is_mate());
+            assert_eq!(mate.mate_in(), Some(plies as i32));
+            assert_eq!(mated.mate_in(), Some(-(plies as i32)));
+            assert_eq!(-mate, mated);
+            assert!(mated < Centipawn(0) && Centipawn(0) < mate);
+        }
+        // Quicker mates are better for the side delivering them.
+        assert!(Centipawn::mate(1) > Centipawn::mate(3));
+        assert!(Centipawn::mated(1) < Centipawn::mated(3));
+        for eval in [
+            0,
+            900,
+            -39000,
+            Centipawn::MATE_VALUE - Centipawn::MAX_MATE_PLIES - 1,
+        ] {
+            assert!(!Centipawn(eval).is_mate());
+            assert_eq!(Centipawn(eval).mate_in(), None);
+        }
+        assert!(!ChessHandler::EVAL_MAXIMUM.is_mate());
+        assert!(!ChessHandler::EVAL_MINIMUM.is_mate());
+        assert_eq!(Centipawn::mate(3).to_string(), "#3");
+        assert_eq!(Centipawn::mated(2).to_string(), "#-2");
+        assert_eq!(Centipawn(-45).to_string(), "-45");
+    }
+
+    #[test]
+    fn format_move_names_squares_from_whites_side() {
+        let handler = handler();
+        let start = ChessPos::startpos(());
+        let e4 = handler.move_from_uci(start, "e2e4").unwrap();
+        assert_eq!(handler.format_move(start, e4), handler.move_string(e4, 0));
+        assert_eq!(handler.format_move(start, e4), "e2e4");
+        // The same packed move played by Black is the mirrored move.
+        let after = start.play_move(e4);
+        let e5 = handler.move_from_uci(after, "e7e5").unwrap();
+        assert_eq!(e5, e4);
+        assert_eq!(handler.format_move(after, e5), handler.move_string(e5, 1));
+        assert_eq!(handler.format_move(after, e5), "e7e5");
+        let promotion = pos("8/P6k/8/8/8/8/8/K7 w - - 0 1");
+        let queen = handler.move_from_uci(promotion, "a7a8q").unwrap();
+        assert_eq!(handler.format_move(promotion, queen), "a7a8q");
+    }
+
+    // The bitboard of the squares named in `names`, separated by spaces.
+    fn squares(names: &str) -> u64 {
+        names.split_whitespace().fold(0, |bb, name| {
+            let name = name.as_bytes();
+            bb | 1 << ((name[1] - b'1') * 8 + (name[0] - b'a'))
         })
     }
 
-    fn evaluate(&self, _pos: ChessPos, _depth: usize, _max_depth: usize) -> Self::Eval {
-        todo!();
+    #[test]
+    fn unblocked_rays_exclude_edges() {
+        assert_eq!(
+            ChessHandler::bishop_unblocked_attack_rays(27),
+            squares("b2 c3 e5 f6 g7 b6 c5 e3 f2")
+        );
+        assert_eq!(
+            ChessHandler::bishop_unblocked_attack_rays(0),
+            squares("b2 c3 d4 e5 f6 g7")
+        );
+        assert_eq!(
+            ChessHandler::rook_unblocked_attack_rays(27),
+            squares("d2 d3 d5 d6 d7 b4 c4 e4 f4 g4")
+        );
+        assert_eq!(
+            ChessHandler::rook_unblocked_attack_rays(0),
+            squares("a2 a3 a4 a5 a6 a7 b1 c1 d1 e1 f1 g1")
+        );
+    }
+
+    #[test]
+    fn blocked_rays_stop_at_blockers() {
+        // Rays from d4, including the square of the first blocker on each.
+        assert_eq!(
+            ChessHandler::rook_blocked_attack_rays(27, squares("d6")),
+            squares("d1 d2 d3 d5 d6 a4 b4 c4 e4 f4 g4 h4")
+        );
+        assert_eq!(
+            ChessHandler::rook_blocked_attack_rays(27, squares("d6 d7 b4 h1")),
+            squares("d1 d2 d3 d5 d6 b4 c4 e4 f4 g4 h4")
+        );
+        assert_eq!(
+            ChessHandler::bishop_blocked_attack_rays(27, 0),
+            squares("a1 b2 c3 e5 f6 g7 h8 a7 b6 c5 e3 f2 g1")
+        );
+        assert_eq!(
+            ChessHandler::bishop_blocked_attack_rays(27, squares("c3 f6 d5")),
+            squares("c3 e5 f6 a7 b6 c5 e3 f2 g1")
+        );
+    }
+
+    #[test]
+    fn flips_mirror_ranks() {
+        assert_eq!(flip_bb(squares("a1")), squares("a8"));
+        assert_eq!(flip_bb(squares("h2 c5")), squares("h7 c4"));
+        assert_eq!(flip_bb(RANK_1), RANK_1 << 56);
+        assert_eq!(flip_bb(FILE_A), FILE_A);
+        assert_eq!(flip_bb(flip_bb(0x0123456789abcdef)), 0x0123456789abcdef);
+        assert_eq!(flip_square(0), 56);
+        assert_eq!(flip_square(12), 52);
+        assert!((0..64).all(|sq| flip_bb(1 << sq) == 1 << flip_square(sq)));
+    }
+
+    #[test]
+    fn bit_permutations_are_every_subset() {
+        assert_eq!(ChessHandler::bit_permutations(0), [0]);
+        assert_eq!(ChessHandler::bit_permutations(0b101), [0, 1, 4, 5]);
+        let mask = ChessHandler::rook_unblocked_attack_rays(27);
+        let mut subsets = ChessHandler::bit_permutations(mask);
+        assert_eq!(subsets.len(), 1 << 10);
+        assert!(subsets.iter().all(|&subset| subset & !mask == 0));
+        subsets.sort();
+        subsets.dedup();
+        assert_eq!(subsets.len(), 1 << 10);
+    }
+
+    #[test]
+    fn format_bitboard_draws_rank_8_first() {
+        let drawn = format_bitboard(squares("a1 h8 d4"));
+        let lines = drawn.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(lines[0], ".......1");
+        assert_eq!(lines[4], "...1....");
+        assert_eq!(lines[7], "1.......");
+        assert!(handler()
+            .debug_bitboards(ChessPos::startpos(()))
+            .contains("pawn:\n........\n11111111\n"));
+    }
+
+    #[test]
+    fn flipped_positions_evaluate_with_opposite_sign() {
+        // Flipping a position hands the move to the other side's pieces,
+        // so every term of the evaluation changes sign.
+        for handler in [handler(), full_eval_handler()] {
+            for fen in MOVE_GENERATION_FENS.iter().chain(&[
+                "4k3/pp4pp/8/8/8/8/P5PP/4K3 w - - 0 1",
+                "3rkr2/8/8/8/8/8/8/R3K2R b - - 0 1",
+                "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2",
+            ]) {
+                let p = pos(fen);
+                let eval = handler.evaluate(p, 0, 0);
+                assert_eq!(handler.evaluate(p.flip_position(), 0, 0), -eval, "{}", fen);
+                assert_eq!(
+                    handler.evaluate_absolute(p.flip_position(), 0, 0),
+                    -handler.evaluate_absolute(p, 0, 0),
+                    "{}",
+                    fen
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn incremental_key_matches_full_key() {
+        // Checks every line of `depth` plies from `p`, whose key is `key`.
+        fn check(handler: &ChessHandler, p: ChessPos, key: u64, depth: usize) {
+            assert_eq!(key, p.zobrist_key());
+            if depth > 0 {
+                for mv in handler.get_legal_moves(p) {
+                    let (next, next_key) = p.play_move_with_key(key, mv);
+                    assert_eq!(next, p.play_move(mv));
+                    check(handler, next, next_key, depth - 1);
+                }
+            }
+        }
+
+        let handler = handler();
+        // Castling, en passant and promotions with and without captures all arise within three plies.
+        for fen in MOVE_GENERATION_FENS.iter().chain(&[
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "r3k2r/1P6/8/8/8/8/6p1/R3K2R b KQkq - 0 1",
+        ]) {
+            let p = pos(fen);
+            check(&handler, p, p.zobrist_key(), 3);
+        }
+        // The same game state reached through a different move order has the same key.
+        let start = ChessPos::startpos(());
+        let lines = [["g1f3", "g8f6", "b1c3"], ["b1c3", "g8f6", "g1f3"]];
+        let keys = lines.map(|line| {
+            line.iter()
+                .fold((start, start.zobrist_key()), |(p, key), uci| {
+                    p.play_move_with_key(key, handler.move_from_uci(p, uci).unwrap())
+                })
+        });
+        assert_eq!(keys[0].1, keys[1].1);
+    }
+
+    #[test]
+    fn staged_captures_come_before_quiet_moves() {
+        let handler = handler();
+        // An en passant capture is staged with the captures, although its destination is empty.
+        let en_passant = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+        for fen in MOVE_GENERATION_FENS.into_iter().chain([en_passant]) {
+            let p = pos(fen);
+            let mut staged = handler.get_legal_moves_staged(p);
+            let mut moves = Vec::new();
+            while let Some(mv) = staged.next() {
+                moves.push((mv, staged.stage()));
+            }
+            let captures = handler.get_capture_moves(p).len();
+            assert!(moves[..captures]
+                .iter()
+                .all(|&(mv, stage)| stage == MoveStage::Captures && handler.is_capture(p, mv)));
+            assert!(moves[captures..]
+                .iter()
+                .all(|&(mv, stage)| stage == MoveStage::Quiets && !handler.is_capture(p, mv)));
+            assert!(moves[..captures]
+                .windows(2)
+                .all(|pair| handler.mvv_lva(p, pair[0].0) >= handler.mvv_lva(p, pair[1].0)));
+            let mut staged = moves.into_iter().map(|(mv, _)| mv).collect::<Vec<_>>();
+            let mut legal = handler.get_legal_moves(p).collect::<Vec<_>>();
+            staged.sort();
+            legal.sort();
+            assert_eq!(staged, legal, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn mvv_lva_prefers_valuable_victims_and_cheap_attackers() {
+        let handler = handler();
+        // The start position has no captures, so every move scores `0`.
+        let start = ChessPos::startpos(());
+        assert!(handler.get_capture_moves(start).is_empty());
+        assert!(handler
+            .get_legal_moves(start)
+            .all(|mv| handler.mvv_lva(start, mv) == 0));
+
+        // With a single victim, the cheaper attacker is preferred.
+        let p = pos("4k3/8/8/3q4/2P5/8/3Q4/4K3 w - - 0 1");
+        let score = |uci| handler.mvv_lva(p, handler.move_from_uci(p, uci).unwrap());
+        assert!(score("c4d5") > score("d2d5"));
+        assert!(score("d2d5") > 0);
+
+        let p = pos("4k3/8/8/1r1q4/2P1Q3/8/8/4K3 w - - 0 1");
+        let score = |uci| handler.mvv_lva(p, handler.move_from_uci(p, uci).unwrap());
+        // A pawn taking the queen, then the queen taking the queen, then the pawn taking the rook.
+        assert!(score("c4d5") > score("e4d5"));
+        assert!(score("e4d5") > score("c4b5"));
+        assert!(score("c4b5") > 0);
+        assert_eq!(score("e4e5"), 0);
+        // En passant captures a pawn, although the destination square is empty.
+        let p = pos("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+        let en_passant = handler.move_from_uci(p, "e5d6").unwrap();
+        let push = handler.move_from_uci(p, "e5e6").unwrap();
+        assert!(handler.mvv_lva(p, en_passant) > 0);
+        assert_eq!(handler.mvv_lva(p, push), 0);
+    }
+
+    #[test]
+    fn uci_strings_round_trip() {
+        let handler = handler();
+        for fen in MOVE_GENERATION_FENS {
+            // Each position with White to move, and a position after it with Black to move.
+            let p = pos(fen);
+            let reply = p.play_move(handler.get_legal_moves(p).next().unwrap());
+            for (p, side) in [(p, 0), (reply, 1)] {
+                for mv in handler.get_legal_moves(p) {
+                    let uci = handler.move_string(mv, side);
+                    assert_eq!(handler.move_from_uci(p, &uci), Some(mv), "{} {}", fen, uci);
+                }
+            }
+        }
+
+        // Castling as the move of the king, promotion, and en passant with or without `ep`.
+        let castling = pos("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        let castle = handler.move_from_uci(castling, "e1g1").unwrap();
+        assert_eq!((castle >> 12) & 0x3, FLAG_CASTLE);
+        let black = pos("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1");
+        let castle = handler.move_from_uci(black, "e8c8").unwrap();
+        assert_eq!(handler.move_string(castle, 1), "e8c8");
+        let promotion = pos("8/4P3/8/8/8/8/k7/4K3 w - - 0 1");
+        let knight = handler.move_from_uci(promotion, "e7e8n").unwrap();
+        assert_eq!(handler.move_string(knight, 0), "e7e8n");
+        assert_ne!(handler.move_from_uci(promotion, "e7e8q"), Some(knight));
+        let en_passant = pos("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+        let capture = handler.move_from_uci(en_passant, "e5d6ep").unwrap();
+        assert_eq!(handler.move_from_uci(en_passant, "e5d6"), Some(capture));
+
+        // Illegal or malformed moves are not found.
+        let start = ChessPos::startpos(());
+        for uci in ["e2e5", "e7e5", "e1e2", "", "e2", "z9z9"] {
+            assert_eq!(handler.move_from_uci(start, uci), None, "{}", uci);
+        }
+    }
+
+    #[test]
+    fn third_occurrence_is_a_draw() {
+        // Black is a pawn up, so White only scores a draw by repeating a position a third time.
+        let handler = handler();
+        let start = pos("4k3/p7/8/8/8/8/8/4K3 w - - 0 1");
+        let (min, max) = (ChessHandler::EVAL_MINIMUM, ChessHandler::EVAL_MAXIMUM);
+        assert!(!handler.is_repetition_draw(start, 2));
+        assert!(handler.is_repetition_draw(start, 3));
+
+        // After one shuffle of the kings, returning to e1 reaches the start position a second time,
+        // and after two shuffles a third time.
+        let mut evals = Vec::new();
+        for cycle in [
+            "e1d1 e8d8 d1e1 d8e8 e1d1 e8d8",
+            "e1d1 e8d8 d1e1 d8e8 e1d1 e8d8 d1e1 d8e8 e1d1 e8d8",
+        ] {
+            let moves = uci_line(&handler, start, cycle)
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+            let mut searcher = Searcher::new();
+            let root = searcher.set_history(start, &moves);
+            let (eval, line) = searcher
+                .alpha_beta_history::<ChessHandler, ChessPos, 1>(&handler, root, 1, min, max);
+            let repeat = handler.move_from_uci(root, "d1e1").unwrap();
+            evals.push((eval, line[0] == Some(repeat)));
+
+            // Forgetting the game makes the repetition invisible again.
+            searcher.clear_history();
+            let (eval, _) = searcher
+                .alpha_beta_history::<ChessHandler, ChessPos, 1>(&handler, root, 1, min, max);
+            assert_eq!(eval, Centipawn(-100));
+        }
+        assert_eq!(evals[0].0, Centipawn(-100));
+        assert_eq!(evals[1], (Centipawn(0), true));
+    }
+
+    #[test]
+    fn san_describes_moves_unambiguously() {
+        let handler = handler();
+        let start = ChessPos::startpos(());
+        let scholars_mate = uci_line(&handler, start, "e2e4 e7e5 f1c4 b8c6 d1h5 g8f6 h5f7");
+        assert_eq!(
+            handler.line_san(start, &scholars_mate),
+            ["e4", "e5", "Bc4", "Nc6", "Qh5", "Nf6", "Qxf7#"]
+        );
+
+        // Disambiguation by file, then by rank, then by both, and promotion giving check.
+        for (fen, uci, san) in [
+            ("6k1/8/8/8/8/8/8/1N3N1K w - - 0 1", "b1d2", "Nbd2"),
+            ("6k1/R7/8/8/8/8/8/R6K w - - 0 1", "a1a4", "R1a4"),
+            ("6k1/8/8/8/8/Q7/8/Q1Q4K w - - 0 1", "a1c3", "Qa1c3"),
+            ("k7/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7e8q", "e8=Q+"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1", "O-O"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8", "O-O-O"),
+        ] {
+            let p = pos(fen);
+            let mv = handler.move_from_uci(p, uci).unwrap();
+            assert_eq!(handler.move_san(p, mv), san, "{}", fen);
+            assert_eq!(handler.move_from_san(p, san), Some(mv), "{}", fen);
+        }
+        let castling = pos("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        assert_eq!(
+            handler.move_from_san(castling, "0-0"),
+            handler.move_from_uci(castling, "e1g1")
+        );
+        // Ambiguous or illegal moves are not found.
+        let knights = pos("6k1/8/8/8/8/8/8/1N3N1K w - - 0 1");
+        assert_eq!(handler.move_from_san(knights, "Nd2"), None);
+        assert_eq!(handler.move_from_san(start, "e5"), None);
+
+        // Every legal move round-trips, with White and with Black to move.
+        for fen in MOVE_GENERATION_FENS {
+            let p = pos(fen);
+            let reply = p.play_move(handler.get_legal_moves(p).next().unwrap());
+            for p in [p, reply] {
+                for mv in handler.get_legal_moves(p) {
+                    let san = handler.move_san(p, mv);
+                    assert_eq!(handler.move_from_san(p, &san), Some(mv), "{} {}", fen, san);
+                }
+            }
+        }
     }
 }
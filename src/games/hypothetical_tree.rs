@@ -10,7 +10,7 @@ use auto_enums::auto_enum;
 // which can have constant or non-constant fanout at each node.
 // The only restrictions are that, if there is a known upper bound to fanout,
 // then there will be no transpositions.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct HypTreePos {
     // The number of child nodes this node will spawn.
     pub fanout: usize,
@@ -38,12 +38,22 @@ impl GamePosition for HypTreePos {
     }
 }
 
+// Every node of the tree is numbered uniquely, so the number itself serves as the key.
+impl ZobristKey for HypTreePos {
+    fn key(&self) -> u64 {
+        self.node as u64
+    }
+}
+
 #[derive(Debug)]
 pub struct UnordIndHypTreeHandler {
     width: usize,
     // The `leaf_start` variable is an exclusive lower bound for leaf nodes.
     leaf_start: usize,
     node_values: Vec<i64>,
+    // The values of the interior nodes marked terminal, indexed by node number,
+    // from the perspective of the root player. Empty when there are no early terminal nodes.
+    terminal_values: Vec<Option<i64>>,
 }
 
 pub struct HypTreeParams {
@@ -53,6 +63,55 @@ pub struct HypTreeParams {
     pub width: usize,
     // The random seed to supply the handler to generate the random node values.
     pub seed: u64,
+    // The percentage of interior nodes, other than the root, that are terminal,
+    // from `0` to `100`. A terminal node has no legal moves and is scored by a stored value,
+    // simulating games which can end before the maximum depth, such as Chess and Ultimate Tic-Tac-Toe.
+    // At `0` every line reaches the maximum depth, and the leaf values are unaffected by this setting.
+    pub early_terminal: usize,
+}
+
+impl UnordIndHypTreeHandler {
+    // The values of the leaf nodes from left to right, from the perspective of the root player.
+    // These are a permutation of `1..=width^depth` determined entirely by the seed,
+    // so that benchmarks over the same seeds always search the same trees.
+    // For reference, a depth of 2, width of 3 and seed of 314159 gives `[3, 1, 2, 4, 6, 8, 5, 7, 9]`.
+    pub fn leaf_values(&self) -> &[i64] {
+        &self.node_values
+    }
+
+    // The number of leaf nodes in the full game tree, `width^depth`,
+    // against which the leaves evaluated by a search can be compared.
+    pub fn leaf_total(&self) -> u128 {
+        self.node_values.len() as u128
+    }
+
+    // The number of nodes in the full game tree, including the root and the leaves.
+    // Interior nodes are numbered from `0` to `leaf_start`.
+    pub fn node_total(&self) -> u128 {
+        (self.leaf_start + 1) as u128 + self.leaf_total()
+    }
+
+    // Whether a node has no legal moves, either as a leaf at the maximum depth
+    // or as an interior node marked terminal through `HypTreeParams::early_terminal`.
+    pub fn is_terminal(&self, pos: HypTreePos) -> bool {
+        pos.node > self.leaf_start || self.terminal_value(pos).is_some()
+    }
+
+    // The number of plies from the root to a node.
+    fn ply(&self, node: usize) -> usize {
+        // The root is node 0, and the children of node `n` are `n * width + 1` to `n * width + width`.
+        let mut node = node;
+        let mut ply = 0;
+        while node > 0 {
+            node = (node - 1) / self.width;
+            ply += 1;
+        }
+        ply
+    }
+
+    fn terminal_value(&self, pos: HypTreePos) -> Option<i64> {
+        self.terminal_values.get(pos.node).copied().flatten()
+    }
 }
 
 impl GameHandler<HypTreePos> for UnordIndHypTreeHandler {
@@ -64,7 +123,12 @@ impl GameHandler<HypTreePos> for UnordIndHypTreeHandler {
     const EVAL_EPSILON: i64 = 1;
 
     fn new(params: HypTreeParams) -> Self {
-        let HypTreeParams { depth, width, seed } = params;
+        let HypTreeParams {
+            depth,
+            width,
+            seed,
+            early_terminal,
+        } = params;
         let mut rng = ChaChaRng::seed_from_u64(seed);
         // With a depth of `d` and a width/fanout of `w`, there are `w^d` leaf nodes.
         // All leaf nodes have a node value greater than or equal to the left-most leaf node.
@@ -78,10 +142,24 @@ impl GameHandler<HypTreePos> for UnordIndHypTreeHandler {
             let j = rng.gen_range(0..=i);
             (node_values[i], node_values[j]) = (node_values[j], node_values[i]);
         }
+        // Terminal nodes are drawn after the leaf values so that they do not change the leaf values,
+        // and their values are drawn from the same range as the leaf values.
+        // Nodes below a terminal node may also be marked, but can never be reached.
+        let terminal_values = if early_terminal == 0 {
+            Vec::new()
+        } else {
+            (0..=leaf_start)
+                .map(|node| {
+                    (node != 0 && rng.gen_range(0..100) < early_terminal)
+                        .then(|| rng.gen_range(1..=node_values.len() as i64))
+                })
+                .collect()
+        };
         Self {
             width,
             leaf_start,
             node_values,
+            terminal_values,
         }
     }
 
@@ -90,7 +168,7 @@ impl GameHandler<HypTreePos> for UnordIndHypTreeHandler {
         &self,
         pos: HypTreePos,
     ) -> impl Iterator<Item = <HypTreePos as GamePosition>::Move> {
-        if pos.node > self.leaf_start {
+        if self.is_terminal(pos) {
             std::iter::empty()
         } else {
             (1..=self.width).map(|shift| (self.width, shift))
@@ -98,16 +176,118 @@ impl GameHandler<HypTreePos> for UnordIndHypTreeHandler {
     }
 
     fn evaluate(&self, pos: HypTreePos, depth: usize, max_depth: usize) -> Self::Eval {
+        // `evaluate` should only be called on leaf and terminal nodes,
+        // which would otherwise indicate a search depth that does not match the tree depth.
+        debug_assert!(
+            self.is_terminal(pos),
+            "evaluate called on non-leaf node {}",
+            pos.node
+        );
+        let toggle = if ((max_depth - depth) & 1) == 0 {
+            1
+        } else {
+            -1
+        };
         if pos.node > self.leaf_start {
-            let toggle = if ((max_depth - depth) & 1) == 0 {
-                1
-            } else {
-                -1
-            };
             self.node_values[pos.node - self.leaf_start - 1] * toggle
+        } else if let Some(value) = self.terminal_value(pos) {
+            value * toggle
         } else {
             // `evaluate` should not be called on non-leaf nodes.
             0
         }
     }
+
+    fn plies_to_leaves(&self, pos: HypTreePos) -> Option<usize> {
+        Some(self.ply(self.leaf_start + 1) - self.ply(pos.node))
+    }
+
+    fn current_player(&self, pos: HypTreePos) -> usize {
+        self.ply(pos.node) & 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::Searcher;
+
+    fn handler(depth: usize, width: usize, seed: u64) -> UnordIndHypTreeHandler {
+        UnordIndHypTreeHandler::new(HypTreeParams {
+            depth,
+            width,
+            seed,
+            early_terminal: 0,
+        })
+    }
+
+    #[test]
+    fn leaf_values_are_fixed_by_seed() {
+        assert_eq!(
+            handler(2, 3, 314159).leaf_values(),
+            [3, 1, 2, 4, 6, 8, 5, 7, 9]
+        );
+        assert_eq!(
+            handler(3, 4, 42).leaf_values(),
+            handler(3, 4, 42).leaf_values()
+        );
+        assert_ne!(
+            handler(3, 4, 42).leaf_values(),
+            handler(3, 4, 43).leaf_values()
+        );
+    }
+
+    #[test]
+    fn leaf_values_are_a_permutation() {
+        let mut values = handler(3, 4, 42).leaf_values().to_vec();
+        values.sort();
+        assert_eq!(values, (1..=64).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn early_terminal_nodes_stop_expansion_and_count_as_leaves() {
+        let params = |early_terminal| HypTreeParams {
+            depth: 4,
+            width: 3,
+            seed: 2137,
+            early_terminal,
+        };
+        let full_depth = handler(4, 3, 2137);
+        let handler = UnordIndHypTreeHandler::new(params(30));
+        assert_eq!(handler.leaf_values(), full_depth.leaf_values());
+
+        // Walks the tree, counting the terminal nodes reached and those above the maximum depth.
+        let (mut terminal, mut early) = (0, 0);
+        let mut stack = vec![(HypTreePos::startpos(3), 0)];
+        while let Some((pos, ply)) = stack.pop() {
+            if handler.is_terminal(pos) {
+                assert_eq!(handler.get_legal_moves(pos).count(), 0);
+                terminal += 1;
+                if ply < 4 {
+                    early += 1;
+                }
+            } else {
+                assert!(ply < 4);
+                stack.extend(
+                    handler
+                        .get_legal_moves(pos)
+                        .map(|mv| (pos.play_move(mv), ply + 1)),
+                );
+            }
+        }
+        assert!(early > 0);
+
+        // A search without pruning evaluates every terminal node reached as a leaf.
+        let mut searcher = Searcher::new();
+        searcher.minimax_explicit::<UnordIndHypTreeHandler, HypTreePos, 4>(
+            &handler,
+            HypTreePos::startpos(3),
+            4,
+        );
+        assert_eq!(searcher.get_leaf_count(), terminal);
+        assert!(searcher.get_leaf_count() < handler.leaf_total());
+
+        let again = UnordIndHypTreeHandler::new(params(30));
+        assert_eq!(again.terminal_values, handler.terminal_values);
+    }
 }
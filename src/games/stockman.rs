@@ -2,7 +2,7 @@ use crate::prelude::*;
 
 use auto_enums::auto_enum;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct StockmanPos {
     pub node: usize,
 }
@@ -35,6 +35,24 @@ impl GamePosition for StockmanPos {
     }
 }
 
+impl ZobristKey for StockmanPos {
+    fn key(&self) -> u64 {
+        self.node as u64
+    }
+}
+
+impl StockmanHandler {
+    // The number of leaf nodes in the game tree, which has a depth of 4 and a width of 2.
+    pub fn leaf_total(&self) -> u128 {
+        16
+    }
+
+    // The number of nodes in the game tree, numbered from `1` to `31`.
+    pub fn node_total(&self) -> u128 {
+        31
+    }
+}
+
 impl GameHandler<StockmanPos> for StockmanHandler {
     type Eval = i32;
     type Params = ();
@@ -61,6 +79,13 @@ impl GameHandler<StockmanPos> for StockmanHandler {
     }
 
     fn evaluate(&self, pos: StockmanPos, _depth: usize, _max_depth: usize) -> Self::Eval {
+        // `evaluate` should only be called on leaf nodes,
+        // which would otherwise indicate a search depth less than the tree depth of 4.
+        debug_assert!(
+            pos.node > 15,
+            "evaluate called on non-leaf node {}",
+            pos.node
+        );
         match pos.node {
             16 => 30,
             17 => 54,
@@ -81,4 +106,32 @@ impl GameHandler<StockmanPos> for StockmanHandler {
             _ => i32::MAX,
         }
     }
+
+    fn plies_to_leaves(&self, pos: StockmanPos) -> Option<usize> {
+        Some(4 - pos.node.ilog2() as usize)
+    }
+
+    fn current_player(&self, pos: StockmanPos) -> usize {
+        // The root is node 1, and each ply doubles the node number.
+        (pos.node.ilog2() & 1) as usize
+    }
+}
+
+impl BoundedEvaluation<StockmanPos> for StockmanHandler {
+    // Internal nodes have no static evaluation, so they are only bounded by the extreme values.
+    fn evaluate_optimistic(&self, pos: StockmanPos, depth: usize, max_depth: usize) -> Self::Eval {
+        if pos.node > 15 {
+            self.evaluate(pos, depth, max_depth)
+        } else {
+            Self::EVAL_MAXIMUM
+        }
+    }
+
+    fn evaluate_pessimistic(&self, pos: StockmanPos, depth: usize, max_depth: usize) -> Self::Eval {
+        if pos.node > 15 {
+            self.evaluate(pos, depth, max_depth)
+        } else {
+            Self::EVAL_MINIMUM
+        }
+    }
 }
@@ -0,0 +1,171 @@
+use crate::prelude::*;
+
+use rand::Rng;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaChaRng;
+
+use auto_enums::auto_enum;
+
+// A node in a hypothetical game tree with transpositions, which is really a directed acyclic graph.
+// Every non-leaf node has `width` children, but each ply only has a fraction of the nodes
+// that a tree would have, so that different lines reach the same node.
+// Nodes are identified by their ply and their index among the nodes of that ply,
+// so two lines reaching the same node reach identical game states.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TranspTreePos {
+    pub width: usize,
+    // The percentage of nodes at each ply that are merged into other nodes.
+    pub density: usize,
+    pub ply: usize,
+    pub node: usize,
+}
+
+impl TranspTreePos {
+    // The number of distinct nodes at a ply, where each ply has `density` percent fewer nodes
+    // than `width` times the previous ply, but at least `width` nodes
+    // so that a node never has two moves to the same child.
+    pub fn level_size(width: usize, density: usize, ply: usize) -> usize {
+        let mut size = 1;
+        for _ in 0..ply {
+            let unmerged = size * width;
+            let merged = (unmerged * (100 - density.min(100)) + 50) / 100;
+            size = merged.clamp(width.min(unmerged), unmerged);
+        }
+        size
+    }
+}
+
+impl GamePosition for TranspTreePos {
+    // The index of the child to move to, from `0` to `width - 1`.
+    type Move = usize;
+    // The width and transposition density of the tree.
+    type Params = (usize, usize);
+
+    fn startpos((width, density): (usize, usize)) -> Self {
+        Self {
+            width,
+            density,
+            ply: 0,
+            node: 0,
+        }
+    }
+
+    // The children of a node are consecutive nodes of the next ply, wrapping around.
+    fn play_move(&self, mv: Self::Move) -> Self {
+        let size = Self::level_size(self.width, self.density, self.ply + 1);
+        Self {
+            ply: self.ply + 1,
+            node: (self.node * self.width + mv) % size,
+            ..*self
+        }
+    }
+}
+
+// Node indices are only unique within a ply, so the ply is kept in the upper bits of the key.
+impl ZobristKey for TranspTreePos {
+    fn key(&self) -> u64 {
+        ((self.ply as u64) << 48) ^ self.node as u64
+    }
+}
+
+pub struct TranspTreeHandler {
+    depth: usize,
+    width: usize,
+    // The values of the nodes at the final ply, from the perspective of the root player.
+    leaf_values: Vec<i64>,
+}
+
+pub struct TranspTreeParams {
+    // The depth of every leaf node.
+    pub depth: usize,
+    // The number of moves at every non-leaf node.
+    pub width: usize,
+    // The percentage of nodes at each ply that are merged into other nodes, from `0` to `100`.
+    // At `0` the game tree has no transpositions and is an ordinary uniform tree,
+    // and at higher densities more lines share the same nodes.
+    // This must match the density given to `TranspTreePos::startpos`.
+    pub density: usize,
+    // The random seed to supply the handler to generate the random leaf values.
+    pub seed: u64,
+}
+
+impl TranspTreeHandler {
+    // The values of the leaf nodes in order of their index, from the perspective of the root player.
+    // These are a permutation of `1..=n`, where `n` is the number of leaf nodes,
+    // determined entirely by the seed.
+    pub fn leaf_values(&self) -> &[i64] {
+        &self.leaf_values
+    }
+}
+
+impl GameHandler<TranspTreePos> for TranspTreeHandler {
+    type Eval = i64;
+    type Params = TranspTreeParams;
+
+    const EVAL_MINIMUM: i64 = -i64::MAX;
+    const EVAL_MAXIMUM: i64 = i64::MAX;
+    const EVAL_EPSILON: i64 = 1;
+
+    fn new(params: TranspTreeParams) -> Self {
+        let TranspTreeParams {
+            depth,
+            width,
+            density,
+            seed,
+        } = params;
+        let mut rng = ChaChaRng::seed_from_u64(seed);
+        let leaves = TranspTreePos::level_size(width, density, depth);
+        let mut leaf_values: Vec<i64> = (1..=leaves as i64).collect();
+        for i in (1..leaf_values.len()).rev() {
+            let j = rng.gen_range(0..=i);
+            leaf_values.swap(i, j);
+        }
+        Self {
+            depth,
+            width,
+            leaf_values,
+        }
+    }
+
+    #[auto_enum(Iterator)]
+    fn get_legal_moves(
+        &self,
+        pos: TranspTreePos,
+    ) -> impl Iterator<Item = <TranspTreePos as GamePosition>::Move> {
+        if pos.ply >= self.depth {
+            std::iter::empty()
+        } else {
+            0..self.width
+        }
+    }
+
+    fn evaluate(&self, pos: TranspTreePos, depth: usize, max_depth: usize) -> Self::Eval {
+        // `evaluate` should only be called on leaf nodes,
+        // which would otherwise indicate a search depth that does not match the tree depth.
+        debug_assert!(
+            pos.ply >= self.depth,
+            "evaluate called on non-leaf node {} at ply {}",
+            pos.node,
+            pos.ply
+        );
+        if pos.ply >= self.depth {
+            let toggle = if ((max_depth - depth) & 1) == 0 {
+                1
+            } else {
+                -1
+            };
+            self.leaf_values[pos.node] * toggle
+        } else {
+            // `evaluate` should not be called on non-leaf nodes.
+            0
+        }
+    }
+
+    fn plies_to_leaves(&self, pos: TranspTreePos) -> Option<usize> {
+        Some(self.depth - pos.ply)
+    }
+
+    fn current_player(&self, pos: TranspTreePos) -> usize {
+        pos.ply & 1
+    }
+}
@@ -3,12 +3,13 @@ use crate::prelude::*;
 use rand::Rng;
 use rand_chacha::rand_core::SeedableRng;
 use rand_chacha::ChaChaRng;
+use rand_distr::{Cauchy, Distribution, Normal};
 
 use auto_enums::auto_enum;
 
 use std::collections::BTreeMap;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Uniform2bWidePos {
     pub node: u32,
 }
@@ -39,6 +40,12 @@ impl GamePosition for Uniform2bWidePos {
     }
 }
 
+impl ZobristKey for Uniform2bWidePos {
+    fn key(&self) -> u64 {
+        self.node as u64
+    }
+}
+
 pub struct Uniform2bWideHandler {
     leaf_start: u32,
     node_values: BTreeMap<u32, i32>,
@@ -47,6 +54,119 @@ pub struct Uniform2bWideHandler {
 pub struct Uniform2bWideParams {
     pub depth: u32,
     pub seed: u64,
+    pub distribution: LeafDistribution,
+}
+
+// The distribution from which the leaf values are drawn. Different distributions stress
+// the algorithms differently, from many ties between lines to sharply separated values.
+// Values are rounded and clamped to `-100..=100`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LeafDistribution {
+    // Uniform over `-100..=100`.
+    #[default]
+    Uniform,
+    // Normal with a mean of `0` and the given standard deviation.
+    Normal(f64),
+    // An even mixture of two normal distributions with means of `-centre` and `centre`
+    // and a standard deviation of `spread`.
+    Bimodal {
+        centre: f64,
+        spread: f64,
+    },
+    // Cauchy with a median of `0` and the given scale, whose heavy tails
+    // place many leaves at the extremes of the range.
+    HeavyTailed(f64),
+    // Every leaf has the given value, so that every line ties.
+    Constant(i32),
+}
+
+// A `LeafDistribution` with its parameters validated, built once for the whole tree.
+enum LeafSampler {
+    Uniform,
+    Normal(Normal<f64>),
+    Bimodal(Normal<f64>, Normal<f64>),
+    Cauchy(Cauchy<f64>),
+    Constant(i32),
+}
+
+impl LeafDistribution {
+    // Spreads that are negative or NaN are clamped to zero, placing every leaf at the centre
+    // of the distribution, and infinite spreads and centres to the largest finite value.
+    fn sampler(self) -> LeafSampler {
+        let clamp = |x: f64, min: f64| {
+            if x.is_nan() {
+                0.0
+            } else {
+                x.clamp(min, f64::MAX)
+            }
+        };
+        match self {
+            Self::Uniform => LeafSampler::Uniform,
+            Self::Normal(std_dev) => LeafSampler::Normal(
+                Normal::new(0.0, clamp(std_dev, 0.0)).expect("standard deviation is finite"),
+            ),
+            Self::Bimodal { centre, spread } => {
+                let (centre, spread) = (clamp(centre, -f64::MAX), clamp(spread, 0.0));
+                LeafSampler::Bimodal(
+                    Normal::new(-centre, spread).expect("standard deviation is finite"),
+                    Normal::new(centre, spread).expect("standard deviation is finite"),
+                )
+            }
+            Self::HeavyTailed(scale) => match Cauchy::new(0.0, clamp(scale, 0.0)) {
+                Ok(cauchy) => LeafSampler::Cauchy(cauchy),
+                // A Cauchy distribution of zero scale places every leaf at its median.
+                Err(_) => LeafSampler::Constant(0),
+            },
+            Self::Constant(value) => LeafSampler::Constant(value.clamp(-100, 100)),
+        }
+    }
+}
+
+impl LeafSampler {
+    fn sample(&self, rng: &mut ChaChaRng) -> i32 {
+        let value = match self {
+            Self::Uniform => return rng.gen_range(-100..=100),
+            Self::Normal(normal) => normal.sample(rng),
+            Self::Bimodal(lower, upper) => {
+                if rng.gen::<bool>() {
+                    upper.sample(rng)
+                } else {
+                    lower.sample(rng)
+                }
+            }
+            Self::Cauchy(cauchy) => cauchy.sample(rng),
+            Self::Constant(value) => return *value,
+        };
+        value.round().clamp(-100.0, 100.0) as i32
+    }
+}
+
+impl Uniform2bWideHandler {
+    // Returned by `evaluate` on a node that is not a leaf in release builds.
+    // Leaf values lie in `-100..=100`, so this is clearly distinguishable from a real evaluation,
+    // yet far enough from `EVAL_MINIMUM` and `EVAL_MAXIMUM` not to be confused with a search bound.
+    pub const INVALID_EVAL: i32 = -1000000;
+
+    // The values of the leaf nodes from left to right, each drawn from the `LeafDistribution`
+    // given in `Uniform2bWideParams`, which is uniform over `-100..=100` by default.
+    // These are determined entirely by the seed, so the same seed and distribution
+    // always yield the same tree.
+    // For reference, a uniform tree of depth 3 and seed 314159 gives
+    // `[-22, 10, 69, -58, 91, 90, -26, 39]`.
+    pub fn leaf_values(&self) -> Vec<i32> {
+        self.node_values.values().copied().collect()
+    }
+
+    // The number of leaf nodes in the full game tree, `2^depth`,
+    // against which the leaves evaluated by a search can be compared.
+    pub fn leaf_total(&self) -> u128 {
+        self.leaf_start as u128
+    }
+
+    // The number of nodes in the full game tree, numbered from `1` to `2^(depth + 1) - 1`.
+    pub fn node_total(&self) -> u128 {
+        2 * self.leaf_total() - 1
+    }
 }
 
 impl GameHandler<Uniform2bWidePos> for Uniform2bWideHandler {
@@ -58,11 +178,16 @@ impl GameHandler<Uniform2bWidePos> for Uniform2bWideHandler {
     const EVAL_EPSILON: i32 = 1;
 
     fn new(params: Uniform2bWideParams) -> Self {
-        let Uniform2bWideParams { depth, seed } = params;
+        let Uniform2bWideParams {
+            depth,
+            seed,
+            distribution,
+        } = params;
         let mut node_values: BTreeMap<u32, i32> = BTreeMap::new();
         let mut rng: ChaChaRng = ChaChaRng::seed_from_u64(seed);
+        let sampler = distribution.sampler();
         for node in 1 << depth..1 << (depth + 1) {
-            node_values.insert(node, rng.gen_range(-100..=100));
+            node_values.insert(node, sampler.sample(&mut rng));
         }
         Self {
             leaf_start: 1 << depth,
@@ -84,9 +209,113 @@ impl GameHandler<Uniform2bWidePos> for Uniform2bWideHandler {
     }
 
     fn evaluate(&self, pos: Uniform2bWidePos, _depth: usize, _max_depth: usize) -> Self::Eval {
+        // `evaluate` should only be called on leaf nodes,
+        // which would otherwise indicate a search depth that does not match the tree depth.
+        debug_assert!(
+            pos.node >= self.leaf_start,
+            "evaluate called on non-leaf node {}",
+            pos.node
+        );
         match self.node_values.get(&pos.node) {
             Some(&n) => n,
-            None => i32::MAX,
+            None => Self::INVALID_EVAL,
+        }
+    }
+
+    fn plies_to_leaves(&self, pos: Uniform2bWidePos) -> Option<usize> {
+        Some((self.leaf_start.ilog2() - pos.node.ilog2()) as usize)
+    }
+
+    fn current_player(&self, pos: Uniform2bWidePos) -> usize {
+        // The root is node 1, and each ply doubles the node number.
+        (pos.node.ilog2() & 1) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::Searcher;
+
+    fn handler(depth: u32, seed: u64, distribution: LeafDistribution) -> Uniform2bWideHandler {
+        Uniform2bWideHandler::new(Uniform2bWideParams {
+            depth,
+            seed,
+            distribution,
+        })
+    }
+
+    #[test]
+    fn leaf_values_are_fixed_by_seed() {
+        assert_eq!(
+            handler(3, 314159, LeafDistribution::Uniform).leaf_values(),
+            [-22, 10, 69, -58, 91, 90, -26, 39]
+        );
+        for distribution in [
+            LeafDistribution::Uniform,
+            LeafDistribution::Normal(30.0),
+            LeafDistribution::HeavyTailed(10.0),
+        ] {
+            let values = handler(6, 42, distribution).leaf_values();
+            assert_eq!(values.len(), 64);
+            assert_eq!(values, handler(6, 42, distribution).leaf_values());
+            assert_ne!(values, handler(6, 43, distribution).leaf_values());
+            assert!(values.iter().all(|v| (-100..=100).contains(v)));
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "evaluate called on non-leaf node")]
+    fn evaluating_non_leaf_panics_in_debug() {
+        let handler = handler(3, 314159, LeafDistribution::Uniform);
+        let interior = Uniform2bWidePos::startpos(()).play_move(Uniform2bWideMove::Left);
+        handler.evaluate(interior, 2, 3);
+    }
+
+    #[test]
+    fn invalid_parameters_are_clamped() {
+        for distribution in [
+            LeafDistribution::Normal(f64::NAN),
+            LeafDistribution::Normal(-5.0),
+            LeafDistribution::HeavyTailed(0.0),
+            LeafDistribution::HeavyTailed(-1.0),
+            LeafDistribution::Bimodal {
+                centre: f64::NAN,
+                spread: f64::NAN,
+            },
+        ] {
+            assert!(handler(4, 42, distribution)
+                .leaf_values()
+                .iter()
+                .all(|&v| v == 0));
+        }
+        let values = handler(4, 42, LeafDistribution::Normal(f64::INFINITY)).leaf_values();
+        assert!(values.iter().all(|v| [-100, 100].contains(v)));
+        let values = handler(4, 42, LeafDistribution::Constant(1000)).leaf_values();
+        assert!(values.iter().all(|&v| v == 100));
+    }
+
+    #[test]
+    fn constant_leaves_give_minimal_tree() {
+        // With every line tied, each first move is best, and alpha-beta evaluates exactly the
+        // minimal tree of `2^ceil(d / 2) + 2^floor(d / 2) - 1` leaves.
+        fn check<const DEPTH: usize>() {
+            let handler = handler(DEPTH as u32, 42, LeafDistribution::Constant(7));
+            let mut searcher = Searcher::new();
+            let (eval, _) = searcher.alpha_beta::<Uniform2bWideHandler, Uniform2bWidePos, DEPTH>(
+                &handler,
+                Uniform2bWidePos::startpos(()),
+                DEPTH,
+                Uniform2bWideHandler::EVAL_MINIMUM,
+                Uniform2bWideHandler::EVAL_MAXIMUM,
+            );
+            assert_eq!(eval, if DEPTH & 1 == 0 { 7 } else { -7 });
+            let minimal = (1 << DEPTH.div_ceil(2)) + (1 << (DEPTH / 2)) - 1;
+            assert_eq!(searcher.get_leaf_count(), minimal);
         }
+        check::<1>();
+        check::<4>();
+        check::<7>();
     }
 }
@@ -2,18 +2,58 @@ use crate::prelude::*;
 
 use auto_enums::auto_enum;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Ut3Board {
     pub us: u64,
     pub them: u64,
     pub share: u64,
 }
 
+// The state that `Ut3Board::make_move` discards, so that `unmake_move` can restore it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ut3Undo {
+    mv: u64,
+    zone: u64,
+}
+
 pub struct Ut3Handler {
     large_table: Vec<i32>,
     small_table: Vec<i32>,
 }
 
+// The weights of the heuristic evaluation, from which `Ut3Handler::new` builds its lookup tables.
+// Each line of a grid still open to only one player is worth the `*_two_count` weight
+// if that player has two cells of it, or the `*_one_count` weight if they have one,
+// where the `big_*` weights apply to the macro-grid and the `small_*` weights to the mini-grids.
+// Occupying a centre, corner or edge cell of any grid is worth `centre`, `corner` or `edge` respectively,
+// multiplied by `sq_big` for the cells of the macro-grid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ut3Params {
+    pub big_two_count: i32,
+    pub big_one_count: i32,
+    pub small_two_count: i32,
+    pub small_one_count: i32,
+    pub centre: i32,
+    pub corner: i32,
+    pub edge: i32,
+    pub sq_big: i32,
+}
+
+impl Default for Ut3Params {
+    fn default() -> Self {
+        Self {
+            big_two_count: 90,
+            big_one_count: 20,
+            small_two_count: 8,
+            small_one_count: 1,
+            centre: 9,
+            corner: 7,
+            edge: 5,
+            sq_big: 25,
+        }
+    }
+}
+
 impl Ut3Board {
     const CHUNK: u64 = 0b111111111;
     const DBLCHUNK: u64 = (Self::CHUNK << 9) | Self::CHUNK;
@@ -42,6 +82,155 @@ impl Ut3Board {
             & (0b11101011 | (((grid >> 7) & 1) * 0xff))
             & (0b10011011 | (((grid >> 8) & 1) * 0xff)))
     }
+
+    // The square that square `sq` of a 3x3 grid is moved to by symmetry `index`,
+    // where squares are numbered row by row, and the 8 symmetries are the 4 rotations
+    // followed by their reflections about the vertical axis.
+    const fn symmetric_square(index: usize, sq: u64) -> u64 {
+        let (row, col) = (sq / 3, sq % 3);
+        let (row, col) = match index & 3 {
+            0 => (row, col),
+            1 => (col, 2 - row),
+            2 => (2 - row, 2 - col),
+            _ => (2 - col, row),
+        };
+        if index & 4 == 0 {
+            3 * row + col
+        } else {
+            3 * row + 2 - col
+        }
+    }
+
+    // Applies one of the 8 symmetries of the board (see `symmetric_square`),
+    // moving both the mini-grids within the macro-grid and the cells within each mini-grid.
+    // The result is equivalent to this board in every respect, including the active zone.
+    pub fn symmetry(&self, index: usize) -> Self {
+        // The cells of both players as 81-bit sets, with cell `9 * zone + sq`.
+        let us_cells = self.us as u128 | ((self.share & Self::DBLCHUNK) as u128) << 63;
+        let them_cells = self.them as u128 | (((self.share >> 18) & Self::DBLCHUNK) as u128) << 63;
+        let transform_cells = |cells: u128| {
+            (0..81)
+                .filter(|cell| (cells >> cell) & 1 == 1)
+                .fold(0u128, |acc, cell| {
+                    let zone = Self::symmetric_square(index, cell / 9);
+                    acc | 1 << (9 * zone + Self::symmetric_square(index, cell % 9))
+                })
+        };
+        let transform_grid = |grid: u64| {
+            (0..9)
+                .filter(|sq| (grid >> sq) & 1 == 1)
+                .fold(0, |acc, sq| acc | 1 << Self::symmetric_square(index, sq))
+        };
+        let us_cells = transform_cells(us_cells);
+        let them_cells = transform_cells(them_cells);
+        let zone = match (self.share >> 54) & 0b1111 {
+            Self::ZONE_ANY => Self::ZONE_ANY,
+            zone => Self::symmetric_square(index, zone),
+        };
+        Self {
+            us: (us_cells & ((1 << 63) - 1)) as u64,
+            them: (them_cells & ((1 << 63) - 1)) as u64,
+            share: (us_cells >> 63) as u64
+                | ((them_cells >> 63) as u64) << 18
+                | transform_grid((self.share >> 36) & Self::CHUNK) << 36
+                | transform_grid((self.share >> 45) & Self::CHUNK) << 45
+                | zone << 54,
+        }
+    }
+
+    // The smallest of the 8 symmetric equivalents of this board, compared by `(us, them, share)`,
+    // so that symmetric positions can share a single transposition table entry.
+    pub fn canonical(&self) -> Self {
+        (0..8)
+            .map(|index| self.symmetry(index))
+            .min_by_key(|board| (board.us, board.them, board.share))
+            .unwrap()
+    }
+
+    // Zobrist keys, indexed by player for each of the 81 cells and 9 mini-grids,
+    // followed by one key for each active zone (including `ZONE_ANY`)
+    // and one for the second player being to move.
+    // The keys are generated at compile time by SplitMix64 from a fixed seed.
+    const ZOBRIST_CELL: usize = 0;
+    const ZOBRIST_LARGE: usize = 162;
+    const ZOBRIST_ZONE: usize = 180;
+    const ZOBRIST_SIDE: usize = 190;
+    const ZOBRIST_KEYS: [u64; 191] = {
+        let mut keys = [0; 191];
+        let mut state: u64 = 0x5554_3320_4b45_5953;
+        let mut i = 0;
+        while i < keys.len() {
+            state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            keys[i] = z ^ (z >> 31);
+            i += 1;
+        }
+        keys
+    };
+
+    // The player to move, where `0` is the player who moved first.
+    // Every move places exactly one piece, and the pieces of both players
+    // are stored in `us`, `them` and the lowest 36 bits of `share`.
+    fn side(&self) -> usize {
+        let pieces = self.us.count_ones()
+            + self.them.count_ones()
+            + (self.share & ((1 << 36) - 1)).count_ones();
+        (pieces & 1) as usize
+    }
+
+    // The Zobrist key of this board, computed from scratch.
+    // Since `us` and `them` swap after every move, the keys are assigned to
+    // the first and second player rather than to `us` and `them`,
+    // so that a move only changes the keys of the cells and zones it affects.
+    pub fn zobrist_key(&self) -> u64 {
+        let side = self.side();
+        let us_cells = self.us as u128 | ((self.share & Self::DBLCHUNK) as u128) << 63;
+        let them_cells = self.them as u128 | (((self.share >> 18) & Self::DBLCHUNK) as u128) << 63;
+        let mut key =
+            Self::ZOBRIST_KEYS[Self::ZOBRIST_ZONE + ((self.share >> 54) & 0b1111) as usize];
+        if side == 1 {
+            key ^= Self::ZOBRIST_KEYS[Self::ZOBRIST_SIDE];
+        }
+        for (player, cells, large) in [
+            (side, us_cells, (self.share >> 36) & Self::CHUNK),
+            (1 - side, them_cells, (self.share >> 45) & Self::CHUNK),
+        ] {
+            for cell in (0..81).filter(|cell| (cells >> cell) & 1 == 1) {
+                key ^= Self::ZOBRIST_KEYS[Self::ZOBRIST_CELL + 81 * player + cell];
+            }
+            for zone in (0..9).filter(|zone| (large >> zone) & 1 == 1) {
+                key ^= Self::ZOBRIST_KEYS[Self::ZOBRIST_LARGE + 9 * player + zone];
+            }
+        }
+        key
+    }
+
+    // Reverses `play_move`, given the move that was played and the active zone before it.
+    // The mini-grid of the move was undecided before it was played,
+    // so if it has been won, it was won by this move.
+    pub fn undo_move(&self, mv: u64, prev_zone: u64) -> Self {
+        // The board is flipped back to the perspective of the player who moved,
+        // which is the same exchange of bits that `play_move` makes.
+        let Self {
+            us: them,
+            them: mut us,
+            share,
+        } = *self;
+        let mut share = ((share & Self::DBLCHUNK) << 18)
+            | ((share >> 18) & Self::DBLCHUNK)
+            | ((share & (Self::CHUNK << 45)) >> 9)
+            | ((share & (Self::CHUNK << 36)) << 9)
+            | (prev_zone << 54);
+        if mv > 62 {
+            share &= !(1 << (mv - 63));
+        } else {
+            us &= !(1 << mv);
+        }
+        share &= !(1 << (36 + mv / 9));
+        Self { us, them, share }
+    }
 }
 
 impl GamePosition for Ut3Board {
@@ -97,37 +286,171 @@ impl GamePosition for Ut3Board {
     }
 }
 
+impl MakeUnmake for Ut3Board {
+    type Undo = Ut3Undo;
+
+    fn make_move(&mut self, mv: u64) -> Ut3Undo {
+        let undo = Ut3Undo {
+            mv,
+            zone: (self.share >> 54) & 0b1111,
+        };
+        *self = self.play_move(mv);
+        undo
+    }
+
+    fn unmake_move(&mut self, undo: Ut3Undo) {
+        *self = self.undo_move(undo.mv, undo.zone);
+    }
+}
+
+impl ZobristKey for Ut3Board {
+    fn key(&self) -> u64 {
+        self.zobrist_key()
+    }
+
+    // Plays a move and updates the Zobrist key `key` of this board to that of the new board,
+    // which only requires the keys of the cell played, the mini-grid if it was won,
+    // the old and new active zones, and the player to move.
+    fn play_move_with_key(&self, key: u64, mv: u64) -> (Self, u64) {
+        let side = self.side();
+        let next = self.play_move(mv);
+        let mut key = key
+            ^ Self::ZOBRIST_KEYS[Self::ZOBRIST_CELL + 81 * side + mv as usize]
+            ^ Self::ZOBRIST_KEYS[Self::ZOBRIST_ZONE + ((self.share >> 54) & 0b1111) as usize]
+            ^ Self::ZOBRIST_KEYS[Self::ZOBRIST_ZONE + ((next.share >> 54) & 0b1111) as usize]
+            ^ Self::ZOBRIST_KEYS[Self::ZOBRIST_SIDE];
+        // After the board is flipped, the mini-grids won by the player who moved are in bits 45 to 53.
+        if ((next.share >> 45) ^ (self.share >> 36)) & Self::CHUNK != 0 {
+            key ^= Self::ZOBRIST_KEYS[Self::ZOBRIST_LARGE + 9 * side + (mv / 9) as usize];
+        }
+        (next, key)
+    }
+}
+
 impl Ut3Handler {
     pub const OUTCOME_WIN: i32 = 1000000;
     pub const OUTCOME_DRAW: i32 = 0;
     pub const OUTCOME_LOSS: i32 = -1000000;
-
-    const BIG_TWO_COUNT: i32 = 90;
-    const BIG_ONE_COUNT: i32 = 20;
-    const SMALL_TWO_COUNT: i32 = 8;
-    const SMALL_ONE_COUNT: i32 = 1;
-
-    const CENTRE: i32 = 9;
-    const CORNER: i32 = 7;
-    const EDGE: i32 = 5;
-    const SQ_BIG: i32 = 25;
+    // A game lasts at most 81 plies, so a decided game scored by its distance from the root
+    // always lies within `MATE_BAND` of `OUTCOME_WIN` or `OUTCOME_LOSS`.
+    pub const MATE_BAND: i32 = 81;
+    // The largest magnitude of the heuristic evaluation of an undecided game,
+    // which keeps it strictly outside the mate band.
+    // With the default weights, the macro-grid contributes at most 8 lines of `big_two_count`
+    // and 57 cell weights of `sq_big`, and each mini-grid at most 8 lines of `small_two_count`
+    // and 57 cell weights, which totals under 3,300. Only extreme weights reach this limit,
+    // at which point the evaluation is clamped.
+    pub const HEURISTIC_LIMIT: i32 = Self::OUTCOME_WIN - Self::MATE_BAND - 1;
+    // The advantage at which `eval_to_win_prob` considers the player to move
+    // ten times as likely to win as to lose. Heuristic evaluations are far smaller than
+    // `OUTCOME_WIN`, so decided games map to `1.0` and `0.0`.
+    pub const WIN_PROB_SCALE: i32 = 1000;
 
     const CORNER_MASK: u64 = 0b_101_000_101;
     const EDGE_MASK: u64 = 0b_010_101_010;
     const CENTRE_MASK: u64 = 0b_000_010_000;
 
     const LINE: u64 = 0b111;
+
+    // The preference of a cell of any grid when ordering moves,
+    // where the centre is preferred to the corners, and the corners to the edges,
+    // as in the weights of the heuristic evaluation.
+    fn cell_rank(cell: u64) -> u8 {
+        if (Self::CENTRE_MASK >> cell) & 1 == 1 {
+            2
+        } else if (Self::CORNER_MASK >> cell) & 1 == 1 {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+impl Ut3Handler {
+    // The moves allowed by the rules of the game, which are every empty cell of the zone
+    // that the previous move sent the player to, or of every undecided zone if that zone
+    // is decided or full, until either player completes a line of the macro-grid.
+    #[auto_enum(Iterator)]
+    fn playable_moves(board: Ut3Board) -> impl Iterator<Item = u64> {
+        let Ut3Board { us, them, share } = board;
+
+        if Ut3Board::line_presence(share >> 36) || Ut3Board::line_presence(share >> 45) {
+            return std::iter::empty();
+        }
+
+        let zone = (share >> 54) & 0b1111;
+
+        match zone {
+            Ut3Board::ZONE_ANY => {
+                let nw_to_sw = us | them;
+                let s_to_se = (share >> 18) | share;
+                let large = (share >> 36) | (share >> 45);
+
+                (0..63)
+                    .filter(move |i| ((nw_to_sw >> i) & 1) == 0 && ((large >> (i / 9)) & 1) == 0)
+                    .chain((63..81).filter(move |i| {
+                        ((s_to_se >> (i - 63)) & 1) == 0 && ((large >> (i / 9)) & 1) == 0
+                    }))
+            }
+            7 | 8 => {
+                let s_to_se = (share >> 18) | share;
+                (9 * zone..9 * zone + 9).filter(move |i| ((s_to_se >> (i - 63)) & 1) == 0)
+            }
+            _ => {
+                let nw_to_sw = us | them;
+                (9 * zone..9 * zone + 9).filter(move |i| ((nw_to_sw >> i) & 1) == 0)
+            }
+        }
+    }
+
+    // Whether neither player can win the game any more, even though it has not ended,
+    // because every line of the macro-grid contains a zone that neither player can still win.
+    // A zone can still be won by a player if they have already won it,
+    // or if it is undecided and has a line without any of the opponent's cells.
+    // Searches treat such game states as terminal, since `get_legal_moves` generates no moves
+    // for them and they are evaluated as draws, as searching the remaining moves cannot change
+    // the result. The game itself has not ended, so `is_legal` still accepts the remaining moves.
+    pub fn is_dead_draw(&self, board: Ut3Board) -> bool {
+        let Ut3Board { us, them, share } = board;
+        let us_large = (share >> 36) & Ut3Board::CHUNK;
+        let them_large = (share >> 45) & Ut3Board::CHUNK;
+        let mut us_winnable = us_large;
+        let mut them_winnable = them_large;
+        for i in 0..9 {
+            if ((us_large | them_large) >> i) & 1 == 1 {
+                continue;
+            }
+            let (us_data, them_data) = if i < 7 {
+                (
+                    (us >> (9 * i)) & Ut3Board::CHUNK,
+                    (them >> (9 * i)) & Ut3Board::CHUNK,
+                )
+            } else {
+                (
+                    (share >> (9 * i - 63)) & Ut3Board::CHUNK,
+                    (share >> (9 * i - 45)) & Ut3Board::CHUNK,
+                )
+            };
+            if Ut3Board::line_presence(!them_data & Ut3Board::CHUNK) {
+                us_winnable |= 1 << i;
+            }
+            if Ut3Board::line_presence(!us_data & Ut3Board::CHUNK) {
+                them_winnable |= 1 << i;
+            }
+        }
+        !Ut3Board::line_presence(us_winnable) && !Ut3Board::line_presence(them_winnable)
+    }
 }
 
 impl GameHandler<Ut3Board> for Ut3Handler {
     type Eval = i32;
-    type Params = ();
+    type Params = Ut3Params;
 
     const EVAL_MINIMUM: i32 = Self::OUTCOME_LOSS;
     const EVAL_MAXIMUM: i32 = Self::OUTCOME_WIN;
     const EVAL_EPSILON: i32 = 1;
 
-    fn new(_: Self::Params) -> Self {
+    fn new(params: Ut3Params) -> Self {
         let mut large_table: Vec<i32> = vec![0; 262144];
         let mut small_table: Vec<i32> = vec![0; 262144];
 
@@ -145,6 +468,7 @@ impl GameHandler<Ut3Board> for Ut3Handler {
 
                 let mut us_won: bool = false;
                 let mut them_won: bool = false;
+                let mut open_lines: i32 = 0;
 
                 for i in (0..24).step_by(3) {
                     let us_count = pop_count[((us_lines >> i) & Self::LINE) as usize];
@@ -153,6 +477,7 @@ impl GameHandler<Ut3Board> for Ut3Handler {
                     if us_count != 0 && them_count != 0 {
                         continue;
                     }
+                    open_lines += 1;
                     if us_count == 3 {
                         us_won = true;
                         break;
@@ -163,31 +488,31 @@ impl GameHandler<Ut3Board> for Ut3Handler {
                     }
 
                     eval_large += match us_count {
-                        2 => Self::BIG_TWO_COUNT,
-                        1 => Self::BIG_ONE_COUNT,
+                        2 => params.big_two_count,
+                        1 => params.big_one_count,
                         _ => 0,
                     } - match them_count {
-                        2 => Self::BIG_TWO_COUNT,
-                        1 => Self::BIG_ONE_COUNT,
+                        2 => params.big_two_count,
+                        1 => params.big_one_count,
                         _ => 0,
                     };
                     eval_small += match us_count {
-                        2 => Self::SMALL_TWO_COUNT,
-                        1 => Self::SMALL_ONE_COUNT,
+                        2 => params.small_two_count,
+                        1 => params.small_one_count,
                         _ => 0,
                     } - match them_count {
-                        2 => Self::SMALL_TWO_COUNT,
-                        1 => Self::SMALL_ONE_COUNT,
+                        2 => params.small_two_count,
+                        1 => params.small_one_count,
                         _ => 0,
                     };
                 }
-                let eval_pos = Self::CORNER
+                let eval_pos = params.corner
                     * (pop_count[(us & Self::CORNER_MASK) as usize]
                         - pop_count[(them & Self::CORNER_MASK) as usize])
-                    + Self::EDGE
+                    + params.edge
                         * (pop_count[(us & Self::EDGE_MASK) as usize]
                             - pop_count[(them & Self::EDGE_MASK) as usize])
-                    + Self::CENTRE
+                    + params.centre
                         * (pop_count[(us & Self::CENTRE_MASK) as usize]
                             - pop_count[(them & Self::CENTRE_MASK) as usize]);
                 if us_won {
@@ -197,8 +522,17 @@ impl GameHandler<Ut3Board> for Ut3Handler {
                 } else if pop_count[(us | them) as usize] == 9 {
                     large_table[((them << 9) | us) as usize] = Self::OUTCOME_DRAW;
                 } else {
-                    large_table[((them << 9) | us) as usize] = eval_large + eval_pos * Self::SQ_BIG;
-                    small_table[((them << 9) | us) as usize] = eval_small + eval_pos;
+                    // Clamped so that extreme weights cannot be mistaken for a decided game.
+                    large_table[((them << 9) | us) as usize] = eval_large
+                        .saturating_add(eval_pos.saturating_mul(params.sq_big))
+                        .clamp(-Self::HEURISTIC_LIMIT, Self::HEURISTIC_LIMIT);
+                    // A mini-board where every line is blocked by both players can never be won,
+                    // so the placement of its pieces is irrelevant, just as if it were full.
+                    small_table[((them << 9) | us) as usize] = if open_lines == 0 {
+                        0
+                    } else {
+                        eval_small + eval_pos
+                    };
                 }
             }
         }
@@ -208,47 +542,35 @@ impl GameHandler<Ut3Board> for Ut3Handler {
         }
     }
 
+    // A dead draw has no moves to search, even though moves remain in the game itself.
     #[auto_enum(Iterator)]
     fn get_legal_moves(&self, board: Ut3Board) -> impl Iterator<Item = u64> {
-        let Ut3Board { us, them, share } = board;
-
-        if Ut3Board::line_presence(share >> 36) || Ut3Board::line_presence(share >> 45) {
-            return std::iter::empty();
+        if self.is_dead_draw(board) {
+            std::iter::empty()
+        } else {
+            Self::playable_moves(board)
         }
+    }
 
-        let zone = (share >> 54) & 0b1111;
-
-        match zone {
-            Ut3Board::ZONE_ANY => {
-                let nw_to_sw = us | them;
-                let s_to_se = (share >> 18) | share;
-                let large = (share >> 36) | (share >> 45);
-
-                (0..63)
-                    .filter(move |i| ((nw_to_sw >> i) & 1) == 0 && ((large >> (i / 9)) & 1) == 0)
-                    .chain((63..81).filter(move |i| {
-                        ((s_to_se >> (i - 63)) & 1) == 0 && ((large >> (i / 9)) & 1) == 0
-                    }))
-            }
-            7 | 8 => {
-                let s_to_se = (share >> 18) | share;
-                (9 * zone..9 * zone + 9).filter(move |i| ((s_to_se >> (i - 63)) & 1) == 0)
-            }
-            _ => {
-                let nw_to_sw = us | them;
-                (9 * zone..9 * zone + 9).filter(move |i| ((nw_to_sw >> i) & 1) == 0)
-            }
-        }
+    // Unlike `get_legal_moves`, the remaining moves of a dead draw are legal.
+    fn is_legal(&self, board: Ut3Board, mv: u64) -> bool {
+        Self::playable_moves(board).any(|playable| playable == mv)
     }
 
     fn evaluate(&self, board: Ut3Board, depth: usize, max_depth: usize) -> Self::Eval {
         let Ut3Board { us, them, share } = board;
         let eval = self.large_table[((share >> 36) & Ut3Board::DBLCHUNK) as usize];
-        if eval == Self::OUTCOME_WIN || eval == Self::OUTCOME_LOSS {
+        // Decided games are scored by their distance from the root, so that the winning side
+        // prefers shorter wins and the losing side prefers longer losses.
+        // Both adjustments move the score towards zero, keeping it within the evaluation bounds.
+        if eval == Self::OUTCOME_WIN {
             return eval - (max_depth - depth) as i32;
         }
+        if eval == Self::OUTCOME_LOSS {
+            return eval + (max_depth - depth) as i32;
+        }
         let large = ((share >> 36) | (share >> 45)) & Ut3Board::CHUNK;
-        if large == Ut3Board::CHUNK {
+        if large == Ut3Board::CHUNK || self.is_dead_draw(board) {
             return Self::OUTCOME_DRAW;
         }
         (0..7)
@@ -272,6 +594,371 @@ impl GameHandler<Ut3Board> for Ut3Handler {
                     self.small_table[((them_data << 9) | us_data) as usize]
                 }
             }))
-            .fold(eval, |acc, x| acc + x)
+            .fold(eval, |acc, x| acc.saturating_add(x))
+            .clamp(-Self::HEURISTIC_LIMIT, Self::HEURISTIC_LIMIT)
+    }
+
+    fn eval_to_win_prob(&self, eval: i32) -> f64 {
+        logistic(eval as f64, Self::WIN_PROB_SCALE as f64)
+    }
+
+    fn current_player(&self, board: Ut3Board) -> usize {
+        board.side()
+    }
+
+    // Moves to the centre of a mini-grid come first, then those to its corners,
+    // with ties broken by the position of the mini-grid itself in the same way.
+    fn order_moves(&self, _board: Ut3Board, moves: &mut Vec<u64>) {
+        moves.sort_by_key(|&mv| {
+            std::cmp::Reverse((Self::cell_rank(mv % 9), Self::cell_rank(mv / 9)))
+        });
+    }
+
+    // A move is shown as the column `a` to `i` and row `1` to `9` of its cell in the full 9x9 grid,
+    // where zones and the cells within each zone are both numbered in rows of three,
+    // so that cell 0 of zone 0 is `a1` and cell 8 of zone 8 is `i9`.
+    fn format_move(&self, _board: Ut3Board, mv: u64) -> String {
+        let (zone, cell) = (mv / 9, mv % 9);
+        let column = (zone % 3) * 3 + cell % 3;
+        let row = (zone / 3) * 3 + cell / 3;
+        format!("{}{}", (b'a' + column as u8) as char, row + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::*;
+
+    use rand::Rng;
+    use rand_chacha::rand_core::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    // The moves of a seeded random game, starting from the empty board.
+    fn random_game(handler: &Ut3Handler, seed: u64) -> Vec<u64> {
+        let mut rng = ChaChaRng::seed_from_u64(seed);
+        let mut board = Ut3Board::startpos(());
+        let mut line = Vec::new();
+        loop {
+            let moves = handler.get_legal_moves(board).collect::<Vec<_>>();
+            if moves.is_empty() {
+                return line;
+            }
+            let mv = moves[rng.gen_range(0..moves.len())];
+            board = board.play_move(mv);
+            line.push(mv);
+        }
+    }
+
+    // Every board along a line, including the empty board it starts from.
+    fn boards(line: &[u64]) -> Vec<Ut3Board> {
+        std::iter::once(Ut3Board::startpos(()))
+            .chain(line.iter().scan(Ut3Board::startpos(()), |board, &mv| {
+                *board = board.play_move(mv);
+                Some(*board)
+            }))
+            .collect()
+    }
+
+    #[test]
+    fn symmetric_boards_share_canonical_form() {
+        let handler = Ut3Handler::new(Ut3Params::default());
+        for seed in 0..4 {
+            for board in boards(&random_game(&handler, seed)) {
+                let canonical = board.canonical();
+                assert_eq!(board.symmetry(0), board);
+                for index in 0..8 {
+                    let symmetric = board.symmetry(index);
+                    assert_eq!(symmetric.canonical(), canonical);
+                    assert_eq!(
+                        handler.get_legal_moves(symmetric).count(),
+                        handler.get_legal_moves(board).count()
+                    );
+                    assert_eq!(
+                        handler.evaluate(symmetric, 0, 0),
+                        handler.evaluate(board, 0, 0)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn incremental_key_matches_full_key() {
+        let handler = Ut3Handler::new(Ut3Params::default());
+        for seed in 0..8 {
+            let mut board = Ut3Board::startpos(());
+            let mut key = board.zobrist_key();
+            for mv in random_game(&handler, seed) {
+                (board, key) = board.play_move_with_key(key, mv);
+                assert_eq!(key, board.zobrist_key());
+            }
+        }
+        // Symmetric boards are different positions, so their keys differ.
+        let board = Ut3Board::startpos(()).play_move(0);
+        assert_ne!(board.zobrist_key(), board.symmetry(2).zobrist_key());
+    }
+
+    // A board whose only pieces are in the top-left mini-grid, with any zone open.
+    fn top_left_board(us: &[u64], them: &[u64]) -> Ut3Board {
+        let cells = |cells: &[u64]| cells.iter().fold(0, |acc, cell| acc | 1 << cell);
+        Ut3Board {
+            us: cells(us),
+            them: cells(them),
+            share: Ut3Board::ZONE_ANY << 54,
+        }
+    }
+
+    #[test]
+    fn dead_mini_board_contributes_nothing() {
+        let handler = Ut3Handler::new(Ut3Params::default());
+        // Both mini-grids have the same numbers of corner, edge and centre cells for each player,
+        // and score the same if their lines are counted, but only the second has an open line.
+        let dead = top_left_board(&[0, 1, 5, 6], &[2, 3, 4, 7]);
+        let live = top_left_board(&[0, 1, 3, 8], &[2, 4, 5, 7]);
+        assert_eq!(handler.evaluate(dead, 0, 0), 0);
+        assert_eq!(handler.evaluate(live, 0, 0), -2);
+    }
+
+    // The first random game that ends with a player winning the macro-grid,
+    // returned as the board before the winning move and the winning move itself.
+    fn decisive_finish(handler: &Ut3Handler) -> (Ut3Board, u64) {
+        (0..)
+            .find_map(|seed| {
+                let line = random_game(handler, seed);
+                let boards = boards(&line);
+                let last = *boards.last().unwrap();
+                let won = handler.evaluate(last, 0, 0) == Ut3Handler::OUTCOME_LOSS;
+                won.then(|| (boards[boards.len() - 2], *line.last().unwrap()))
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn decided_games_prefer_short_wins_and_long_losses() {
+        let handler = Ut3Handler::new(Ut3Params::default());
+        let (before, winning_move) = decisive_finish(&handler);
+        let lost = before.play_move(winning_move);
+        // The player to move has lost, and prefers the loss to come later.
+        let (sooner, later) = (handler.evaluate(lost, 3, 4), handler.evaluate(lost, 1, 4));
+        assert_eq!(sooner, Ut3Handler::OUTCOME_LOSS + 1);
+        assert_eq!(later, Ut3Handler::OUTCOME_LOSS + 3);
+        assert!(later > sooner);
+        // The winner prefers the sooner win, so a deeper search still wins at once.
+        let (eval, line) = Searcher::new().alpha_beta::<Ut3Handler, Ut3Board, 3>(
+            &handler,
+            before,
+            3,
+            Ut3Handler::EVAL_MINIMUM,
+            Ut3Handler::EVAL_MAXIMUM,
+        );
+        assert_eq!(eval, Ut3Handler::OUTCOME_WIN - 1);
+        assert_eq!(line[1], None);
+        assert_eq!(
+            handler.evaluate(before.play_move(line[0].unwrap()), 0, 0),
+            Ut3Handler::OUTCOME_LOSS
+        );
+    }
+
+    #[test]
+    fn default_params_reproduce_original_tables() {
+        // Fingerprints of the tables as they were built when the weights were constants.
+        let fingerprint = |table: &[i32]| {
+            table.iter().fold(0u64, |hash, &value| {
+                hash.wrapping_mul(1_000_003) ^ (value as u32 as u64)
+            })
+        };
+        let handler = Ut3Handler::new(Ut3Params::default());
+        assert_eq!(fingerprint(&handler.large_table), 0x888c11394f79d3b8);
+        assert_eq!(fingerprint(&handler.small_table), 0xed054146a21b06d4);
+
+        let tuned = Ut3Handler::new(Ut3Params {
+            centre: 10,
+            ..Ut3Params::default()
+        });
+        assert!(tuned.large_table != handler.large_table);
+        assert!(tuned.small_table != handler.small_table);
+    }
+
+    #[test]
+    fn dead_draws_end_searches_but_not_games() {
+        let handler = Ut3Handler::new(Ut3Params::default());
+        // Random games are continued past a dead draw until one is found with a move left.
+        let (line, board) = (0..)
+            .find_map(|seed| {
+                let line = random_game(&handler, seed);
+                let board = *boards(&line).last().unwrap();
+                let playable = Ut3Handler::playable_moves(board).next();
+                handler
+                    .is_dead_draw(board)
+                    .then_some((line, board))
+                    .filter(|_| playable.is_some())
+            })
+            .unwrap();
+        let remaining = Ut3Handler::playable_moves(board).collect::<Vec<_>>();
+
+        assert_eq!(handler.get_legal_moves(board).next(), None);
+        assert_eq!(handler.evaluate(board, 0, 4), Ut3Handler::OUTCOME_DRAW);
+        assert!(remaining.iter().all(|&mv| handler.is_legal(board, mv)));
+        let mut record = line.clone();
+        record.push(remaining[0]);
+        let start = Ut3Board::startpos(());
+        assert!(handler.replay(start, &record) == Ok(board.play_move(remaining[0])));
+
+        let mut searcher = Searcher::new();
+        let (eval, pv) = searcher.alpha_beta::<Ut3Handler, Ut3Board, 4>(
+            &handler,
+            board,
+            4,
+            Ut3Handler::EVAL_MINIMUM,
+            Ut3Handler::EVAL_MAXIMUM,
+        );
+        assert_eq!((eval, pv), (Ut3Handler::OUTCOME_DRAW, [None; 4]));
+    }
+
+    #[test]
+    fn undo_move_reverses_play_move() {
+        let handler = Ut3Handler::new(Ut3Params::default());
+        for seed in 0..20 {
+            let line = random_game(&handler, seed);
+            let boards = boards(&line);
+            for &board in &boards {
+                let zone = (board.share >> 54) & 0b1111;
+                for mv in Ut3Handler::playable_moves(board) {
+                    assert_eq!(board.play_move(mv).undo_move(mv, zone), board);
+                }
+            }
+
+            // The whole game is made and then unmade in reverse, back to the empty board.
+            let mut board = Ut3Board::startpos(());
+            let undos = line
+                .iter()
+                .map(|&mv| board.make_move(mv))
+                .collect::<Vec<_>>();
+            assert_eq!(board, *boards.last().unwrap());
+            for (&undo, &expected) in undos.iter().zip(&boards).rev() {
+                board.unmake_move(undo);
+                assert_eq!(board, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn alpha_beta_inplace_matches_alpha_beta() {
+        let handler = Ut3Handler::new(Ut3Params::default());
+        let (min, max) = (Ut3Handler::EVAL_MINIMUM, Ut3Handler::EVAL_MAXIMUM);
+        for seed in 0..4 {
+            let line = random_game(&handler, seed);
+            let board = boards(&line)[line.len() / 2];
+            let mut searcher = Searcher::new();
+            let expected =
+                searcher.alpha_beta::<Ut3Handler, Ut3Board, 4>(&handler, board, 4, min, max);
+            let mut inplace = board;
+            let result = searcher.alpha_beta_inplace::<Ut3Handler, Ut3Board, 4>(
+                &handler,
+                &mut inplace,
+                4,
+                min,
+                max,
+            );
+            assert_eq!(result, expected);
+            assert_eq!(inplace, board);
+        }
+    }
+
+    #[test]
+    fn format_move_names_cells_of_full_grid() {
+        let handler = Ut3Handler::new(Ut3Params::default());
+        let board = Ut3Board::startpos(());
+        let names = [0, 2, 8, 9, 40, 54, 72, 80].map(|mv| handler.format_move(board, mv));
+        assert_eq!(names, ["a1", "c1", "c3", "d1", "e5", "a7", "g7", "i9"]);
+    }
+
+    #[test]
+    fn undecided_evaluations_stay_out_of_mate_band() {
+        let extreme = Ut3Params {
+            big_two_count: 250000,
+            big_one_count: 250000,
+            small_two_count: 250000,
+            small_one_count: 250000,
+            centre: 250000,
+            corner: 250000,
+            edge: 250000,
+            sq_big: 1000,
+        };
+        for params in [Ut3Params::default(), extreme] {
+            let handler = Ut3Handler::new(params);
+            let mut rng = ChaChaRng::seed_from_u64(2140);
+            for _ in 0..200 {
+                // Plays a random game to its end, through any dead draw.
+                let mut board = Ut3Board::startpos(());
+                for ply in 0.. {
+                    let eval = handler.evaluate(board, 0, ply);
+                    if handler.get_legal_moves(board).next().is_some() {
+                        assert!(eval.abs() <= Ut3Handler::HEURISTIC_LIMIT);
+                    } else if eval != Ut3Handler::OUTCOME_DRAW {
+                        let distance = Ut3Handler::OUTCOME_WIN - eval.abs();
+                        assert_eq!(distance, ply as i32);
+                    }
+                    let moves = Ut3Handler::playable_moves(board).collect::<Vec<_>>();
+                    if moves.is_empty() {
+                        break;
+                    }
+                    board = board.play_move(moves[rng.gen_range(0..moves.len())]);
+                }
+            }
+        }
+    }
+
+    // `Ut3Handler` with its legal moves generated in the order given by `order_moves`,
+    // so that plain `alpha_beta` searches them in that order.
+    struct OrderedUt3(Ut3Handler);
+
+    impl GameHandler<Ut3Board> for OrderedUt3 {
+        type Eval = i32;
+        type Params = Ut3Params;
+
+        const EVAL_MINIMUM: i32 = Ut3Handler::EVAL_MINIMUM;
+        const EVAL_MAXIMUM: i32 = Ut3Handler::EVAL_MAXIMUM;
+        const EVAL_EPSILON: i32 = Ut3Handler::EVAL_EPSILON;
+
+        fn new(params: Ut3Params) -> Self {
+            Self(Ut3Handler::new(params))
+        }
+
+        fn get_legal_moves(&self, board: Ut3Board) -> impl Iterator<Item = u64> {
+            let mut moves = self.0.get_legal_moves(board).collect::<Vec<_>>();
+            self.0.order_moves(board, &mut moves);
+            moves.into_iter()
+        }
+
+        fn evaluate(&self, board: Ut3Board, depth: usize, max_depth: usize) -> i32 {
+            self.0.evaluate(board, depth, max_depth)
+        }
+
+        fn current_player(&self, board: Ut3Board) -> usize {
+            self.0.current_player(board)
+        }
+    }
+
+    #[test]
+    fn move_ordering_reduces_leaf_count() {
+        let handler = Ut3Handler::new(Ut3Params::default());
+        let ordered = OrderedUt3::new(Ut3Params::default());
+        let (min, max) = (Ut3Handler::EVAL_MINIMUM, Ut3Handler::EVAL_MAXIMUM);
+        let mut leaves = [0, 0];
+        let game = random_game(&handler, 2268);
+        for board in boards(&game[..6]) {
+            let mut searcher = Searcher::new();
+            let (expected, _) =
+                searcher.alpha_beta::<Ut3Handler, Ut3Board, 5>(&handler, board, 5, min, max);
+            leaves[0] += searcher.get_leaf_count();
+            searcher.reset_leaf_count();
+            let (eval, _) =
+                searcher.alpha_beta::<OrderedUt3, Ut3Board, 5>(&ordered, board, 5, min, max);
+            leaves[1] += searcher.get_leaf_count();
+            assert_eq!(eval, expected);
+        }
+        assert!(leaves[1] < leaves[0]);
     }
 }
@@ -0,0 +1,66 @@
+use std::sync::Mutex;
+
+// Killer moves are moves which caused a beta cutoff at some node, and which are
+// tried first at the other nodes of the same ply in the hope that they cause a cutoff there too.
+// Two killer moves are kept per ply, with the most recent first.
+// Each ply has its own lock, held only while reading or writing its two slots,
+// so that the threads of a parallel search can share a table with little contention.
+pub struct KillerTable<TMove> {
+    slots: Vec<Mutex<[Option<TMove>; 2]>>,
+}
+
+// How `Searcher::alpha_beta_root_parallel` uses killer moves below the root.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParallelKillers {
+    // No killer moves, so that each subtree is searched exactly as `alpha_beta` would.
+    Off,
+    // One table for every worker, so that cutoffs found in one subtree improve the move ordering
+    // of its siblings.
+    Shared,
+    // A separate table for each root move.
+    PerRootMove,
+}
+
+impl<TMove> KillerTable<TMove>
+where
+    TMove: Copy + Eq,
+{
+    // A table for searches of at most `max_depth` plies.
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            slots: (0..max_depth).map(|_| Mutex::new([None; 2])).collect(),
+        }
+    }
+
+    pub fn get(&self, ply: usize) -> [Option<TMove>; 2] {
+        *self.slots[ply].lock().unwrap()
+    }
+
+    // Records a move that caused a cutoff at `ply`, displacing the older killer move.
+    pub fn record(&self, ply: usize, mv: TMove) {
+        let mut slots = self.slots[ply].lock().unwrap();
+        if slots[0] != Some(mv) {
+            slots[1] = slots[0];
+            slots[0] = Some(mv);
+        }
+    }
+
+    pub fn clear(&self) {
+        for slots in self.slots.iter() {
+            *slots.lock().unwrap() = [None; 2];
+        }
+    }
+
+    // Moves the killer moves of `ply` that appear in `moves` to the front,
+    // keeping the order of the remaining moves.
+    pub fn order(&self, ply: usize, moves: &mut [TMove]) {
+        let killers = self.get(ply);
+        let mut front = 0;
+        for killer in killers.into_iter().flatten() {
+            if let Some(index) = moves[front..].iter().position(|&mv| mv == killer) {
+                moves[front..=front + index].rotate_right(1);
+                front += 1;
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::bench::*;
 use crate::games::chess::*;
 use crate::games::hypothetical_tree::*;
 use crate::games::stockman::*;
@@ -6,179 +7,42 @@ use crate::games::ut3::*;
 use crate::prelude::*;
 use crate::search::*;
 
+pub mod bench;
+pub mod book;
+pub mod counting;
+pub mod epd;
 pub mod games;
+pub mod killers;
+#[cfg(test)]
+mod perft_suite;
 pub mod prelude;
+pub mod pruning;
 pub mod search;
+pub mod tactics;
+pub mod tt;
 
 use colored::Colorize;
 use seq_macro::seq;
 
-use std::time::Instant;
-
-#[derive(Clone, Copy, Debug)]
-struct AlgorithmStats {
-    pub avg_leaves: f64,
-    pub avg_ms: f64,
-    pub avg_us: f64,
-    pub avg_ns: f64,
-}
-
-impl AlgorithmStats {
-    fn new() -> Self {
-        Self {
-            avg_leaves: 0.0,
-            avg_ms: 0.0,
-            avg_us: 0.0,
-            avg_ns: 0.0,
-        }
-    }
-}
-
-impl Default for AlgorithmStats {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl std::ops::Add for AlgorithmStats {
-    type Output = AlgorithmStats;
-
-    fn add(self, rhs: Self) -> Self::Output {
-        Self {
-            avg_leaves: self.avg_leaves + rhs.avg_leaves,
-            avg_ms: self.avg_ms + rhs.avg_ms,
-            avg_us: self.avg_us + rhs.avg_us,
-            avg_ns: self.avg_ns + rhs.avg_ns,
-        }
-    }
-}
-
-impl std::ops::AddAssign for AlgorithmStats {
-    fn add_assign(&mut self, rhs: Self) {
-        *self = *self + rhs;
-    }
-}
-
-fn eval_from_line<THandler, TPosition, const SIZE: usize>(
-    handler: &THandler,
-    initial_pos: TPosition,
-    line: [Option<<TPosition as GamePosition>::Move>; SIZE],
-) -> <THandler as GameHandler<TPosition>>::Eval
-where
-    THandler: GameHandler<TPosition>,
-    TPosition: GamePosition,
-{
-    let mut pos = initial_pos;
-    let mut depth = 0;
-    for &mv in line.iter() {
-        if let Some(m) = mv {
-            pos = pos.play_move(m);
-            depth += 1;
-        }
-    }
-    if (depth & 1) == 0 {
-        handler.evaluate(pos, SIZE - depth, SIZE)
-    } else {
-        -handler.evaluate(pos, SIZE - depth, SIZE)
-    }
-}
-
-fn root_call_bb<THandler, TPosition, const DEPTH: usize>(
-    searcher: &mut Searcher,
-    handler: &THandler,
-    root: TPosition,
-) -> EvalAndPV<THandler, TPosition, DEPTH>
-where
-    THandler: GameHandler<TPosition>,
-    TPosition: GamePosition,
-{
-    searcher.branch_and_bound::<THandler, TPosition, DEPTH>(
-        handler,
-        root,
-        DEPTH,
-        <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM,
-    )
-}
-
-fn root_call_ab<THandler, TPosition, const DEPTH: usize>(
-    searcher: &mut Searcher,
-    handler: &THandler,
-    root: TPosition,
-) -> EvalAndPV<THandler, TPosition, DEPTH>
-where
-    THandler: GameHandler<TPosition>,
-    TPosition: GamePosition,
-{
-    searcher.alpha_beta::<THandler, TPosition, DEPTH>(
-        handler,
-        root,
-        DEPTH,
-        <THandler as GameHandler<TPosition>>::EVAL_MINIMUM,
-        <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM,
-    )
-}
-
-fn root_call_pab<THandler, TPosition, const DEPTH: usize>(
-    searcher: &mut Searcher,
-    handler: &THandler,
-    root: TPosition,
-) -> EvalAndPV<THandler, TPosition, DEPTH>
-where
-    THandler: GameHandler<TPosition>,
-    TPosition: GamePosition,
-{
-    searcher.p_alpha_beta(handler, root, DEPTH)
-}
-
-fn root_call_pvs<THandler, TPosition, const DEPTH: usize>(
-    searcher: &mut Searcher,
+fn moves_display<THandler, TPosition, const SIZE: usize>(
     handler: &THandler,
-    root: TPosition,
-) -> EvalAndPV<THandler, TPosition, DEPTH>
+    startpos: TPosition,
+    move_list: [Option<<TPosition as GamePosition>::Move>; SIZE],
+) -> String
 where
     THandler: GameHandler<TPosition>,
     TPosition: GamePosition,
 {
-    searcher.pvs(
-        handler,
-        root,
-        DEPTH,
-        <THandler as GameHandler<TPosition>>::EVAL_MINIMUM,
-        <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM,
-    )
-}
-
-fn root_call_scout<THandler, TPosition, const DEPTH: usize>(
-    searcher: &mut Searcher,
-    handler: &THandler,
-    root: TPosition,
-) -> EvalAndPV<THandler, TPosition, DEPTH>
-where
-    THandler: GameHandler<TPosition>,
-    TPosition: GamePosition,
-{
-    searcher.scout(handler, root, DEPTH)
-}
-
-fn root_call_sss<THandler, TPosition, const DEPTH: usize>(
-    searcher: &mut Searcher,
-    handler: &THandler,
-    root: TPosition,
-) -> EvalAndPV<THandler, TPosition, DEPTH>
-where
-    THandler: GameHandler<TPosition>,
-    TPosition: GamePosition,
-{
-    searcher.sss(handler, root, DEPTH)
-}
-
-fn raw_moves_display<T, const SIZE: usize>(move_list: [Option<T>; SIZE]) -> String
-where
-    T: std::fmt::Debug,
-{
+    let mut pos = startpos;
     move_list
         .iter()
-        .map_while(|mv| mv.as_ref().map(|m| format!("{:?}", m)))
+        .map_while(|&mv| {
+            mv.map(|m| {
+                let formatted = handler.format_move(pos, m);
+                pos = pos.play_move(m);
+                formatted
+            })
+        })
         .collect::<Vec<String>>()
         .join(", ")
 }
@@ -195,58 +59,66 @@ fn test_algorithms_once<THandler, TPosition, const DEPTH: usize>(
     let handler = <THandler as GameHandler<TPosition>>::new(handler_params);
     let startpos = <TPosition as GamePosition>::startpos(startpos_params);
 
-    let algorithms = (
-        root_call_bb,
-        root_call_ab,
-        root_call_pab,
-        root_call_pvs,
-        root_call_scout,
-        root_call_sss,
-    );
-
-    let algorithm_names = (
-        "branch_and_bound",
-        "alpha_beta",
-        "p_alpha_beta",
-        "pvs",
-        "scout",
-        "sss",
-    );
-
     println!("{}", position_name.bright_magenta());
-    seq!(N in 0..6 {
-        println!("{}", algorithm_names.N.bright_cyan());
-        searcher.reset_leaf_count();
-        let s = Instant::now();
-        let result: EvalAndPV<THandler, TPosition, DEPTH> = algorithms.N(searcher, &handler, startpos);
-        let elapsed = s.elapsed();
+    for run in run_once::<THandler, TPosition, DEPTH>(searcher, &handler, startpos) {
+        println!("{}", run.algorithm.bright_cyan());
         println!(
             "Time elapsed: {} ms, {} us, {} ns",
-            elapsed.as_millis().to_string().bright_yellow(),
-            elapsed.as_micros().to_string().bright_cyan(),
-            elapsed.as_nanos().to_string().bright_blue(),
+            run.elapsed.as_millis().to_string().bright_yellow(),
+            run.elapsed.as_micros().to_string().bright_cyan(),
+            run.elapsed.as_nanos().to_string().bright_blue(),
         );
-        println!("Leaf nodes evaluated: {}", searcher.get_leaf_count().to_string().bright_yellow());
-        let recalculated_eval = eval_from_line(&handler, startpos, result.1);
-        if recalculated_eval == result.0 {
-            println!("Eval and Line {}", "MATCH".bright_green());
-            println!(
-                "Eval: {}, Line: {}",
-                format!("{:?}", result.0).bright_green(),
-                raw_moves_display(result.1).bright_green(),
-            );
-        } else {
-            println!("Eval and Line {}", "MISMATCH".bright_red());
+        println!("Leaf nodes evaluated: {}", run.leaf_count.to_string().bright_yellow());
+        match run.verification {
+            Ok(()) => {
+                println!("Eval and Line {}", "MATCH".bright_green());
+                println!(
+                    "Eval: {}, Line: {}",
+                    format!("{:?}", run.result.0).bright_green(),
+                    moves_display(&handler, startpos, run.result.1).bright_green(),
+                );
+            }
+            Err(mismatch) => {
+                println!("Eval and Line {}", "MISMATCH".bright_red());
+                println!(
+                    "Returned Eval: {}, Recalculated Eval: {}",
+                    format!("{:?}", mismatch.returned_eval).bright_green(),
+                    format!("{:?}", mismatch.recalculated_eval).bright_red(),
+                );
+                println!("Line Given: {}", moves_display(&handler, startpos, run.result.1).bright_red());
+            }
+        }
+    }
+}
+
+// Prints any disagreement found within a single iteration of `run_average`.
+fn report_mismatches<THandler, TPosition, const DEPTH: usize>(
+    runs: &[AlgorithmRun<THandler, TPosition, DEPTH>],
+) where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    for run in runs {
+        if let Err(mismatch) = run.verification {
             println!(
-                "Returned Eval: {}, Recalculated Eval: {}",
-                format!("{:?}", result.0).bright_green(),
-                format!("{:?}", recalculated_eval).bright_red(),
+                "{}",
+                format!(
+                    "INDIVIDUAL MISMATCH (Alg: {}, Returned Eval: {:?}, Recalc Eval: {:?}, Returned Line: {:?})",
+                    run.algorithm,
+                    mismatch.returned_eval,
+                    mismatch.recalculated_eval,
+                    run.result.1,
+                )
+                .bright_red(),
             );
-            println!("Line Given: {}", raw_moves_display(result.1).bright_red());
         }
-    });
-
-    searcher.reset_leaf_count();
+    }
+    if !runs_agree(runs) {
+        println!("{}", "ALGORITHM MISMATCH".bright_red());
+        for run in runs {
+            println!("Alg: {}, Result: {:?}", run.algorithm, run.result);
+        }
+    }
 }
 
 fn test_algorithms_average<THandler, TPosition, const DEPTH: usize>(
@@ -260,39 +132,6 @@ fn test_algorithms_average<THandler, TPosition, const DEPTH: usize>(
     THandler: GameHandler<TPosition>,
     TPosition: GamePosition,
 {
-    if handler_params.len() < times {
-        panic!("List of GameHandler parameters needs to be equal to or more than the number of iterations");
-    }
-
-    let startpos = <TPosition as GamePosition>::startpos(startpos_params);
-
-    let algorithms = (
-        root_call_bb,
-        root_call_ab,
-        root_call_pab,
-        root_call_pvs,
-        root_call_scout,
-        root_call_sss,
-    );
-
-    let algorithm_names = [
-        "branch_and_bound",
-        "alpha_beta",
-        "p_alpha_beta",
-        "pvs",
-        "scout",
-        "sss",
-    ];
-
-    let mut stats = [
-        AlgorithmStats::new(),
-        AlgorithmStats::new(),
-        AlgorithmStats::new(),
-        AlgorithmStats::new(),
-        AlgorithmStats::new(),
-        AlgorithmStats::new(),
-    ];
-
     if verbose {
         println!(
             "Running {} times: {}",
@@ -301,99 +140,24 @@ fn test_algorithms_average<THandler, TPosition, const DEPTH: usize>(
         );
     }
 
-    let mut i: usize = 1;
-    for param in handler_params {
-        if verbose {
-            println!("Iteration {}", i.to_string().bright_cyan());
-        }
-
-        let handler = <THandler as GameHandler<TPosition>>::new(param);
-        let mut results: [Option<EvalAndPV<THandler, TPosition, DEPTH>>; 6] = [None; 6];
-
-        seq!(N in 0..6 {
-            searcher.reset_leaf_count();
-
-            let s = Instant::now();
-            let result: EvalAndPV<THandler, TPosition, DEPTH> = algorithms.N(searcher, &handler, startpos);
-            let elapsed = s.elapsed();
-
-            let recalculated_eval = eval_from_line(&handler, startpos, result.1);
-            if recalculated_eval != result.0 {
-                println!(
-                    "{}",
-                    format!(
-                        "INDIVIDUAL MISMATCH (Alg: {}, Returned Eval: {:?}, Recalc Eval: {:?}, Returned Line: {:?})",
-                        algorithm_names[N],
-                        result.0,
-                        recalculated_eval,
-                        result.1,
-                    )
-                        .bright_red(),
-                );
+    let records = run_average::<THandler, TPosition, DEPTH>(
+        searcher,
+        position_name,
+        times,
+        handler_params,
+        startpos_params,
+        |i, runs| {
+            if verbose {
+                println!("Iteration {}", i.to_string().bright_cyan());
             }
-            results[N] = Some(result);
-
-            stats[N] += AlgorithmStats {
-                avg_leaves: (searcher.get_leaf_count() as f64) / (times as f64),
-                avg_ms: (elapsed.as_millis() as f64) / (times as f64),
-                avg_us: (elapsed.as_micros() as f64) / (times as f64),
-                avg_ns: (elapsed.as_nanos() as f64) / (times as f64),
-            };
-
-        });
-
-        let algorithms_match = results
-            .iter()
-            .skip(1)
-            .fold(
-                (results[0], true),
-                |(previous_result, all_match), &current_result| {
-                    (
-                        previous_result,
-                        all_match && previous_result == current_result,
-                    )
-                },
-            )
-            .1;
-
-        if !algorithms_match {
-            println!("{}", "ALGORITHM MISMATCH".bright_red());
-            for i in 0..6 {
-                println!("Alg: {}, Result: {:?}", algorithm_names[i], results[i]);
-            }
-        }
-
-        i += 1;
-    }
+            report_mismatches(runs);
+        },
+    );
 
     println!("{}", position_name.bright_magenta());
-    for (result, alg_name) in stats.iter().zip(algorithm_names.iter()) {
-        let AlgorithmStats {
-            avg_leaves,
-            avg_ms,
-            avg_us,
-            avg_ns,
-        } = result;
-        println!("Algorithm Tested: {}", alg_name.bright_cyan());
-        println!(
-            "Average number of leaf nodes evaluated: {}",
-            format!("{:.2}", avg_leaves).bright_yellow()
-        );
-        println!(
-            "Average compute time (milliseconds, 2 d.p.): {} ms",
-            format!("{:.2}", avg_ms).bright_blue()
-        );
-        println!(
-            "Average compute time (microseconds, 2 d.p.): {} us",
-            format!("{:.2}", avg_us).bright_blue()
-        );
-        println!(
-            "Average compute time (nanoseconds, 2 d.p.): {} ns",
-            format!("{:.2}", avg_ns).bright_blue()
-        );
+    for record in records {
+        println!("{}", record.to_console());
     }
-
-    searcher.reset_leaf_count();
 }
 
 fn output_result_table<THandler, TPosition, const DEPTH: usize>(
@@ -407,39 +171,6 @@ fn output_result_table<THandler, TPosition, const DEPTH: usize>(
     THandler: GameHandler<TPosition>,
     TPosition: GamePosition,
 {
-    if handler_params.len() < times {
-        panic!("List of GameHandler parameters needs to be equal to or more than the number of iterations");
-    }
-
-    let startpos = <TPosition as GamePosition>::startpos(startpos_params);
-
-    let algorithms = (
-        root_call_ab,
-        root_call_bb,
-        root_call_pab,
-        root_call_scout,
-        root_call_pvs,
-        root_call_sss,
-    );
-
-    let algorithm_names = [
-        "AB",
-        "BB",
-        "PAB",
-        "Scout",
-        "PVS",
-        "SSS*",
-    ];
-
-    let mut stats = [
-        AlgorithmStats::new(),
-        AlgorithmStats::new(),
-        AlgorithmStats::new(),
-        AlgorithmStats::new(),
-        AlgorithmStats::new(),
-        AlgorithmStats::new(),
-    ];
-
     if verbose {
         println!(
             "Running {} times: {}",
@@ -448,126 +179,57 @@ fn output_result_table<THandler, TPosition, const DEPTH: usize>(
         );
     }
 
-    let mut i: usize = 1;
-    for param in handler_params {
-        if verbose {
-            println!("Iteration {}", i.to_string().bright_cyan());
-        }
-
-        let handler = <THandler as GameHandler<TPosition>>::new(param);
-        let mut results: [Option<EvalAndPV<THandler, TPosition, DEPTH>>; 6] = [None; 6];
-
-        seq!(N in 0..6 {
-            searcher.reset_leaf_count();
-
-            let s = Instant::now();
-            let result: EvalAndPV<THandler, TPosition, DEPTH> = algorithms.N(searcher, &handler, startpos);
-            let elapsed = s.elapsed();
-
-            let recalculated_eval = eval_from_line(&handler, startpos, result.1);
-            if recalculated_eval != result.0 {
-                println!(
-                    "{}",
-                    format!(
-                        "INDIVIDUAL MISMATCH (Alg: {}, Returned Eval: {:?}, Recalc Eval: {:?}, Returned Line: {:?})",
-                        algorithm_names[N],
-                        result.0,
-                        recalculated_eval,
-                        result.1,
-                    )
-                        .bright_red(),
-                );
-            }
-            results[N] = Some(result);
-
-            stats[N] += AlgorithmStats {
-                avg_leaves: (searcher.get_leaf_count() as f64) / (times as f64),
-                avg_ms: (elapsed.as_millis() as f64) / (times as f64),
-                avg_us: (elapsed.as_micros() as f64) / (times as f64),
-                avg_ns: (elapsed.as_nanos() as f64) / (times as f64),
-            };
-
-        });
-
-        let algorithms_match = results
-            .iter()
-            .skip(1)
-            .fold(
-                (results[0], true),
-                |(previous_result, all_match), &current_result| {
-                    (
-                        previous_result,
-                        all_match && previous_result == current_result,
-                    )
-                },
-            )
-            .1;
-
-        if !algorithms_match {
-            println!("{}", "ALGORITHM MISMATCH".bright_red());
-            for i in 0..6 {
-                println!("Alg: {}, Result: {:?}", algorithm_names[i], results[i]);
+    let records = run_average::<THandler, TPosition, DEPTH>(
+        searcher,
+        position_name,
+        times,
+        handler_params,
+        startpos_params,
+        |i, runs| {
+            if verbose {
+                println!("Iteration {}", i.to_string().bright_cyan());
             }
-        }
-
-        i += 1;
-    }
-
-    println!("{}", algorithm_names.into_iter().collect::<Vec<_>>().join(", "));
-
-    println!(
-        "{} [leaf]: {}",
-        position_name.bright_magenta(),
-        stats
-            .iter()
-            .map(|alg_stat| {
-                format!("{:.2}", alg_stat.avg_leaves)
-            })
-            .collect::<Vec<_>>()
-            .join(", ")
-            .bright_cyan()
+            report_mismatches(runs);
+        },
     );
 
-    println!(
-        "{} [ms]: {}",
-        position_name.bright_magenta(),
-        stats
-            .iter()
-            .map(|alg_stat| {
-                format!("{:.2}", alg_stat.avg_ms)
-            })
-            .collect::<Vec<_>>()
-            .join(", ")
-            .bright_cyan()
-    );
+    // The table columns are ordered differently to `ALGORITHM_NAMES`,
+    // so each column is given as an index into the averaged records.
+    let columns = [
+        (1, "AB"),
+        (0, "BB"),
+        (2, "PAB"),
+        (4, "Scout"),
+        (3, "PVS"),
+        (5, "SSS*"),
+        (6, "MTD(f)"),
+        (7, "DUAL*"),
+    ];
 
     println!(
-        "{} [us]: {}",
-        position_name.bright_magenta(),
-        stats
-            .iter()
-            .map(|alg_stat| {
-                format!("{:.2}", alg_stat.avg_us)
-            })
-            .collect::<Vec<_>>()
-            .join(", ")
-            .bright_cyan()
+        "{}",
+        columns.iter().map(|&(_, name)| name).collect::<Vec<_>>().join(", ")
     );
 
-    println!(
-        "{} [ns]: {}",
-        position_name.bright_magenta(),
-        stats
-            .iter()
-            .map(|alg_stat| {
-                format!("{:.2}", alg_stat.avg_ns)
-            })
-            .collect::<Vec<_>>()
-            .join(", ")
-            .bright_cyan()
-    );
+    let print_row = |unit: &str, field: fn(&StatsRecord) -> f64| {
+        println!(
+            "{} [{}]: {}",
+            position_name.bright_magenta(),
+            unit,
+            columns
+                .iter()
+                .map(|&(index, _)| format!("{:.2}", field(&records[index])))
+                .collect::<Vec<_>>()
+                .join(", ")
+                .bright_cyan()
+        );
+    };
 
-    searcher.reset_leaf_count();
+    print_row("leaf", |record| record.stats.avg_leaves);
+    print_row("ebf", |record| record.effective_branching_factor());
+    print_row("ms", |record| record.stats.avg_ms);
+    print_row("us", |record| record.stats.avg_us);
+    print_row("ns", |record| record.stats.avg_ns);
 }
 
 fn main() {
@@ -580,13 +242,19 @@ fn main() {
         (),
     );
 
-    test_algorithms_once::<Ut3Handler, Ut3Board, 6>(&mut searcher, "Ultimate Tic-Tac-Toe", (), ());
+    test_algorithms_once::<Ut3Handler, Ut3Board, 6>(
+        &mut searcher,
+        "Ultimate Tic-Tac-Toe",
+        Ut3Params::default(),
+        (),
+    );
     test_algorithms_once::<Uniform2bWideHandler, Uniform2bWidePos, 16>(
         &mut searcher,
         "Uniform Tree (Branching Factor = 2)",
         Uniform2bWideParams {
             depth: 16,
             seed: 314159,
+            distribution: LeafDistribution::Uniform,
         },
         (),
     );
@@ -630,30 +298,28 @@ fn main() {
                 depth: DEPTH_WIDTH_PAIRS[N].0,
                 width: DEPTH_WIDTH_PAIRS[N].1,
                 seed: 314159,
+                early_terminal: 0,
             },
             DEPTH_WIDTH_PAIRS[N].1,
         );
     });
 
-    seq!(N in 0..24 {
-        // Tests all 6 algorithms at once, averaging their results over different seeds
-        test_algorithms_average::<UnordIndHypTreeHandler, HypTreePos, { DEPTH_WIDTH_PAIRS[N].0 }>(
-            &mut searcher,
-            &format!("U({}, {})", DEPTH_WIDTH_PAIRS[N].1, DEPTH_WIDTH_PAIRS[N].0),
-            50,
-            (314159..314159 + 50)
-                .map(|seed| {
-                    HypTreeParams {
-                        depth: DEPTH_WIDTH_PAIRS[N].0,
-                        width: DEPTH_WIDTH_PAIRS[N].1,
-                        seed,
-                    }
-                })
-                .collect(),
-            DEPTH_WIDTH_PAIRS[N].1,
-            false,
-        );
-    });
+    // Tests all 6 algorithms at once, averaging their results over different seeds
+    let seeds = (314159..314159 + 50).collect::<Vec<u64>>();
+    let results = sweep(&mut searcher, &DEPTH_WIDTH_PAIRS, &seeds, 50)
+        .expect("DEPTH_WIDTH_PAIRS only has depths up to MAX_SWEEP_DEPTH");
+    for result in results {
+        if result.mismatches > 0 {
+            println!(
+                "{}",
+                format!("{} MISMATCHED ITERATIONS", result.mismatches).bright_red()
+            );
+        }
+        println!("{}", format!("U({}, {})", result.width, result.depth).bright_magenta());
+        for record in result.records {
+            println!("{}", record.to_console());
+        }
+    }
 
     test_algorithms_average::<UnordIndHypTreeHandler, HypTreePos, 8>(
         &mut searcher,
@@ -664,6 +330,7 @@ fn main() {
                 depth: 8,
                 width: 8,
                 seed,
+                early_terminal: 0,
             })
             .collect(),
         8,
@@ -671,7 +338,46 @@ fn main() {
     );
 
     println!("Perft(6) from chess start position");
-    Searcher::perft_div_parallel(6, ChessPos::startpos(()), &ChessHandler::new(()), true);
+    Searcher::perft_div_parallel(6, ChessPos::startpos(()), &ChessHandler::new(ChessParams::default()), true);
+
+    // A mate in 2 that `solve_tactic` finds through checks and captures alone:
+    // 1. Qb8+ Re8 2. Rxe8#
+    println!("Forced mate search on a mate in 2");
+    let chess_handler = ChessHandler::new(ChessParams::default());
+    let mate_in_two = ChessPos::from_fen("6k1/5ppp/4r3/8/8/8/5PPP/1Q2R1K1 w - - 0 1").unwrap();
+    searcher.reset_leaf_count();
+    let (eval, line) = searcher.solve_tactic::<3>(&chess_handler, mate_in_two, 3);
+    if eval == Centipawn::mate(3) {
+        println!("Forced mate {}", "FOUND".bright_green());
+    } else {
+        println!("Forced mate {}", "NOT FOUND".bright_red());
+    }
+    println!(
+        "Eval: {}, Line: {}, Leaf nodes evaluated: {}",
+        eval,
+        moves_display(&chess_handler, mate_in_two, line),
+        searcher.get_leaf_count()
+    );
+
+    // `find_mate` finds the shortest mate, here a mate in 3, and finds none in the start position.
+    for (name, fen, max_n) in [
+        ("mate in 3", "r5rk/5p1p/5R2/4B3/8/8/7P/7K w - - 0 1", 3),
+        ("start position", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 3),
+    ] {
+        let pos = ChessPos::from_fen(fen).unwrap();
+        match searcher.find_mate(&chess_handler, pos, max_n) {
+            Some(line) => {
+                let mut sans = Vec::new();
+                let mut current = pos;
+                for mv in line {
+                    sans.push(chess_handler.move_san(current, mv));
+                    current = current.play_move(mv);
+                }
+                println!("Mate search on {}: {}", name, sans.join(" ").bright_green());
+            }
+            None => println!("Mate search on {}: {}", name, "no mate".bright_yellow()),
+        }
+    }
 
     seq!(N in 0..24 {
         // Tests all 6 algorithms at once, averaging their results over different seeds
@@ -685,6 +391,7 @@ fn main() {
                         depth: DEPTH_WIDTH_PAIRS[N].0,
                         width: DEPTH_WIDTH_PAIRS[N].1,
                         seed,
+                        early_terminal: 0,
                     }
                 })
                 .collect(),
@@ -0,0 +1,138 @@
+use crate::games::chess::*;
+use crate::games::hypothetical_tree::*;
+use crate::games::stockman::*;
+use crate::games::transposition_tree::*;
+use crate::games::uniform_2b_wide::*;
+use crate::games::ut3::*;
+use crate::prelude::*;
+use crate::search::*;
+
+// A game whose legal move generation is checked by `perft` against known node counts.
+// The handler and start position are captured by `count`, so games with different
+// position and handler types can be kept in the same list.
+struct PerftEntry {
+    name: &'static str,
+    // The expected `perft` counts from the start position, starting at depth 1.
+    expected: Vec<u128>,
+    count: Box<dyn Fn(usize) -> u128>,
+}
+
+impl PerftEntry {
+    fn new<THandler, TPosition>(
+        name: &'static str,
+        handler: THandler,
+        startpos: TPosition,
+        expected: Vec<u128>,
+    ) -> Self
+    where
+        THandler: GameHandler<TPosition> + 'static,
+        TPosition: GamePosition + 'static,
+    {
+        Self {
+            name,
+            expected,
+            count: Box::new(move |depth| perft(depth, startpos, &handler)),
+        }
+    }
+
+    fn count(&self, depth: usize) -> u128 {
+        (self.count)(depth)
+    }
+}
+
+// A depth at which `perft` did not give the expected count.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct PerftMismatch {
+    name: &'static str,
+    depth: usize,
+    expected: u128,
+    counted: u128,
+}
+
+// The start position of every game in the crate with its hand-verified `perft` counts.
+// The tree games have every node at the same depth with the same number of moves,
+// so their counts are powers of the width.
+// The chess counts are the well-known counts of the standard start position.
+// Adding a new game only requires adding its entry here.
+fn perft_entries() -> Vec<PerftEntry> {
+    vec![
+        PerftEntry::new(
+            "Stockman",
+            StockmanHandler::new(()),
+            StockmanPos::startpos(()),
+            vec![2, 4, 8, 16, 0],
+        ),
+        PerftEntry::new(
+            "Uniform Tree (Branching Factor = 2)",
+            Uniform2bWideHandler::new(Uniform2bWideParams {
+                depth: 8,
+                seed: 314159,
+                distribution: LeafDistribution::Uniform,
+            }),
+            Uniform2bWidePos::startpos(()),
+            vec![2, 4, 8, 16, 32, 64, 128, 256],
+        ),
+        PerftEntry::new(
+            "Hypothetical Game Tree (Depth = 4, Width = 5)",
+            UnordIndHypTreeHandler::new(HypTreeParams {
+                depth: 4,
+                width: 5,
+                seed: 314159,
+                early_terminal: 0,
+            }),
+            HypTreePos::startpos(5),
+            vec![5, 25, 125, 625, 0],
+        ),
+        PerftEntry::new(
+            "Transposition Tree (Depth = 4, Width = 3, Density = 50)",
+            TranspTreeHandler::new(TranspTreeParams {
+                depth: 4,
+                width: 3,
+                density: 50,
+                seed: 314159,
+            }),
+            TranspTreePos::startpos((3, 50)),
+            vec![3, 9, 27, 81, 0],
+        ),
+        PerftEntry::new(
+            "Ultimate Tic-Tac-Toe",
+            Ut3Handler::new(Ut3Params::default()),
+            Ut3Board::startpos(()),
+            vec![81, 720, 6336],
+        ),
+        PerftEntry::new(
+            "Chess",
+            ChessHandler::new(ChessParams::default()),
+            ChessPos::startpos(()),
+            vec![20, 400, 8902, 197281],
+        ),
+    ]
+}
+
+// Runs `perft` at every depth with an expected count, returning each depth that disagrees.
+fn check_perft(entries: &[PerftEntry]) -> Vec<PerftMismatch> {
+    entries
+        .iter()
+        .flat_map(|entry| {
+            entry
+                .expected
+                .iter()
+                .enumerate()
+                .filter_map(move |(index, &expected)| {
+                    let depth = index + 1;
+                    let counted = entry.count(depth);
+                    (counted != expected).then_some(PerftMismatch {
+                        name: entry.name,
+                        depth,
+                        expected,
+                        counted,
+                    })
+                })
+        })
+        .collect()
+}
+
+#[test]
+fn every_game_matches_perft_counts() {
+    assert_eq!(check_perft(&perft_entries()), []);
+}
@@ -12,7 +12,8 @@
 // this trait definition forces `Copy` to be implemented.
 // Additionally, `Eq` is to enable functionalities such as verification of algorithm correctness
 // and for the utility of checking whether a position has already been searched, among others.
-pub trait GamePosition: Copy + std::fmt::Debug + Eq {
+// `Hash` is enforced so that game states can be looked up in tables, such as opening books.
+pub trait GamePosition: Copy + std::fmt::Debug + Eq + std::hash::Hash {
 
     // The transition from one game state to another is represented by a `Move`.
     // `Copy` is enforced to allow for game lines to be efficiently generated and recorded.
@@ -39,6 +40,42 @@ pub trait GamePosition: Copy + std::fmt::Debug + Eq {
 }
 
 
+// Game states that can be identified by a 64-bit key implement `ZobristKey`,
+// which allows algorithms that remember game states, such as those using transposition tables,
+// repetition detection or opening books, to be written generically over `GamePosition + ZobristKey`.
+// Equal game states must have equal keys, and unequal game states should have unequal keys
+// with high probability, though unlike `Eq` this is not guaranteed.
+// Games whose game states are already numbered uniquely can use that number as the key,
+// while others typically use Zobrist hashing, updated incrementally with each move.
+// Either way the key depends only on the game state, so it is the same across runs and builds,
+// which keeps tables that are saved to disk or seeded from the key usable.
+pub trait ZobristKey: GamePosition {
+    fn key(&self) -> u64;
+
+    // Plays a move as `play_move` does, also returning the key of the new game state
+    // given `key`, the key of this one, so that searches can carry the key down the game tree.
+    // By default the new key is computed from scratch, but games using Zobrist hashing
+    // override this to update the key from only the components that the move changes.
+    fn play_move_with_key(&self, _key: u64, mv: Self::Move) -> (Self, u64) {
+        let next = self.play_move(mv);
+        (next, next.key())
+    }
+}
+
+
+// Game states that can also be updated in-place, as described above, implement `MakeUnmake`.
+// `make_move` plays a move on the game state itself, with the same result as `play_move`,
+// and returns the information needed by `unmake_move` to restore the game state exactly as it was.
+// Moves must be unmade in the reverse order that they were made.
+pub trait MakeUnmake: GamePosition {
+    type Undo: Copy;
+
+    fn make_move(&mut self, mv: Self::Move) -> Self::Undo;
+
+    fn unmake_move(&mut self, undo: Self::Undo);
+}
+
+
 // The `GameHandler` trait is implemented by an object, which should not be copied or moved.
 // In game tree searching functions, the functionalities it provides should be accessed
 // through an immutable reference. Hence, neither `Clone` nor `Copy` is needed.
@@ -115,6 +152,50 @@ where
         pos: TPosition,
     ) -> impl Iterator<Item = <TPosition as GamePosition>::Move>;
 
+    // Appends all legal moves from a given game state to `buf`, in the same order as `get_legal_moves`.
+    // Callers that search many nodes can clear and reuse a single buffer,
+    // and implementations may override this to avoid intermediate allocations.
+    fn legal_moves_into(&self, pos: TPosition, buf: &mut Vec<<TPosition as GamePosition>::Move>) {
+        buf.extend(self.get_legal_moves(pos));
+    }
+
+    // Generates the legal moves from a given game state that make it unstable to evaluate,
+    // such as captures and promotions in Chess, which a quiescence search plays out
+    // before trusting the static evaluation. Every sequence of such moves must be finite.
+    // Games without such a concept have no loud moves, so a quiescence search only stands pat.
+    fn get_loud_moves(
+        &self,
+        _pos: TPosition,
+    ) -> impl Iterator<Item = <TPosition as GamePosition>::Move> {
+        std::iter::empty()
+    }
+
+    // Whether `mv` is a legal move in the given game state, such as for validating
+    // a move entered by a user. The move is assumed to be already decoded into a `Move`.
+    // By default this scans `get_legal_moves`, stopping once the move is found,
+    // but implementations may override it with a cheaper check of the single move.
+    fn is_legal(&self, pos: TPosition, mv: <TPosition as GamePosition>::Move) -> bool {
+        self.get_legal_moves(pos).any(|legal| legal == mv)
+    }
+
+    // Plays the moves of a game record in order from `startpos`, checking each with `is_legal`,
+    // and returns the final game state, or the index of the first illegal move in `moves`.
+    // Unlike `play_move`, which assumes the move is legal, this is suitable for untrusted input
+    // such as game records loaded from a file.
+    fn replay(
+        &self,
+        startpos: TPosition,
+        moves: &[<TPosition as GamePosition>::Move],
+    ) -> Result<TPosition, usize> {
+        moves.iter().enumerate().try_fold(startpos, |pos, (index, &mv)| {
+            if self.is_legal(pos, mv) {
+                Ok(pos.play_move(mv))
+            } else {
+                Err(index)
+            }
+        })
+    }
+
     // This function returns the static heuristic evaluation function for a given game state,
     // from the perspective of the player to move in the given position.
     // The parameter `max_depth` is the maximum number of plies currently being searched ahead in the game tree.
@@ -122,4 +203,291 @@ where
     // These values are required to be given to the `evaluate` function to allow for frameworks where
     // a quicker path to victory can be numerically represented as more favourable than a longer path to victory.
     fn evaluate(&self, pos: TPosition, depth: usize, max_depth: usize) -> Self::Eval;
+
+    // The evaluation of a game state with no legal moves, which searching algorithms call
+    // instead of `evaluate` once they have found that move generation is empty.
+    // This must agree with `evaluate` on such game states, but an implementation may
+    // use the knowledge that the game is over to skip work, such as Chess deciding between
+    // checkmate and stalemate without generating moves again.
+    // A leaf of a game with `plies_to_leaves` that is reached with plies left to search shows that
+    // the search is deeper than the tree, which fails in debug builds.
+    fn terminal_eval(&self, pos: TPosition, depth: usize, max_depth: usize) -> Self::Eval {
+        debug_assert!(
+            depth == 0 || self.plies_to_leaves(pos) != Some(0),
+            "search is {} plies deeper than the game tree",
+            depth
+        );
+        self.evaluate(pos, depth, max_depth)
+    }
+
+    // The number of plies from the given game state to the leaves of the game tree,
+    // for games such as the hypothetical game trees whose interior nodes have no static evaluation,
+    // so that `evaluate` may only be called on their leaves and terminal nodes.
+    // Searches that choose their own depths, namely `iterative_deepening`, `self_play`, `b_star`
+    // and internal iterative deepening in `alpha_beta_tt`, search to the leaves of such games.
+    // Games that can evaluate every game state return `None`.
+    fn plies_to_leaves(&self, _pos: TPosition) -> Option<usize> {
+        None
+    }
+
+    // Whether the given game state is drawn by repetition, having occurred `occurrences` times
+    // in the game so far and the line being searched, counting this occurrence.
+    // Searches that remember the game states before each node, such as `alpha_beta_history`,
+    // score such a game state with `draw_eval` instead of searching it further.
+    // Games whose game states cannot repeat never draw by repetition.
+    fn is_repetition_draw(&self, _pos: TPosition, _occurrences: usize) -> bool {
+        false
+    }
+
+    // The evaluation of a drawn game state, from the perspective of the player to move.
+    // Since `EVAL_MINIMUM == -EVAL_MAXIMUM`, their sum is the evaluation that is equal
+    // for both players, which is the default, but an implementation may bias it,
+    // such as by a contempt factor in Chess.
+    fn draw_eval(&self, _pos: TPosition, _depth: usize, _max_depth: usize) -> Self::Eval {
+        Self::EVAL_MINIMUM + Self::EVAL_MAXIMUM
+    }
+
+    // The player to move in the given game state, where `0` is the player to move at the start of the game
+    // and `1` is their opponent. All games implemented here are two-player games with alternating turns.
+    fn current_player(&self, pos: TPosition) -> usize;
+
+    // Whether the search should look one ply further than planned below this game state,
+    // such as when the player to move is in check in Chess, so that forcing sequences
+    // are not cut off at the search horizon. Games without such a concept never extend.
+    fn should_extend(&self, _pos: TPosition) -> bool {
+        false
+    }
+
+    // Whether `mv` is a quiet move in the given game state, such as a move in Chess
+    // that neither captures nor promotes. Killer moves are only recorded for quiet moves,
+    // since other moves are expected to be ordered on their own merits.
+    // Games without such a concept treat every move as quiet.
+    fn is_quiet_move(&self, _pos: TPosition, _mv: <TPosition as GamePosition>::Move) -> bool {
+        true
+    }
+
+    // Reorders `moves`, the legal moves of the given game state, so that the moves
+    // most likely to be best come first, which lets alpha-beta searches cut off sooner.
+    // Only the efficiency of a search depends on the ordering, never its evaluation.
+    // By default the moves are left in the order given by `get_legal_moves`.
+    fn order_moves(&self, _pos: TPosition, _moves: &mut Vec<<TPosition as GamePosition>::Move>) {}
+
+    // The probability, from `0.0` to `1.0`, that the player to move goes on to win
+    // from a game state evaluated as `eval`, for use by probabilistic searches and reporting.
+    // Equal evaluations for both players map to `0.5`, and winning and losing evaluations
+    // map to `1.0` and `0.0` respectively.
+    // Since `Eval` need not be numeric, the default implementation only looks at the sign of `eval`,
+    // and handlers with numeric evaluations should override it using `logistic`.
+    fn eval_to_win_prob(&self, eval: Self::Eval) -> f64 {
+        match eval.cmp(&-eval) {
+            std::cmp::Ordering::Greater => 1.0,
+            std::cmp::Ordering::Equal => 0.5,
+            std::cmp::Ordering::Less => 0.0,
+        }
+    }
+
+    // The static heuristic evaluation from the perspective of the first player,
+    // rather than the player to move, for use by callers that want an absolute score
+    // (such as a White-positive evaluation in Chess).
+    fn evaluate_absolute(&self, pos: TPosition, depth: usize, max_depth: usize) -> Self::Eval {
+        let eval = self.evaluate(pos, depth, max_depth);
+        if self.current_player(pos) == 0 {
+            eval
+        } else {
+            -eval
+        }
+    }
+
+    // A human-readable representation of `mv`, played from the game state `pos`,
+    // for printing principal variations of any game through a single interface.
+    // The game state is given for games such as Chess whose moves are stored
+    // relative to the player to move. By default this is the `Debug` format of the move.
+    fn format_move(&self, _pos: TPosition, mv: <TPosition as GamePosition>::Move) -> String {
+        format!("{:?}", mv)
+    }
+}
+
+
+// Handlers that can bound the true value of a game state implement `BoundedEvaluation`,
+// for use by selective searches such as B* which expand the game tree
+// until one move is proven better than all others.
+// The optimistic evaluation must be at least the pessimistic evaluation,
+// and the search can only be relied upon when the true value lies between them,
+// so there are no default bounds: a margin around `evaluate` rarely holds for a real game.
+pub trait BoundedEvaluation<TPosition>: GameHandler<TPosition>
+where
+    TPosition: GamePosition,
+{
+    fn evaluate_optimistic(&self, pos: TPosition, depth: usize, max_depth: usize) -> Self::Eval;
+
+    fn evaluate_pessimistic(&self, pos: TPosition, depth: usize, max_depth: usize) -> Self::Eval;
+}
+
+// The logistic curve used by `GameHandler::eval_to_win_prob`, mapping an evaluation `x`
+// to a probability in `[0, 1]`, such that an advantage of `scale` is ten times as likely
+// to win as to lose, as in the Elo rating system.
+pub fn logistic(x: f64, scale: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-x / scale))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::chess::*;
+    use crate::games::hypothetical_tree::*;
+    use crate::games::stockman::*;
+    use crate::games::transposition_tree::*;
+    use crate::games::uniform_2b_wide::*;
+    use crate::games::ut3::*;
+
+    // Checks `legal_moves_into` against `get_legal_moves` at every node down to `depth` plies,
+    // appending to a buffer that already holds the moves of the parent node.
+    fn check_legal_moves_into<THandler, TPosition>(
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        buf: &mut Vec<<TPosition as GamePosition>::Move>,
+    ) where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        let start = buf.len();
+        handler.legal_moves_into(pos, buf);
+        assert!(buf[start..] == handler.get_legal_moves(pos).collect::<Vec<_>>()[..]);
+        if depth > 0 {
+            for i in start..buf.len() {
+                let mut child_buf = buf.clone();
+                check_legal_moves_into(handler, pos.play_move(buf[i]), depth - 1, &mut child_buf);
+                assert!(child_buf[..buf.len()] == buf[..]);
+            }
+        }
+    }
+
+    #[test]
+    fn legal_moves_into_matches_iterator() {
+        let stockman = StockmanHandler::new(());
+        check_legal_moves_into(&stockman, StockmanPos::startpos(()), 4, &mut vec![]);
+        let uniform = Uniform2bWideHandler::new(Uniform2bWideParams {
+            depth: 4,
+            seed: 1,
+            distribution: LeafDistribution::Uniform,
+        });
+        check_legal_moves_into(&uniform, Uniform2bWidePos::startpos(()), 4, &mut vec![]);
+        let hyp_tree = UnordIndHypTreeHandler::new(HypTreeParams {
+            depth: 3,
+            width: 3,
+            seed: 1,
+            early_terminal: 30,
+        });
+        check_legal_moves_into(&hyp_tree, HypTreePos::startpos(3), 3, &mut vec![]);
+        let transp_tree = TranspTreeHandler::new(TranspTreeParams {
+            depth: 3,
+            width: 3,
+            density: 50,
+            seed: 1,
+        });
+        check_legal_moves_into(&transp_tree, TranspTreePos::startpos((3, 50)), 3, &mut vec![]);
+        let ut3 = Ut3Handler::new(Ut3Params::default());
+        check_legal_moves_into(&ut3, Ut3Board::startpos(()), 2, &mut vec![]);
+        let chess = ChessHandler::new(ChessParams::default());
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        check_legal_moves_into(&chess, ChessPos::from_fen(kiwipete).unwrap(), 1, &mut vec![]);
+        check_legal_moves_into(&chess, ChessPos::startpos(()), 2, &mut vec![]);
+    }
+
+    // Checks that `is_legal` accepts every legal move of `pos`, and rejects the moves of `other`
+    // that are not legal in `pos`, of which there must be at least one.
+    fn check_is_legal<THandler, TPosition>(handler: &THandler, pos: TPosition, other: TPosition)
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        let legal = handler.get_legal_moves(pos).collect::<Vec<_>>();
+        assert!(legal.iter().all(|&mv| handler.is_legal(pos, mv)));
+        let illegal = handler
+            .get_legal_moves(other)
+            .filter(|mv| !legal.contains(mv))
+            .collect::<Vec<_>>();
+        assert!(!illegal.is_empty());
+        assert!(illegal.iter().all(|&mv| !handler.is_legal(pos, mv)));
+    }
+
+    #[test]
+    fn is_legal_accepts_legal_and_rejects_illegal_moves() {
+        // Every interior node of the Stockman tree has the same two moves,
+        // so they are only illegal once a leaf is reached.
+        let stockman = StockmanHandler::new(());
+        check_is_legal(&stockman, StockmanPos { node: 16 }, StockmanPos::startpos(()));
+
+        let ut3 = Ut3Handler::new(Ut3Params::default());
+        let start = Ut3Board::startpos(());
+        let reply_pos = start.play_move(ut3.get_legal_moves(start).nth(40).unwrap());
+        check_is_legal(&ut3, reply_pos, start);
+
+        let chess = ChessHandler::new(ChessParams::default());
+        let start = ChessPos::startpos(());
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        check_is_legal(&chess, start, ChessPos::from_fen(kiwipete).unwrap());
+        // The bishop on e2 is pinned to its king, so moving it is only pseudo-legal,
+        // and a pawn cannot move three squares.
+        let pinned = ChessPos::from_fen("k3r3/8/8/8/8/8/4B3/4K3 w - - 0 1").unwrap();
+        let (e2, d3, e5) = (12, 19, 36);
+        assert!(!chess.is_legal(pinned, e2 | (d3 << 6)));
+        assert!(chess.is_legal(pinned, chess.move_from_uci(pinned, "e1d1").unwrap()));
+        assert!(!chess.is_legal(start, e2 | (e5 << 6)));
+        check_is_legal(&chess, pinned, start);
+    }
+
+    #[test]
+    fn replay_plays_valid_records_and_stops_at_illegal_moves() {
+        let chess = ChessHandler::new(ChessParams::default());
+        let start = ChessPos::startpos(());
+        let mut record = vec![];
+        let end = "e2e4 e7e5 g1f3 b8c6 f1b5".split_whitespace().fold(start, |pos, uci| {
+            let mv = chess.move_from_uci(pos, uci).unwrap();
+            record.push(mv);
+            pos.play_move(mv)
+        });
+        let expected = "r1bqkbnr/pppp1ppp/2n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R b KQkq - 3 3";
+        let replayed = chess.replay(start, &record).unwrap();
+        assert!(replayed == end);
+        assert_eq!(replayed.key(), ChessPos::from_fen(expected).unwrap().key());
+
+        // White's pawn has already left e2, so playing e2e4 again is illegal.
+        record.insert(4, record[0]);
+        assert!(chess.replay(start, &record) == Err(4));
+
+        // A cell of Ultimate Tic-Tac-Toe cannot be played twice.
+        let ut3 = Ut3Handler::new(Ut3Params::default());
+        let start = Ut3Board::startpos(());
+        let centre = ut3.get_legal_moves(start).nth(40).unwrap();
+        assert!(ut3.replay(start, &[]) == Ok(start));
+        assert!(ut3.replay(start, &[centre]) == Ok(start.play_move(centre)));
+        assert!(ut3.replay(start, &[centre, centre]) == Err(1));
+    }
+
+    #[test]
+    fn win_probabilities_are_symmetric() {
+        let chess = ChessHandler::new(ChessParams::default());
+        assert_eq!(chess.eval_to_win_prob(Centipawn(0)), 0.5);
+        let (ahead, behind) = (
+            chess.eval_to_win_prob(Centipawn(250)),
+            chess.eval_to_win_prob(Centipawn(-250)),
+        );
+        assert!(ahead > 0.5 && (ahead + behind - 1.0).abs() < 1e-12);
+        assert!(chess.eval_to_win_prob(ChessHandler::EVAL_MAXIMUM) > 0.999);
+        assert!(chess.eval_to_win_prob(Centipawn::mate(5)) > 0.999);
+        assert!(chess.eval_to_win_prob(Centipawn::mated(4)) < 0.001);
+
+        let ut3 = Ut3Handler::new(Ut3Params::default());
+        assert_eq!(ut3.eval_to_win_prob(0), 0.5);
+        assert!(ut3.eval_to_win_prob(Ut3Handler::EVAL_MAXIMUM) > 0.999);
+        assert!(ut3.eval_to_win_prob(Ut3Handler::EVAL_MINIMUM) < 0.001);
+
+        // Handlers without a scale only look at the sign of the evaluation.
+        let tree = StockmanHandler::new(());
+        assert_eq!(tree.eval_to_win_prob(0), 0.5);
+        assert_eq!(tree.eval_to_win_prob(StockmanHandler::EVAL_MAXIMUM), 1.0);
+        assert_eq!(tree.eval_to_win_prob(-3), 0.0);
+    }
 }
@@ -0,0 +1,203 @@
+use crate::games::chess::*;
+use crate::prelude::*;
+use crate::search::*;
+
+// Forward pruning options for `Searcher::alpha_beta_pruned`.
+// Both techniques are unsound: they skip moves based on the static evaluation alone,
+// so they can change the evaluation and principal variation that the search returns,
+// for instance by razoring away a quiet move that sets up a mate.
+// They are therefore both disabled by default, and each can be enabled independently.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PruningParams {
+    // Whether to skip quiet moves at frontier nodes (depth 1 or 2) when the static evaluation
+    // plus the margin for that depth cannot raise the evaluation above `alpha`.
+    pub futility: bool,
+    // The futility margins for nodes 1 and 2 plies from the horizon, in centipawns.
+    pub futility_margins: [i32; 2],
+    // Whether to drop into quiescence search at nodes at most `razor_depth` plies
+    // from the horizon when the static evaluation plus the margin is below `alpha`.
+    // Above depth 1, the node is only cut off if the quiescence search also fails low.
+    pub razoring: bool,
+    pub razor_depth: usize,
+    pub razor_margin: i32,
+}
+
+impl Default for PruningParams {
+    fn default() -> Self {
+        Self {
+            futility: false,
+            futility_margins: [200, 500],
+            razoring: false,
+            razor_depth: 1,
+            razor_margin: 300,
+        }
+    }
+}
+
+impl Searcher {
+    // Algorithm B for Chess with captures ordered by `ChessHandler::mvv_lva` before quiet moves,
+    // a `quiescence` search of captures and promotions at the horizon,
+    // and the optional forward pruning described by `PruningParams`. Nodes in check are never pruned.
+    // With both options disabled, this is a full-width search to `depth` followed by quiescence search.
+    pub fn alpha_beta_pruned<const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &ChessHandler,
+        pos: ChessPos,
+        depth: usize,
+        alpha: Centipawn,
+        beta: Centipawn,
+        params: &PruningParams,
+    ) -> EvalAndPV<ChessHandler, ChessPos, MAX_DEPTH> {
+        self.update_seldepth(MAX_DEPTH - depth);
+        if depth == 0 {
+            return (
+                self.quiescence(handler, pos, alpha, beta, MAX_DEPTH - depth),
+                [None; MAX_DEPTH],
+            );
+        }
+
+        let mut moves = handler.staged_moves(pos, None, [None; 2]).peekable();
+        if moves.peek().is_none() {
+            self.increment_leaf_count();
+            return (
+                handler.terminal_eval(pos, depth, MAX_DEPTH),
+                [None; MAX_DEPTH],
+            );
+        }
+
+        let in_check = handler.in_check(pos);
+        let static_eval = if !in_check && (params.futility || params.razoring) {
+            Some(handler.evaluate(pos, depth, MAX_DEPTH))
+        } else {
+            None
+        };
+
+        if let Some(static_eval) = static_eval.filter(|_| params.razoring) {
+            if depth <= params.razor_depth && static_eval + Centipawn(params.razor_margin) < alpha {
+                let eval = self.quiescence(handler, pos, alpha, beta, MAX_DEPTH - depth);
+                if depth == 1 || eval <= alpha {
+                    return (eval, [None; MAX_DEPTH]);
+                }
+            }
+        }
+
+        let futile = static_eval.is_some_and(|static_eval| {
+            params.futility
+                && depth <= params.futility_margins.len()
+                && static_eval + Centipawn(params.futility_margins[depth - 1]) <= alpha
+        });
+
+        let mut m = alpha;
+        let mut pv = [None; MAX_DEPTH];
+
+        for mv in moves {
+            let child = pos.play_move(mv);
+            if futile
+                && !handler.is_capture(pos, mv)
+                && !handler.is_promotion(mv)
+                && !handler.in_check(child)
+            {
+                continue;
+            }
+
+            let (t, mut line) =
+                self.alpha_beta_pruned::<MAX_DEPTH>(handler, child, depth - 1, -beta, -m, params);
+            let t = -t;
+            line[MAX_DEPTH - depth] = Some(mv);
+
+            if t > m {
+                m = t;
+                pv = line;
+            }
+
+            if m >= beta {
+                return (m, line);
+            }
+        }
+
+        (m, pv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Positions with a single winning move: winning a queen, a back-rank mate,
+    // winning a queen with a knight and Scholar's Mate.
+    const TACTICS: [(&str, &str); 4] = [
+        ("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", "d2d5"),
+        ("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1", "a1a8"),
+        ("6k1/8/8/8/8/5q2/8/4K1N1 w - - 0 1", "g1f3"),
+        (
+            "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+            "h5f7",
+        ),
+    ];
+
+    #[test]
+    fn forward_pruning_keeps_tactics_with_fewer_leaves() {
+        let handler = ChessHandler::new(ChessParams::default());
+        let pruning = PruningParams {
+            futility: true,
+            razoring: true,
+            razor_depth: 2,
+            ..Default::default()
+        };
+        let mut leaves = [0, 0];
+        for (fen, best) in TACTICS {
+            let pos = ChessPos::from_fen(fen).unwrap();
+            for (params, count) in [PruningParams::default(), pruning].iter().zip(&mut leaves) {
+                let mut searcher = Searcher::new();
+                let (_, line) = searcher.alpha_beta_pruned::<3>(
+                    &handler,
+                    pos,
+                    3,
+                    ChessHandler::EVAL_MINIMUM,
+                    ChessHandler::EVAL_MAXIMUM,
+                    params,
+                );
+                assert_eq!(handler.move_string(line[0].unwrap(), 0), best);
+                *count += searcher.get_leaf_count();
+            }
+        }
+        assert!(leaves[1] < leaves[0]);
+    }
+
+    #[test]
+    fn quiescence_plays_out_capture_sequences() {
+        let handler = ChessHandler::new(ChessParams::default());
+        let (min, max) = (ChessHandler::EVAL_MINIMUM, ChessHandler::EVAL_MAXIMUM);
+
+        // The pawn on d5 is defended, so only a search without quiescence takes it with the queen.
+        let pos = ChessPos::from_fen("4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1").unwrap();
+        let mut searcher = Searcher::new();
+        let (_, line) =
+            searcher.alpha_beta::<ChessHandler, ChessPos, 1>(&handler, pos, 1, min, max);
+        assert_eq!(handler.move_string(line[0].unwrap(), 0), "d2d5");
+        let params = PruningParams::default();
+        let (_, line) = searcher.alpha_beta_pruned::<1>(&handler, pos, 1, min, max, &params);
+        assert_ne!(handler.move_string(line[0].unwrap(), 0), "d2d5");
+        let capture = handler
+            .get_loud_moves(pos)
+            .find(|&mv| handler.move_string(mv, 0) == "d2d5")
+            .unwrap();
+        let after = pos.play_move(capture);
+        let eval = -searcher.quiescence(&handler, after, min, max, 1);
+        // After the pawn takes back, White is left a pawn down.
+        let recapture = handler.get_loud_moves(after).next().unwrap();
+        assert_eq!(handler.move_string(recapture, 1), "c6d5");
+        let exchanged = handler.evaluate(after.play_move(recapture), 0, 2);
+        assert_eq!(eval, exchanged);
+
+        // Scholar's Mate is a capture, found past the horizon and scored at its true distance.
+        let pos = ChessPos::from_fen(TACTICS[3].0).unwrap();
+        for ply in 0..4 {
+            let eval = searcher.quiescence(&handler, pos, min, max, ply);
+            assert_eq!(eval.mate_in(), Some(ply as i32 + 1));
+        }
+        // At the horizon of a longer line, the mate is counted from the root of that line.
+        let (eval, _) = searcher.alpha_beta_pruned::<3>(&handler, pos, 0, min, max, &params);
+        assert_eq!(eval, Centipawn::mate(4));
+    }
+}
@@ -1,9 +1,31 @@
+use crate::book::*;
+use crate::killers::*;
 use crate::prelude::*;
+use crate::tt::*;
 
+use rand::Rng;
+use rand_chacha::ChaChaRng;
 use rayon::prelude::*;
 
 use std::collections::BinaryHeap;
-use std::time::Instant;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Internal iterative deepening in `alpha_beta_tt` is only applied to nodes at least this deep,
+// and searches them this many plies shallower to find a move to try first.
+pub const IID_MIN_DEPTH: usize = 3;
+pub const IID_REDUCTION: usize = 2;
+
+// The number of buckets in the histogram of cutoffs kept by `Searcher`.
+// Bucket `i` counts the cutoffs made by the move at index `i` of a node's move ordering,
+// except that the last bucket also counts the cutoffs made by every later move.
+pub const CUTOFF_BUCKETS: usize = 8;
+
+// The number of interior nodes `alpha_beta_timed` visits between readings of the clock,
+// since reading it at every node would slow the search down noticeably.
+pub const DEADLINE_POLL_INTERVAL: u32 = 1024;
 
 // Return type of all searching algorithms,
 // consisting of the calculated heuristic evaluation of the position
@@ -13,6 +35,289 @@ pub type EvalAndPV<THandler, TPosition, const SIZE: usize> = (
     [Option<<TPosition as GamePosition>::Move>; SIZE],
 );
 
+// Return type of the runtime-depth variants of the searching algorithms, such as `alpha_beta_dyn`,
+// where the principal variation is only as long as the line actually searched.
+pub type EvalAndLine<THandler, TPosition> = (
+    <THandler as GameHandler<TPosition>>::Eval,
+    Vec<<TPosition as GamePosition>::Move>,
+);
+
+// Root moves paired with the lines that refuted them during a null-window search,
+// as returned by `pvs_with_refutations`.
+pub type Refutations<TPosition, const SIZE: usize> = Vec<(
+    <TPosition as GamePosition>::Move,
+    [Option<<TPosition as GamePosition>::Move>; SIZE],
+)>;
+
+// Root moves paired with their exact evaluations from the perspective of the root player,
+// as returned by `root_scores` and `pvs_analysis`.
+pub type RootScores<THandler, TPosition> = Vec<(
+    <TPosition as GamePosition>::Move,
+    <THandler as GameHandler<TPosition>>::Eval,
+)>;
+
+// A triangular principal variation table, where the row for each ply holds the line
+// from that ply onwards, and `lengths` records how many moves of each row are in use.
+// A node prepends its move to the row of the next ply by copying only the moves of that line,
+// instead of copying an entire array of `SIZE` moves at every improvement.
+// The table is owned by the caller of `alpha_beta_triangular`, so it is allocated once per search.
+#[derive(Clone, Debug)]
+pub struct TriangularPV<TMove: Copy, const SIZE: usize> {
+    lengths: [usize; SIZE],
+    rows: [[Option<TMove>; SIZE]; SIZE],
+}
+
+impl<TMove: Copy, const SIZE: usize> Default for TriangularPV<TMove, SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<TMove: Copy, const SIZE: usize> TriangularPV<TMove, SIZE> {
+    pub fn new() -> Self {
+        Self {
+            lengths: [0; SIZE],
+            rows: [[None; SIZE]; SIZE],
+        }
+    }
+
+    // Empties the line from `ply` onwards.
+    fn clear(&mut self, ply: usize) {
+        if ply < SIZE {
+            self.lengths[ply] = 0;
+        }
+    }
+
+    // Makes the line from `ply` onwards `mv` followed by the line recorded for `ply + 1`.
+    fn update(&mut self, ply: usize, mv: TMove) {
+        let child_length = if ply + 1 < SIZE {
+            self.lengths[ply + 1]
+        } else {
+            0
+        };
+        let (parents, children) = self.rows.split_at_mut(ply + 1);
+        let row = &mut parents[ply];
+        row[ply] = Some(mv);
+        if let Some(child) = children.first() {
+            row[ply + 1..ply + 1 + child_length]
+                .copy_from_slice(&child[ply + 1..ply + 1 + child_length]);
+        }
+        self.lengths[ply] = child_length + 1;
+    }
+
+    pub fn len(&self) -> usize {
+        if SIZE == 0 {
+            0
+        } else {
+            self.lengths[0]
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // The moves of the line found from the root, in the order they are played.
+    pub fn iter(&self) -> impl Iterator<Item = TMove> + '_ {
+        let length = self.len();
+        self.rows.first().into_iter().flat_map(move |row| row[..length].iter().flatten().copied())
+    }
+
+    // Converts the line from the root to the array-based representation used by `EvalAndPV`,
+    // with the line starting at the first index and unused entries left as `None`.
+    pub fn to_array(&self) -> [Option<TMove>; SIZE] {
+        let mut array = [None; SIZE];
+        for (entry, mv) in array.iter_mut().zip(self.iter()) {
+            *entry = Some(mv);
+        }
+        array
+    }
+}
+
+// Return type of the `open` list search behind `sss` and `sss_capped`,
+// consisting of the evaluation and principal variation if the root was solved,
+// and the highest merit of any state spilled from the `open` list.
+type OpenListResult<THandler, TPosition, const SIZE: usize> = (
+    Option<EvalAndPV<THandler, TPosition, SIZE>>,
+    Option<<THandler as GameHandler<TPosition>>::Eval>,
+);
+
+// Replays the line from the initial position and evaluates the resulting position,
+// negating if the side to move has changed, so that the result can be compared
+// with the evaluation an algorithm returned alongside its principal variation.
+// The side to move is compared through `current_player` rather than the parity of the line,
+// so that the root is the maximising side whichever player moves first there,
+// such as in a Chess position set up with Black to move.
+pub fn eval_from_line<THandler, TPosition, const SIZE: usize>(
+    handler: &THandler,
+    initial_pos: TPosition,
+    line: [Option<<TPosition as GamePosition>::Move>; SIZE],
+) -> <THandler as GameHandler<TPosition>>::Eval
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    let mut pos = initial_pos;
+    let mut depth = 0;
+    for &mv in line.iter() {
+        if let Some(m) = mv {
+            pos = pos.play_move(m);
+            depth += 1;
+        }
+    }
+    if handler.current_player(pos) == handler.current_player(initial_pos) {
+        handler.evaluate(pos, SIZE - depth, SIZE)
+    } else {
+        -handler.evaluate(pos, SIZE - depth, SIZE)
+    }
+}
+
+// Describes how the evaluation a search returned differs from
+// the evaluation obtained by replaying the principal variation it returned.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mismatch<TEval> {
+    pub returned_eval: TEval,
+    pub recalculated_eval: TEval,
+    // Number of moves in the line before it was terminated by `None`.
+    pub line_length: usize,
+}
+
+// The correctness check applied to every search result in the driver program:
+// replaying the returned line from `initial_pos` must reproduce the returned evaluation.
+pub fn verify_line<THandler, TPosition, const SIZE: usize>(
+    handler: &THandler,
+    initial_pos: TPosition,
+    result: EvalAndPV<THandler, TPosition, SIZE>,
+) -> Result<(), Mismatch<<THandler as GameHandler<TPosition>>::Eval>>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    let recalculated_eval = eval_from_line(handler, initial_pos, result.1);
+    if recalculated_eval == result.0 {
+        Ok(())
+    } else {
+        Err(Mismatch {
+            returned_eval: result.0,
+            recalculated_eval,
+            line_length: result.1.iter().take_while(|mv| mv.is_some()).count(),
+        })
+    }
+}
+
+// The check of `verify_line`, made inside the searches themselves in debug builds
+// so that a move written to the wrong index of a principal variation is caught immediately.
+// It is only made on results of the root, and only when the evaluation is exact,
+// being strictly inside the window `(alpha, beta)`, since a line that failed low or high
+// need not lead to a game state with the returned evaluation.
+fn debug_assert_line<THandler, TPosition, const SIZE: usize>(
+    handler: &THandler,
+    initial_pos: TPosition,
+    alpha: <THandler as GameHandler<TPosition>>::Eval,
+    beta: <THandler as GameHandler<TPosition>>::Eval,
+    result: EvalAndPV<THandler, TPosition, SIZE>,
+) where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    if cfg!(debug_assertions) && alpha < result.0 && result.0 < beta {
+        if let Err(mismatch) = verify_line(handler, initial_pos, result) {
+            panic!("Principal variation does not reproduce its evaluation: {:?}", mismatch);
+        }
+    }
+}
+
+// The window of an aspiration search around `guess`, after the search has failed low
+// `fails_low` times and failed high `fails_high` times.
+// Each side of the window starts `window` away from the guess, is widened to twice that
+// after the first fail on that side, and is opened to `EVAL_MINIMUM` or `EVAL_MAXIMUM`
+// after the second.
+// The window is clipped to the range of evaluations without overflowing.
+fn aspiration_window<THandler, TPosition>(
+    guess: <THandler as GameHandler<TPosition>>::Eval,
+    window: <THandler as GameHandler<TPosition>>::Eval,
+    fails_low: usize,
+    fails_high: usize,
+) -> (
+    <THandler as GameHandler<TPosition>>::Eval,
+    <THandler as GameHandler<TPosition>>::Eval,
+)
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    let minimum = <THandler as GameHandler<TPosition>>::EVAL_MINIMUM;
+    let maximum = <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM;
+    let guess = guess.clamp(minimum, maximum);
+    let width = |fails| match fails {
+        0 => Some(window),
+        1 if window <= maximum - window => Some(window + window),
+        _ => None,
+    };
+    let alpha = match width(fails_low) {
+        Some(width) if guess >= minimum + width => guess - width,
+        _ => minimum,
+    };
+    let beta = match width(fails_high) {
+        Some(width) if guess <= maximum - width => guess + width,
+        _ => maximum,
+    };
+    (alpha, beta)
+}
+
+// Utility functions for testing legal move generation and calculating
+// the total number of leaf nodes in a maximal tree of a given depth.
+// The terminology of `perft` is borrowed from the functionality of chess engines
+// that carries out this functionality, commonly used for legal move generation debugging.
+// These do not evaluate any positions, so they need no `Searcher`.
+pub fn perft<THandler, TPosition>(depth: usize, pos: TPosition, handler: &THandler) -> u128
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    match depth {
+        0 => 1,
+        1 => handler.get_legal_moves(pos).count() as u128,
+        _ => handler
+            .get_legal_moves(pos)
+            .map(|mv| perft(depth - 1, pos.play_move(mv), handler))
+            .sum(),
+    }
+}
+
+// The `perft` count below each legal move of `pos`, in the order the moves are generated,
+// which sum to `perft(depth, pos, handler)` for any positive depth.
+// Comparing these counts against those of a reference implementation
+// narrows down which move leads to a move generation error.
+pub fn perft_divide<THandler, TPosition>(
+    depth: usize,
+    pos: TPosition,
+    handler: &THandler,
+) -> Vec<(<TPosition as GamePosition>::Move, u128)>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    handler
+        .get_legal_moves(pos)
+        .map(|mv| (mv, perft(depth.saturating_sub(1), pos.play_move(mv), handler)))
+        .collect()
+}
+
+// The callback given to `Searcher::set_info_callback`, which receives the evaluations
+// and moves of one game. Since `Searcher` is not tied to any one game,
+// it is stored without its type, and kept behind a `Mutex` so that a `Searcher` holding one
+// can still be shared between threads.
+pub type InfoCallback<TEval, TMove> = Box<dyn FnMut(SearchInfo<TEval, TMove>) + Send>;
+
+// The callback given to `Searcher::set_progress_callback`, called every `interval` leaves
+// with the time elapsed since it was registered.
+struct ProgressReporter {
+    interval: u64,
+    start: Instant,
+    callback: Mutex<Box<dyn FnMut(SearchProgress) + Send>>,
+}
+
 // To enable the counting of leaf node evaluation,
 // we implement all searching algorithms as member functions
 // of a `Searcher` object, which separates the need for counting
@@ -20,8 +325,87 @@ pub type EvalAndPV<THandler, TPosition, const SIZE: usize> = (
 // and also satisfying the borrow checker.
 // This construct is required as legal move generation returns an `Iterator`,
 // which would require an immutable borrow of the `GameHandler`.
+// `Searcher` is `Sync`, so the threads of a parallel search can share one to count leaves.
 pub struct Searcher {
-    leaf_count: u128,
+    // The leaf count can be incremented through a shared reference by several threads at once,
+    // and is shared with the searchers given to worker threads by `worker`.
+    // There is no stable 128-bit atomic integer, so it is kept in 64 bits,
+    // which would take centuries to overflow at any realistic rate of evaluation.
+    leaf_count: Arc<AtomicU64>,
+    seldepth: usize,
+    cutoffs: [u128; CUTOFF_BUCKETS],
+    info_callback: Option<Mutex<Box<dyn std::any::Any + Send>>>,
+    progress: Option<Arc<ProgressReporter>>,
+    // The wall time of the last search that records it, as listed at `SearchStats`,
+    // and the leaves it evaluated.
+    search_elapsed: Duration,
+    search_leaves: u128,
+    // The killer moves of `alpha_beta_ordered`, stored without their type
+    // since `Searcher` is not tied to any one game,
+    // along with the `MAX_DEPTH` of the searches that recorded them.
+    killers: Option<(usize, Box<dyn std::any::Any + Send + Sync>)>,
+    // The interior nodes visited by `alpha_beta_timed` since it last read the clock,
+    // and whether it has found its deadline to have passed, after which every node returns at once.
+    nodes_since_poll: u32,
+    aborted: bool,
+    // The leaf count at which `alpha_beta_limited`, `alpha_beta_timed` and `pvs_limited`
+    // stop searching, if any.
+    node_limit: Option<u128>,
+    // The keys of the game states leading to the node being searched by `alpha_beta_history`,
+    // starting with those played before the root, as given to `set_history`.
+    key_history: Vec<u64>,
+}
+
+// A snapshot of the counters kept by a `Searcher`.
+// `elapsed` and `nps` describe the last search run by `alpha_beta_dyn`, `pvs_dyn`,
+// `iterative_deepening`, `iterative_deepening_aspiration` or `alpha_beta_timed`,
+// which time themselves from their root call, and are zero if there has been none.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SearchStats {
+    pub leaf_count: u128,
+    pub seldepth: usize,
+    pub cutoffs: [u128; CUTOFF_BUCKETS],
+    pub elapsed: Duration,
+    pub nps: f64,
+}
+
+impl SearchStats {
+    // The fraction of cutoffs made by the first move tried, which is the usual measure
+    // of how good the move ordering is, since the minimal game tree is searched
+    // exactly when every cutoff is made by the first move. This is `0.0` if there were no cutoffs.
+    pub fn first_move_cutoff_rate(&self) -> f64 {
+        let total: u128 = self.cutoffs.iter().sum();
+        if total == 0 {
+            0.0
+        } else {
+            self.cutoffs[0] as f64 / total as f64
+        }
+    }
+}
+
+// Progress of a search, as passed to the callback given to `Searcher::set_info_callback`
+// each time a search to a given depth is completed, with the score and best move
+// in the evaluation and move types of the game being searched.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchInfo<TEval, TMove> {
+    pub depth: usize,
+    pub seldepth: usize,
+    pub score: TEval,
+    pub best_move: Option<TMove>,
+    // The number of leaves evaluated by this search, and the rate at which they were evaluated.
+    pub nodes: u128,
+    pub nps: f64,
+    pub elapsed: Duration,
+}
+
+// Progress of a search in between completed depths, as passed to the callback given to
+// `Searcher::set_progress_callback` whenever the leaf count reaches a multiple of its interval.
+// Nothing is known about the best move until a depth is completed, so only the leaf count
+// is reported, with the time elapsed since the callback was registered.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchProgress {
+    pub nodes: u128,
+    pub elapsed: Duration,
 }
 
 // Suggestion from #[warn(clippy::new_without_default)]
@@ -33,46 +417,256 @@ impl Default for Searcher {
 
 impl Searcher {
     // The only internal state of `Searcher` that gets mutated incrementally
-    // as an algorithm runs is the number of leaf nodes evaluated to this point.
+    // as an algorithm runs is the number of leaf nodes evaluated to this point,
+    // the selective depth reached and the histogram of cutoffs made.
     pub fn new() -> Self {
-        Self { leaf_count: 0 }
+        Self {
+            leaf_count: Arc::new(AtomicU64::new(0)),
+            seldepth: 0,
+            cutoffs: [0; CUTOFF_BUCKETS],
+            info_callback: None,
+            progress: None,
+            search_elapsed: Duration::ZERO,
+            search_leaves: 0,
+            killers: None,
+            nodes_since_poll: 0,
+            aborted: false,
+            node_limit: None,
+            key_history: Vec::new(),
+        }
+    }
+
+    // A new searcher for a worker thread of a parallel search, which counts its leaves
+    // in the leaf count of this searcher and reports them to its progress callback,
+    // so that the count is exact once the workers have finished.
+    // Nothing else is shared, so the worker starts without a table, killer moves or limits.
+    fn worker(&self) -> Self {
+        Self {
+            leaf_count: Arc::clone(&self.leaf_count),
+            progress: self.progress.clone(),
+            ..Self::new()
+        }
+    }
+
+    // Discards the killer moves recorded by `alpha_beta_ordered`,
+    // which are otherwise kept from one search to the next.
+    pub fn clear_killers(&mut self) {
+        self.killers = None;
     }
 
     // Functions for the algorithms to increment the `leaf_count`
     // and for the user to reset this count in between separate algorithm calls.
-    pub fn increment_leaf_count(&mut self) {
-        self.leaf_count += 1;
+    // The count is only used as a tally, so no ordering with other memory is needed,
+    // and a count read after the threads incrementing it have been joined is exact.
+    pub fn increment_leaf_count(&self) {
+        self.count_leaves(1);
+    }
+
+    // Adds the leaves counted elsewhere, such as the evaluations made in parallel by `sss_parallel`.
+    // A count too large for the 64 bits of the leaf count saturates instead of being truncated.
+    pub fn add_leaf_count(&self, leaves: u128) {
+        self.count_leaves(u64::try_from(leaves).unwrap_or(u64::MAX));
+    }
+
+    // Adds to the leaf count, calling the progress callback if the count passes
+    // a multiple of its interval.
+    fn count_leaves(&self, leaves: u64) {
+        let previous = self.leaf_count.fetch_add(leaves, Ordering::Relaxed);
+        if let Some(progress) = &self.progress {
+            let count = previous.saturating_add(leaves);
+            if count / progress.interval != previous / progress.interval {
+                let mut callback = progress
+                    .callback
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner);
+                callback(SearchProgress {
+                    nodes: count as u128,
+                    elapsed: progress.start.elapsed(),
+                });
+            }
+        }
     }
 
     pub fn get_leaf_count(&self) -> u128 {
-        self.leaf_count
+        self.leaf_count.load(Ordering::Relaxed) as u128
     }
 
-    pub fn reset_leaf_count(&mut self) {
-        self.leaf_count = 0;
+    pub fn reset_leaf_count(&self) {
+        self.leaf_count.store(0, Ordering::Relaxed);
     }
 
-    // Utility functions for testing legal move generation and calculating
-    // the total number of leaf nodes in a maximal tree of a given depth.
-    // The terminology of `perft` is borrowed from the functionality of chess engines
-    // that carries out this functionality, commonly used for legal move generation debugging.
-    // Since many game trees are very large in size, we give parallel implementations as well,
-    // with the side effect that verbose parallel options do not have a move printing order guarantee.
-    fn perft<THandler, TPosition>(depth: usize, pos: TPosition, handler: &THandler) -> u128
+    // Stops `alpha_beta_limited`, `alpha_beta_timed` and `pvs_limited` once the leaf count
+    // reaches `limit`, after which each node returns the best line it has completed,
+    // or the line of the move it was searching if it has not completed any.
+    // The limit applies to the leaf count itself, which includes the leaves of earlier searches
+    // unless it is reset in between. `None` removes the limit.
+    pub fn set_node_limit(&mut self, limit: Option<u128>) {
+        self.node_limit = limit;
+    }
+
+    // Whether the leaf count has reached the limit set by `set_node_limit`,
+    // in which case the evaluation of the last search should not be trusted.
+    pub fn node_limit_reached(&self) -> bool {
+        matches!(self.node_limit, Some(limit) if self.get_leaf_count() >= limit)
+    }
+
+    // Records the game states of a game played from `startpos` through `moves`, which are assumed
+    // to be legal, so that `alpha_beta_history` recognises repetitions of game states played
+    // before its root. The game state reached is returned, which is the root to search from.
+    pub fn set_history<TPosition>(
+        &mut self,
+        startpos: TPosition,
+        moves: &[<TPosition as GamePosition>::Move],
+    ) -> TPosition
     where
-        THandler: GameHandler<TPosition>,
-        TPosition: GamePosition,
+        TPosition: GamePosition + ZobristKey,
     {
-        if depth == 1 {
-            handler.get_legal_moves(pos).count() as u128
+        self.key_history.clear();
+        moves.iter().fold(startpos, |pos, &mv| {
+            self.key_history.push(pos.key());
+            pos.play_move(mv)
+        })
+    }
+
+    // Forgets the game given to `set_history`, such as before searching an unrelated game.
+    pub fn clear_history(&mut self) {
+        self.key_history.clear();
+    }
+
+    // The selective depth is the greatest number of plies from the root of any node visited,
+    // which exceeds the nominal depth when a search extends lines or enters quiescence search.
+    // Only the algorithms which can search past their nominal depth record it,
+    // namely `alpha_beta_ext`, `alpha_beta_pruned` and `quiescence`.
+    pub fn update_seldepth(&mut self, ply: usize) {
+        self.seldepth = self.seldepth.max(ply);
+    }
+
+    pub fn get_seldepth(&self) -> usize {
+        self.seldepth
+    }
+
+    pub fn reset_seldepth(&mut self) {
+        self.seldepth = 0;
+    }
+
+    // Records a beta cutoff made by the move at `move_index` of a node's move ordering,
+    // in the histogram described at `CUTOFF_BUCKETS`.
+    // Only the algorithms which order their moves record cutoffs, namely `alpha_beta`
+    // and `alpha_beta_timed` (in the order the moves are generated),
+    // `alpha_beta_tt` and `alpha_beta_ordered`.
+    pub fn record_cutoff(&mut self, move_index: usize) {
+        self.cutoffs[move_index.min(CUTOFF_BUCKETS - 1)] += 1;
+    }
+
+    pub fn get_cutoffs(&self) -> [u128; CUTOFF_BUCKETS] {
+        self.cutoffs
+    }
+
+    pub fn reset_cutoffs(&mut self) {
+        self.cutoffs = [0; CUTOFF_BUCKETS];
+    }
+
+    pub fn stats(&self) -> SearchStats {
+        let nps = if self.search_elapsed.is_zero() {
+            0.0
         } else {
-            handler
-                .get_legal_moves(pos)
-                .map(|mv| Self::perft(depth - 1, pos.play_move(mv), handler))
-                .sum()
+            self.search_leaves as f64 / self.search_elapsed.as_secs_f64()
+        };
+        SearchStats {
+            leaf_count: self.get_leaf_count(),
+            seldepth: self.seldepth,
+            cutoffs: self.cutoffs,
+            elapsed: self.search_elapsed,
+            nps,
+        }
+    }
+
+    // Records the wall time since `start` and the leaves evaluated since the leaf count was
+    // `leaves`, to be reported by `stats` as `elapsed` and `nps`, and returns them.
+    // Searches call this from their root call only, so the search itself is not slowed down.
+    fn record_search_time(&mut self, start: Instant, leaves: u128) -> (u128, Duration) {
+        let elapsed = start.elapsed();
+        // If the leaf count was reset during the search, it only holds leaves evaluated since.
+        let leaf_count = self.get_leaf_count();
+        let nodes = leaf_count.checked_sub(leaves).unwrap_or(leaf_count);
+        (self.search_leaves, self.search_elapsed) = (nodes, elapsed);
+        (nodes, elapsed)
+    }
+
+    // Registers a callback to be given a `SearchInfo` whenever a search reports its progress.
+    // The callback is owned by the `Searcher`, so it cannot borrow from the caller's stack,
+    // but it can share state with the caller through types such as `Arc<Mutex<_>>`.
+    // Only searches of games whose evaluation and move types are `TEval` and `TMove` report to it.
+    pub fn set_info_callback<TEval, TMove>(&mut self, callback: InfoCallback<TEval, TMove>)
+    where
+        TEval: 'static,
+        TMove: 'static,
+    {
+        self.info_callback = Some(Mutex::new(Box::new(callback)));
+    }
+
+    pub fn clear_info_callback(&mut self) {
+        self.info_callback = None;
+    }
+
+    // Registers a callback to be given a `SearchProgress` every `interval` leaves,
+    // so that long searches report while a depth is still being searched.
+    // Unlike the info callback, it is called from within the search by every algorithm.
+    pub fn set_progress_callback(
+        &mut self,
+        interval: u64,
+        callback: Box<dyn FnMut(SearchProgress) + Send>,
+    ) {
+        self.progress = Some(Arc::new(ProgressReporter {
+            interval: interval.max(1),
+            start: Instant::now(),
+            callback: Mutex::new(callback),
+        }));
+    }
+
+    pub fn clear_progress_callback(&mut self) {
+        self.progress = None;
+    }
+
+    // Passes the result of a completed search to the info callback, if there is one
+    // for this evaluation and move type,
+    // where `nodes` leaves were evaluated in the `elapsed` time since the search started.
+    pub fn report_info<TEval, TMove>(
+        &mut self,
+        depth: usize,
+        score: TEval,
+        best_move: Option<TMove>,
+        nodes: u128,
+        elapsed: Duration,
+    ) where
+        TEval: 'static,
+        TMove: 'static,
+    {
+        let seldepth = self.seldepth.max(depth);
+        let callback = self
+            .info_callback
+            .as_mut()
+            .and_then(|c| {
+                c.get_mut()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner)
+                    .downcast_mut::<InfoCallback<TEval, TMove>>()
+            });
+        if let Some(callback) = callback {
+            callback(SearchInfo {
+                depth,
+                seldepth,
+                score,
+                best_move,
+                nodes,
+                nps: nodes as f64 / elapsed.as_secs_f64().max(f64::MIN_POSITIVE),
+                elapsed,
+            });
         }
     }
 
+    // Printing versions of `perft_divide`, which also report the time taken.
+    // Since many game trees are very large in size, we give parallel implementations as well,
+    // with the side effect that verbose parallel options do not have a move printing order guarantee.
     pub fn perft_div_serial<THandler, TPosition>(
         depth: usize,
         pos: TPosition,
@@ -96,7 +690,7 @@ impl Searcher {
             handler
                 .get_legal_moves(pos)
                 .map(|mv| {
-                    let num = Self::perft(depth - 1, pos.play_move(mv), handler);
+                    let num = perft(depth - 1, pos.play_move(mv), handler);
                     println!("{:?}: {num}", mv);
                     num
                 })
@@ -104,7 +698,7 @@ impl Searcher {
         } else {
             handler
                 .get_legal_moves(pos)
-                .map(|mv| Self::perft(depth - 1, pos.play_move(mv), handler))
+                .map(|mv| perft(depth - 1, pos.play_move(mv), handler))
                 .sum()
         };
         println!("Nodes searched: {sum}");
@@ -139,7 +733,7 @@ impl Searcher {
                 .collect::<Vec<_>>()
                 .par_iter()
                 .map(|&mv| {
-                    let num = Self::perft(depth - 1, pos.play_move(mv), handler);
+                    let num = perft(depth - 1, pos.play_move(mv), handler);
                     println!("{:?}: {num}", mv);
                     num
                 })
@@ -149,15 +743,201 @@ impl Searcher {
                 .get_legal_moves(pos)
                 .collect::<Vec<_>>()
                 .par_iter()
-                .map(|&mv| Self::perft(depth - 1, pos.play_move(mv), handler))
+                .map(|&mv| perft(depth - 1, pos.play_move(mv), handler))
                 .sum()
         };
         println!("Nodes searched: {sum}");
         println!("Time elapsed {} ms", s.elapsed().as_millis());
     }
 
+    // Plays uniformly random legal moves from `pos` until a terminal node is reached,
+    // and returns its evaluation from the perspective of the player to move in `pos`.
+    // The terminal node is evaluated as a leaf of a search as deep as the playout was long.
+    // This is the building block of Monte Carlo methods, and can also be used
+    // to sample the distribution of leaf values in a game tree.
+    // Since Chess here has no fifty-move or repetition rule, playouts of Chess may never terminate.
+    pub fn random_playout<THandler, TPosition>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        rng: &mut ChaChaRng,
+    ) -> <THandler as GameHandler<TPosition>>::Eval
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        let mut pos = pos;
+        let mut plies = 0;
+        let mut moves = Vec::new();
+        loop {
+            moves.clear();
+            handler.legal_moves_into(pos, &mut moves);
+            if moves.is_empty() {
+                break;
+            }
+            pos = pos.play_move(moves[rng.gen_range(0..moves.len())]);
+            plies += 1;
+        }
+        self.increment_leaf_count();
+        if (plies & 1) == 0 {
+            handler.terminal_eval(pos, 0, plies)
+        } else {
+            -handler.terminal_eval(pos, 0, plies)
+        }
+    }
+
     // Replication of algorithms described in Muszycka & Shinghal (1985).
 
+    // Plays a move from the opening book if the root position is in it,
+    // and otherwise searches the root position with the given algorithm,
+    // such as `|searcher, handler, pos| searcher.sss(handler, pos, MAX_DEPTH)`.
+    pub fn search_with_book<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        book: &OpeningBook<TPosition>,
+        handler: &THandler,
+        pos: TPosition,
+        algorithm: impl FnOnce(
+            &mut Self,
+            &THandler,
+            TPosition,
+        ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>,
+    ) -> BookOrSearch<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition + ZobristKey,
+    {
+        match book.pick(pos) {
+            Some(mv) => BookOrSearch::Book(mv),
+            None => BookOrSearch::Searched(algorithm(self, handler, pos)),
+        }
+    }
+
+    // Searches the root position to the given depth with the given algorithm,
+    // such as `|searcher, handler, pos, depth| searcher.sss(handler, pos, depth)`,
+    // and returns only the first move of the principal variation, which the algorithm writes
+    // at index `MAX_DEPTH - depth` of the line, since each move is written at the index
+    // of its ply below a root searched to `MAX_DEPTH`.
+    // This is `None` when the root position has no legal moves or the depth is 0.
+    pub fn best_move<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        algorithm: impl FnOnce(
+            &mut Self,
+            &THandler,
+            TPosition,
+            usize,
+        ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+    ) -> Option<<TPosition as GamePosition>::Move>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        let (_, line) = algorithm(self, handler, pos, depth);
+        MAX_DEPTH
+            .checked_sub(depth)
+            .and_then(|index| line.get(index).copied().flatten())
+    }
+
+    // Plays a game from `startpos` in which both sides play the first move
+    // of the principal variation found by `alpha_beta` to the given depth,
+    // returning the moves played in order. The game stops at a game state without legal moves,
+    // or once `max_moves` moves have been played, so that games which need not end,
+    // such as Chess without repetition detection, still stop.
+    // Since the search is deterministic, the same arguments always give the same game.
+    // For games with `GameHandler::plies_to_leaves`, each search stops at the leaves.
+    pub fn self_play<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        startpos: TPosition,
+        depth: usize,
+        max_moves: usize,
+    ) -> Vec<<TPosition as GamePosition>::Move>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        let mut record = Vec::new();
+        let mut pos = startpos;
+        while record.len() < max_moves {
+            let depth = handler.plies_to_leaves(pos).map_or(depth, |plies| depth.min(plies));
+            let (_, line) = self.alpha_beta::<THandler, TPosition, MAX_DEPTH>(
+                handler,
+                pos,
+                depth,
+                <THandler as GameHandler<TPosition>>::EVAL_MINIMUM,
+                <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM,
+            );
+            // A search of depth 0 chooses no move, and its line has no entry for the root.
+            match line.get(MAX_DEPTH - depth).copied().flatten() {
+                Some(mv) => {
+                    record.push(mv);
+                    pos = pos.play_move(mv);
+                }
+                None => break,
+            }
+        }
+        record
+    }
+
+    // Classic minimax without pruning, written with separate maximising and minimising nodes
+    // rather than the negamax formulation used by every other algorithm,
+    // as a reference for learners and a correctness check for the other algorithms.
+    // The root player maximises, and a node `ply = MAX_DEPTH - depth` plies from the root
+    // is a maximising node if `ply` is even and a minimising node otherwise.
+    // The evaluation is always from the perspective of the root player,
+    // so leaves an odd number of plies away have their evaluations negated,
+    // and the result at the root equals that of `alpha_beta`.
+    pub fn minimax_explicit<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        let ply = MAX_DEPTH - depth;
+        let maximising = (ply & 1) == 0;
+        let absolute = |eval: <THandler as GameHandler<TPosition>>::Eval| {
+            if maximising {
+                eval
+            } else {
+                -eval
+            }
+        };
+
+        if depth == 0 {
+            self.increment_leaf_count();
+            return (absolute(handler.evaluate(pos, depth, MAX_DEPTH)), [None; MAX_DEPTH]);
+        }
+
+        let mut best: Option<EvalAndPV<THandler, TPosition, MAX_DEPTH>> = None;
+        for mv in handler.get_legal_moves(pos) {
+            let (t, mut line) = self.minimax_explicit::<THandler, TPosition, MAX_DEPTH>(
+                handler,
+                pos.play_move(mv),
+                depth - 1,
+            );
+            line[ply] = Some(mv);
+            let improves = match best {
+                None => true,
+                Some((m, _)) if maximising => t > m,
+                Some((m, _)) => t < m,
+            };
+            if improves {
+                best = Some((t, line));
+            }
+        }
+
+        best.unwrap_or_else(|| {
+            self.increment_leaf_count();
+            (absolute(handler.terminal_eval(pos, depth, MAX_DEPTH)), [None; MAX_DEPTH])
+        })
+    }
+
     // Algorithm A.
     pub fn branch_and_bound<THandler, TPosition, const MAX_DEPTH: usize>(
         &mut self,
@@ -217,7 +997,7 @@ impl Searcher {
         } else {
             // Statement 5.
             self.increment_leaf_count();
-            (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH])
+            (handler.terminal_eval(pos, depth, MAX_DEPTH), [None; MAX_DEPTH])
         }
     }
 
@@ -248,6 +1028,7 @@ impl Searcher {
             // Statement 6.
             let mut m = alpha;
             let mut pv = [None; MAX_DEPTH];
+            let mut move_index = 0;
 
             loop {
                 // Statement 9.
@@ -268,99 +1049,239 @@ impl Searcher {
 
                 // Statement 10.
                 if m >= beta {
+                    self.record_cutoff(move_index);
                     return (m, line);
                 }
 
                 if let Some(new_mv) = move_iter.next() {
                     mv = new_mv;
+                    move_index += 1;
                 } else {
                     break;
                 }
             }
 
+            if depth == MAX_DEPTH {
+                debug_assert_line(handler, pos, alpha, beta, (m, pv));
+            }
             (m, pv)
         } else {
             // Statement 5.
             self.increment_leaf_count();
-            (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH])
+            (handler.terminal_eval(pos, depth, MAX_DEPTH), [None; MAX_DEPTH])
         }
     }
 
-    // Algorithm C.
-    pub fn p_alpha_beta<THandler, TPosition, const MAX_DEPTH: usize>(
+    // Algorithm B, stopping once the leaf count reaches the limit set by `set_node_limit`,
+    // after which each node returns the best line it has completed, or the line of the move
+    // it was searching if it has not completed any, as in `alpha_beta_timed`.
+    // Without a limit, or if it is not reached, the result is the same as that of `alpha_beta`,
+    // which is kept free of the check as the reference algorithm of the benchmarks.
+    pub fn alpha_beta_limited<THandler, TPosition, const MAX_DEPTH: usize>(
         &mut self,
         handler: &THandler,
         pos: TPosition,
         depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
     ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
     where
         THandler: GameHandler<TPosition>,
         TPosition: GamePosition,
     {
-        // A node `MAX_DEPTH` plies ahead of the root is considered a leaf.
-        // Statement 5.
         if depth == 0 {
             self.increment_leaf_count();
             return (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
         }
 
-        // Statement 4.
         let mut move_iter = handler.get_legal_moves(pos);
 
-        if let Some(mv) = move_iter.next() {
-            // Statement 6.
-            let (mut m, mut pv) = self.p_alpha_beta::<THandler, TPosition, MAX_DEPTH>(
-                handler,
-                pos.play_move(mv),
-                depth - 1,
-            );
-            m = -m;
-            pv[MAX_DEPTH - depth] = Some(mv);
+        if let Some(mut mv) = move_iter.next() {
+            let mut m = alpha;
+            let mut pv = [None; MAX_DEPTH];
+            let mut move_index = 0;
 
-            // Statement 7.
-            for mv in move_iter {
-                let next_pos = pos.play_move(mv);
+            loop {
+                let (t, mut line) = self.alpha_beta_limited::<THandler, TPosition, MAX_DEPTH>(
+                    handler,
+                    pos.play_move(mv),
+                    depth - 1,
+                    -beta,
+                    -m,
+                );
+                let t = -t;
+                line[MAX_DEPTH - depth] = Some(mv);
 
-                // Statement 9.
-                let t = -self
-                    .f_alpha_beta::<THandler, TPosition, MAX_DEPTH>(
-                        handler,
-                        next_pos,
-                        depth - 1,
-                        -m - <THandler as GameHandler<TPosition>>::EVAL_EPSILON,
-                        -m,
-                    )
-                    .0;
+                // The search of this move was cut short by the node limit, so it is only used
+                // if no other move has been completed.
+                if self.node_limit_reached() {
+                    if pv[MAX_DEPTH - depth].is_none() {
+                        return (t, line);
+                    }
+                    return (m, pv);
+                }
 
-                // Statement 10.
                 if t > m {
-                    // Statement 11.
-                    // In Muszycka & Shinghal (1985), this statement was erroneously written as
-                    // `m = -alphabeta(p_i, -MAXINT, -t);` as opposed to
-                    // `m = -falphabeta(p_i, -MAXINT, -t);`. Fishburn & Finkel (1980)
-                    // originally describe this algorithm correctly.
-                    let (t, mut line) = self.f_alpha_beta::<THandler, TPosition, MAX_DEPTH>(
-                        handler,
-                        next_pos,
-                        depth - 1,
-                        <THandler as GameHandler<TPosition>>::EVAL_MINIMUM,
-                        -t,
-                    );
-                    m = -t;
-                    line[MAX_DEPTH - depth] = Some(mv);
+                    m = t;
                     pv = line;
                 }
+
+                if m >= beta {
+                    self.record_cutoff(move_index);
+                    return (m, line);
+                }
+
+                if let Some(new_mv) = move_iter.next() {
+                    mv = new_mv;
+                    move_index += 1;
+                } else {
+                    break;
+                }
             }
 
+            if depth == MAX_DEPTH {
+                debug_assert_line(handler, pos, alpha, beta, (m, pv));
+            }
             (m, pv)
         } else {
-            // Statement 5.
             self.increment_leaf_count();
-            (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH])
+            (handler.terminal_eval(pos, depth, MAX_DEPTH), [None; MAX_DEPTH])
         }
     }
 
-    pub fn f_alpha_beta<THandler, TPosition, const MAX_DEPTH: usize>(
+    // Algorithm B, where a game state that has occurred before, either in the game given to
+    // `set_history` or earlier in the line being searched, is scored by `GameHandler::draw_eval`
+    // instead of being searched once `GameHandler::is_repetition_draw` holds for it.
+    // Game states are compared by `ZobristKey::key`, and the key of each node is pushed onto the history
+    // before its moves are searched and popped afterwards, so the history is left as it was.
+    // The root is always searched, so that a move is found even if it is itself a repetition.
+    // For games whose game states cannot repeat, this is identical to `alpha_beta`.
+    pub fn alpha_beta_history<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition + ZobristKey,
+    {
+        self.alpha_beta_history_node::<THandler, TPosition, MAX_DEPTH>(
+            handler,
+            (pos, pos.key()),
+            depth,
+            alpha,
+            beta,
+        )
+    }
+
+    // A node of `alpha_beta_history`, given with its key, which is carried down from the root
+    // through `ZobristKey::play_move_with_key` rather than computed at every node.
+    fn alpha_beta_history_node<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        (pos, key): (TPosition, u64),
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition + ZobristKey,
+    {
+        if depth < MAX_DEPTH {
+            let occurrences = 1 + self.key_history.iter().filter(|&&seen| seen == key).count();
+            if handler.is_repetition_draw(pos, occurrences) {
+                self.increment_leaf_count();
+                return (handler.draw_eval(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+            }
+        }
+
+        if depth == 0 {
+            self.increment_leaf_count();
+            return (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+        }
+
+        let mut m = alpha;
+        let mut pv = [None; MAX_DEPTH];
+        let mut has_moves = false;
+
+        self.key_history.push(key);
+        for (move_index, mv) in handler.get_legal_moves(pos).enumerate() {
+            has_moves = true;
+            let (t, mut line) = self.alpha_beta_history_node::<THandler, TPosition, MAX_DEPTH>(
+                handler,
+                pos.play_move_with_key(key, mv),
+                depth - 1,
+                -beta,
+                -m,
+            );
+            let t = -t;
+            line[MAX_DEPTH - depth] = Some(mv);
+
+            if t > m {
+                m = t;
+                pv = line;
+            }
+
+            if m >= beta {
+                self.record_cutoff(move_index);
+                break;
+            }
+        }
+        self.key_history.pop();
+
+        if !has_moves {
+            self.increment_leaf_count();
+            return (handler.terminal_eval(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+        }
+        (m, pv)
+    }
+
+    // Algorithm B, also reporting whether the evaluation is exact or only a bound
+    // on the true value, which callers such as MTD(f) or aspiration windows need
+    // before trusting the evaluation. A search that fails high returns a lower bound,
+    // and one that fails low returns an upper bound, as described by the same `Bound`
+    // as the entries of a `TranspositionTable`. An evaluation equal to `alpha`
+    // is reported as an upper bound, since a search that fails low returns `alpha`.
+    pub fn alpha_beta_bounded<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+    ) -> (EvalAndPV<THandler, TPosition, MAX_DEPTH>, Bound)
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        let (m, pv) =
+            self.alpha_beta::<THandler, TPosition, MAX_DEPTH>(handler, pos, depth, alpha, beta);
+        let bound = if m >= beta {
+            Bound::Lower
+        } else if m <= alpha {
+            Bound::Upper
+        } else {
+            Bound::Exact
+        };
+        ((m, pv), bound)
+    }
+
+    // Algorithm B in its fail-soft form, which starts each node from `EVAL_MINIMUM` instead of
+    // `alpha`, so that the evaluation may fall outside the window `(alpha, beta)`.
+    // `alpha_beta` is fail-hard, and returns `alpha` on failing low and at most `beta` on failing
+    // high, which is kept as the algorithm compared by Muszycka & Shinghal (1985).
+    // Here, an evaluation of at most `alpha` is an upper bound on the true value
+    // and one of at least `beta` is a lower bound, either of which may be tighter than
+    // the window itself, making better bounds for a `TranspositionTable` or a re-search.
+    // An evaluation inside the window is exact and the same as that of `alpha_beta`,
+    // and the same moves are searched with the same windows, so a search that fails high
+    // returns the same refutation as `alpha_beta`.
+    // This is the same algorithm as `f_alpha_beta`, which MTD(f) and NegaC* use.
+    pub fn alpha_beta_fail_soft<THandler, TPosition, const MAX_DEPTH: usize>(
         &mut self,
         handler: &THandler,
         pos: TPosition,
@@ -372,249 +1293,467 @@ impl Searcher {
         THandler: GameHandler<TPosition>,
         TPosition: GamePosition,
     {
-        // A node `MAX_DEPTH` plies ahead of the root is considered a leaf.
-        // Statement 5.
+        self.f_alpha_beta::<THandler, TPosition, MAX_DEPTH>(handler, pos, depth, alpha, beta)
+    }
+
+    // Algorithm B with an aspiration window, which searches the window `window` either side
+    // of `guess` (such as the evaluation of a shallower search) in the hope of more cutoffs.
+    // When the search fails outside the window, it is searched again with that side
+    // of the window widened, first to twice `window` and then to the extreme evaluation,
+    // so the evaluation and principal variation are always the same as those of `alpha_beta`.
+    // `iterative_deepening_aspiration` applies this to each depth of `iterative_deepening`.
+    pub fn aspiration_search<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        guess: <THandler as GameHandler<TPosition>>::Eval,
+        window: <THandler as GameHandler<TPosition>>::Eval,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        let (mut fails_low, mut fails_high) = (0, 0);
+        loop {
+            let (alpha, beta) =
+                aspiration_window::<THandler, TPosition>(guess, window, fails_low, fails_high);
+            let (result, bound) = self.alpha_beta_bounded::<THandler, TPosition, MAX_DEPTH>(
+                handler, pos, depth, alpha, beta,
+            );
+            match bound {
+                Bound::Lower if beta < <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM => {
+                    fails_high += 1;
+                }
+                Bound::Upper if alpha > <THandler as GameHandler<TPosition>>::EVAL_MINIMUM => {
+                    fails_low += 1;
+                }
+                _ => return result,
+            }
+        }
+    }
+
+    // Algorithm B, abandoning the search once `deadline` has passed.
+    // The clock is only read every `DEADLINE_POLL_INTERVAL` interior nodes,
+    // so the search may run slightly past the deadline.
+    // Alongside the result, this returns `true` if the search completed, in which case
+    // the result is the same as that of `alpha_beta`, and `false` if it was abandoned.
+    // An abandoned search returns the best root move whose search had completed,
+    // or otherwise the root move being searched, so there is always a move to play
+    // if the root position has any, but its evaluation should not be trusted.
+    pub fn alpha_beta_timed<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+        deadline: Instant,
+    ) -> (EvalAndPV<THandler, TPosition, MAX_DEPTH>, bool)
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        self.nodes_since_poll = 0;
+        self.aborted = false;
+        let (start, leaves) = (Instant::now(), self.get_leaf_count());
+        let result = self.alpha_beta_deadline::<THandler, TPosition, MAX_DEPTH>(
+            handler, pos, depth, alpha, beta, deadline,
+        );
+        self.record_search_time(start, leaves);
+        (result, !self.aborted && !self.node_limit_reached())
+    }
+
+    // Reads the clock if `DEADLINE_POLL_INTERVAL` nodes have been visited since it was last read,
+    // and returns whether the search has passed `deadline`.
+    fn deadline_passed(&mut self, deadline: Instant) -> bool {
+        self.stop_requested(&mut || Instant::now() >= deadline)
+    }
+
+    // Calls `should_stop` if `DEADLINE_POLL_INTERVAL` nodes have been visited since it was
+    // last called, and returns whether the search has been told to stop.
+    fn stop_requested(&mut self, should_stop: &mut impl FnMut() -> bool) -> bool {
+        self.nodes_since_poll += 1;
+        if self.nodes_since_poll >= DEADLINE_POLL_INTERVAL {
+            self.nodes_since_poll = 0;
+            if should_stop() {
+                self.aborted = true;
+            }
+        }
+        self.aborted
+    }
+
+    // The recursive part of `alpha_beta_timed`.
+    // Once the deadline has passed, each node returns the best line it has completed,
+    // or the line of the move it was searching if it has not completed any.
+    fn alpha_beta_deadline<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+        deadline: Instant,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
         if depth == 0 {
             self.increment_leaf_count();
             return (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
         }
 
-        // Statement 4.
+        if self.deadline_passed(deadline) {
+            return (alpha, [None; MAX_DEPTH]);
+        }
+
         let mut move_iter = handler.get_legal_moves(pos);
 
         if let Some(mut mv) = move_iter.next() {
-            // Statement 6.
-            let mut m = <THandler as GameHandler<TPosition>>::EVAL_MINIMUM;
+            let mut m = alpha;
             let mut pv = [None; MAX_DEPTH];
+            let mut move_index = 0;
 
             loop {
-                // Statement 9.
-                let (t, mut line) = self.f_alpha_beta::<THandler, TPosition, MAX_DEPTH>(
+                let (t, mut line) = self.alpha_beta_deadline::<THandler, TPosition, MAX_DEPTH>(
                     handler,
                     pos.play_move(mv),
                     depth - 1,
                     -beta,
-                    -std::cmp::max(m, alpha),
+                    -m,
+                    deadline,
                 );
                 let t = -t;
                 line[MAX_DEPTH - depth] = Some(mv);
 
+                // The search of this move was cut short by the deadline or the node limit,
+                // so it is only used if no other move has been completed.
+                if self.aborted || self.node_limit_reached() {
+                    if pv[MAX_DEPTH - depth].is_none() {
+                        return (t, line);
+                    }
+                    return (m, pv);
+                }
+
                 if t > m {
                     m = t;
                     pv = line;
                 }
 
-                // Statement 10.
                 if m >= beta {
+                    self.record_cutoff(move_index);
                     return (m, line);
                 }
 
                 if let Some(new_mv) = move_iter.next() {
                     mv = new_mv;
+                    move_index += 1;
                 } else {
                     break;
                 }
             }
 
+            if depth == MAX_DEPTH {
+                debug_assert_line(handler, pos, alpha, beta, (m, pv));
+            }
             (m, pv)
         } else {
-            // Statement 5.
             self.increment_leaf_count();
-            (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH])
+            (handler.terminal_eval(pos, depth, MAX_DEPTH), [None; MAX_DEPTH])
         }
     }
 
-    // Algorithm D.
-    pub fn pvs<THandler, TPosition, const MAX_DEPTH: usize>(
+    // The exact evaluation of every root move, each searched by Algorithm B with the full window
+    // so that no score is merely a bound and all of them can be compared.
+    // Moves are ordered as described at `root_lines`,
+    // so the first move is the one `alpha_beta` would choose.
+    pub fn root_scores<THandler, TPosition, const MAX_DEPTH: usize>(
         &mut self,
         handler: &THandler,
         pos: TPosition,
         depth: usize,
-        alpha: <THandler as GameHandler<TPosition>>::Eval,
-        beta: <THandler as GameHandler<TPosition>>::Eval,
-    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    ) -> RootScores<THandler, TPosition>
     where
         THandler: GameHandler<TPosition>,
         TPosition: GamePosition,
     {
-        // A node `MAX_DEPTH` plies ahead of the root is considered a leaf.
-        // Statement 5.
-        if depth == 0 {
-            self.increment_leaf_count();
-            return (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
-        }
-
-        // Statement 4.
-        let mut move_iter = handler.get_legal_moves(pos);
-
-        if let Some(mv) = move_iter.next() {
-            // Statement 6.
-            let (mut m, mut pv) = self.pvs::<THandler, TPosition, MAX_DEPTH>(
-                handler,
-                pos.play_move(mv),
-                depth - 1,
-                -beta,
-                -alpha,
-            );
-            m = -m;
-            pv[MAX_DEPTH - depth] = Some(mv);
-
-            // Statement 7.
-            if m < beta {
-                // Statement 8.
-                for mv in move_iter {
-                    // Statement 10.
-                    let bound = std::cmp::max(m, alpha);
-
-                    let next_pos = pos.play_move(mv);
-
-                    // Statement 11.
-                    let t = -self
-                        .pvs::<THandler, TPosition, MAX_DEPTH>(
-                            handler,
-                            next_pos,
-                            depth - 1,
-                            -bound - <THandler as GameHandler<TPosition>>::EVAL_EPSILON,
-                            -bound,
-                        )
-                        .0;
-
-                    // Statement 12.
-                    if t > m {
-                        // Statement 13.
-                        let (value, mut line) = self.pvs::<THandler, TPosition, MAX_DEPTH>(
-                            handler,
-                            next_pos,
-                            depth - 1,
-                            -beta,
-                            -t,
-                        );
-                        m = -value;
-                        line[MAX_DEPTH - depth] = Some(mv);
-                        pv = line;
-                    }
-                    // Statement 14.
-                    if m >= beta {
-                        return (m, pv);
-                    }
-                }
-            }
+        self.root_lines::<THandler, TPosition, MAX_DEPTH>(handler, pos, depth, false)
+            .into_iter()
+            .map(|(eval, line)| (line[MAX_DEPTH - depth].unwrap(), eval))
+            .collect()
+    }
 
-            (m, pv)
-        } else {
-            // Statement 5.
-            self.increment_leaf_count();
-            (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH])
+    // The best `k` root moves with their exact evaluations and principal variations,
+    // each root move being searched by Algorithm B with the full window as in `root_scores`.
+    // Lines are ordered as described at `root_lines`, so the first line has the evaluation
+    // of `alpha_beta`, as well as its principal variation when no two lines share an evaluation.
+    pub fn alpha_beta_multipv<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        k: usize,
+    ) -> Vec<EvalAndPV<THandler, TPosition, MAX_DEPTH>>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        if k == 0 {
+            return Vec::new();
         }
+        let mut lines =
+            self.root_lines::<THandler, TPosition, MAX_DEPTH>(handler, pos, depth, false);
+        lines.truncate(k);
+        lines
     }
 
-    // Algorithm E.
-    pub fn scout<THandler, TPosition, const MAX_DEPTH: usize>(
+    // The line of every root move with its exact evaluation, each root move being searched
+    // with the full window by `pvs` if `use_pvs` is set and by `alpha_beta` otherwise.
+    // This is shared by `root_scores`, `alpha_beta_multipv` and `pvs_analysis`.
+    // Lines are sorted from best to worst, with lines of equal evaluation kept in the order
+    // their root moves were generated, so that the result is reproducible.
+    // The list is empty when the root position has no legal moves or the depth is 0.
+    fn root_lines<THandler, TPosition, const MAX_DEPTH: usize>(
         &mut self,
         handler: &THandler,
         pos: TPosition,
         depth: usize,
-    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+        use_pvs: bool,
+    ) -> Vec<EvalAndPV<THandler, TPosition, MAX_DEPTH>>
     where
         THandler: GameHandler<TPosition>,
         TPosition: GamePosition,
     {
-        // A node `MAX_DEPTH` plies ahead of the root is considered a leaf.
-        // Statement 5.
         if depth == 0 {
-            self.increment_leaf_count();
-            return (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+            return Vec::new();
         }
+        let (alpha, beta) = (
+            <THandler as GameHandler<TPosition>>::EVAL_MINIMUM,
+            <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM,
+        );
+        let mut lines: Vec<_> = handler
+            .get_legal_moves(pos)
+            .map(|mv| {
+                let child = pos.play_move(mv);
+                let (t, mut line) = if use_pvs {
+                    self.pvs::<THandler, TPosition, MAX_DEPTH>(
+                        handler,
+                        child,
+                        depth - 1,
+                        alpha,
+                        beta,
+                    )
+                } else {
+                    self.alpha_beta::<THandler, TPosition, MAX_DEPTH>(
+                        handler,
+                        child,
+                        depth - 1,
+                        alpha,
+                        beta,
+                    )
+                };
+                line[MAX_DEPTH - depth] = Some(mv);
+                (-t, line)
+            })
+            .collect();
+        lines.sort_by_key(|&(eval, _)| std::cmp::Reverse(eval));
+        lines
+    }
 
-        // Statement 4.
-        let mut move_iter = handler.get_legal_moves(pos);
+    // Algorithm B, evaluating the leaves with `eval_fn` instead of `GameHandler::evaluate`,
+    // so that a new evaluation can be tried on an existing game without changing its handler.
+    // `eval_fn` is called with the same arguments as `evaluate`, and must likewise evaluate
+    // from the perspective of the player to move. Game states without legal moves
+    // are still scored by `GameHandler::terminal_eval`.
+    // Passing `|pos, depth, max_depth| handler.evaluate(*pos, depth, max_depth)`
+    // gives the same result as `alpha_beta`.
+    pub fn alpha_beta_with_eval<THandler, TPosition, TEvalFn, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+        eval_fn: &TEvalFn,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+        TEvalFn: Fn(&TPosition, usize, usize) -> <THandler as GameHandler<TPosition>>::Eval,
+    {
+        if depth == 0 {
+            self.increment_leaf_count();
+            return (eval_fn(&pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+        }
 
-        if let Some(mv) = move_iter.next() {
-            // Statement 6.
-            let (mut m, mut pv) = self.scout::<THandler, TPosition, MAX_DEPTH>(
-                handler,
-                pos.play_move(mv),
-                depth - 1,
-            );
-            m = -m;
-            pv[MAX_DEPTH - depth] = Some(mv);
+        let mut move_iter = handler.get_legal_moves(pos).peekable();
+        if move_iter.peek().is_none() {
+            self.increment_leaf_count();
+            return (handler.terminal_eval(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+        }
 
-            // Statement 7.
-            let op = true;
+        let mut m = alpha;
+        let mut pv = [None; MAX_DEPTH];
 
-            // Statement 8.
-            for mv in move_iter {
-                let next_pos = pos.play_move(mv);
+        for mv in move_iter {
+            let (t, mut line) = self
+                .alpha_beta_with_eval::<THandler, TPosition, TEvalFn, MAX_DEPTH>(
+                    handler,
+                    pos.play_move(mv),
+                    depth - 1,
+                    -beta,
+                    -m,
+                    eval_fn,
+                );
+            let t = -t;
+            line[MAX_DEPTH - depth] = Some(mv);
 
-                // Statement 9.
-                if !self.test::<THandler, TPosition>(
+            if t > m {
+                m = t;
+                pv = line;
+            }
+
+            if m >= beta {
+                return (m, line);
+            }
+        }
+
+        (m, pv)
+    }
+
+    // Algorithm B recording its principal variation in the caller's `TriangularPV`
+    // instead of returning an array-based principal variation from every node.
+    // The evaluation is returned, and the line left in `pv` is identical to that of `alpha_beta`.
+    pub fn alpha_beta_triangular<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+        pv: &mut TriangularPV<<TPosition as GamePosition>::Move, MAX_DEPTH>,
+    ) -> <THandler as GameHandler<TPosition>>::Eval
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        let ply = MAX_DEPTH - depth;
+        pv.clear(ply);
+
+        if depth == 0 {
+            self.increment_leaf_count();
+            return handler.evaluate(pos, depth, MAX_DEPTH);
+        }
+
+        let mut move_iter = handler.get_legal_moves(pos);
+
+        if let Some(mut mv) = move_iter.next() {
+            let mut m = alpha;
+
+            loop {
+                let t = -self.alpha_beta_triangular::<THandler, TPosition, MAX_DEPTH>(
                     handler,
-                    next_pos,
+                    pos.play_move(mv),
                     depth - 1,
-                    MAX_DEPTH,
+                    -beta,
                     -m,
-                    !op,
-                ) {
-                    let (new_m, mut line) = self.scout::<THandler, TPosition, MAX_DEPTH>(
-                        handler,
-                        next_pos,
-                        depth - 1,
-                    );
-                    let new_m = -new_m;
-                    line[MAX_DEPTH - depth] = Some(mv);
-                    m = new_m;
-                    pv = line;
+                    pv,
+                );
+
+                if t > m {
+                    m = t;
+                    pv.update(ply, mv);
+                }
+
+                if m >= beta {
+                    return m;
+                }
+
+                if let Some(new_mv) = move_iter.next() {
+                    mv = new_mv;
+                } else {
+                    break;
                 }
             }
 
-            (m, pv)
+            m
         } else {
-            // Statement 5.
             self.increment_leaf_count();
-            (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH])
+            handler.terminal_eval(pos, depth, MAX_DEPTH)
         }
     }
 
-    pub fn test<THandler, TPosition>(
+    // Algorithm B with the depth chosen at runtime rather than by `MAX_DEPTH`,
+    // returning the principal variation as a `Vec` instead of an array.
+    // The evaluation and line are identical to those of `alpha_beta` with `MAX_DEPTH == depth`.
+    // As each call completes a search to `depth`, the result is passed to `report_info`,
+    // so that a loop over increasing depths reports progress after every iteration.
+    pub fn alpha_beta_dyn<THandler, TPosition>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+    ) -> EvalAndLine<THandler, TPosition>
+    where
+        THandler: GameHandler<TPosition> + 'static,
+        TPosition: GamePosition + 'static,
+    {
+        let (start, leaves) = (Instant::now(), self.get_leaf_count());
+        let (eval, mut line) = self.alpha_beta_dyn_node(handler, pos, depth, depth, alpha, beta);
+        line.reverse();
+        let (nodes, elapsed) = self.record_search_time(start, leaves);
+        self.report_info(depth, eval, line.first().copied(), nodes, elapsed);
+        (eval, line)
+    }
+
+    // The recursive part of `alpha_beta_dyn`, which builds each line from the leaf upwards
+    // so that every node can append its move instead of inserting it at the front.
+    fn alpha_beta_dyn_node<THandler, TPosition>(
         &mut self,
         handler: &THandler,
         pos: TPosition,
         depth: usize,
         max_depth: usize,
-        v: <THandler as GameHandler<TPosition>>::Eval,
-        op: bool,
-    ) -> bool
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+    ) -> EvalAndLine<THandler, TPosition>
     where
         THandler: GameHandler<TPosition>,
         TPosition: GamePosition,
     {
-        // A node `max_depth` plies ahead of the root is considered a leaf.
-        // Statement 5.
         if depth == 0 {
-            // Statements 6-9.
             self.increment_leaf_count();
-            return if op {
-                handler.evaluate(pos, depth, max_depth) >= v
-            } else {
-                handler.evaluate(pos, depth, max_depth) > v
-            };
+            return (handler.evaluate(pos, depth, max_depth), Vec::new());
         }
 
-        // Statement 4.
         let mut move_iter = handler.get_legal_moves(pos);
 
         if let Some(mut mv) = move_iter.next() {
+            let mut m = alpha;
+            let mut pv = Vec::new();
+
             loop {
-                // Statement 11.
-                if !self.test::<THandler, TPosition>(
+                let (t, mut line) = self.alpha_beta_dyn_node(
                     handler,
                     pos.play_move(mv),
                     depth - 1,
                     max_depth,
-                    -v,
-                    !op,
-                ) {
-                    return true;
+                    -beta,
+                    -m,
+                );
+                let t = -t;
+                line.push(mv);
+
+                if t > m {
+                    m = t;
+                    pv = line;
+                }
+
+                if m >= beta {
+                    return (m, pv);
                 }
 
                 if let Some(new_mv) = move_iter.next() {
@@ -623,331 +1762,3890 @@ impl Searcher {
                     break;
                 }
             }
-            // Statement 13.
-            false
+
+            (m, pv)
         } else {
-            // Statements 6-9.
             self.increment_leaf_count();
-            if op {
-                handler.evaluate(pos, depth, max_depth) >= v
+            (handler.terminal_eval(pos, depth, max_depth), Vec::new())
+        }
+    }
+
+    // Iterative deepening, which searches the root position with Algorithm B to each depth
+    // from 1 to `MAX_DEPTH` in turn, searching the best root move of the previous depth first.
+    // For games with `GameHandler::plies_to_leaves`, the first depth is that of the leaves instead,
+    // since the shallower depths would stop at interior nodes that cannot be evaluated.
+    // Each depth is searched as by `alpha_beta_dyn`, so that evaluations such as mate scores
+    // are relative to the root of that search, and the line is written from the start of the array.
+    // `should_stop` is polled every `DEADLINE_POLL_INTERVAL` interior nodes after the first depth,
+    // and once it returns `true` the unfinished depth is abandoned and the result of the deepest
+    // completed depth is returned, such as with `|| Instant::now() >= deadline`.
+    // The first depth always completes, so the line has a move whenever the root has one.
+    // When no search is stopped, the evaluation is identical to that of `alpha_beta`,
+    // as is the principal variation when no two root moves share an evaluation.
+    // Each completed depth is passed to `report_info`.
+    pub fn iterative_deepening<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        should_stop: impl FnMut() -> bool,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition> + 'static,
+        TPosition: GamePosition + 'static,
+    {
+        self.deepen::<THandler, TPosition, MAX_DEPTH>(handler, pos, None, should_stop)
+    }
+
+    // `iterative_deepening` where each depth after the first is searched with an aspiration window
+    // around the evaluation of the previous depth, widened as described at `aspiration_search`
+    // whenever the search fails outside it. The result is the same as `iterative_deepening`.
+    pub fn iterative_deepening_aspiration<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        window: <THandler as GameHandler<TPosition>>::Eval,
+        should_stop: impl FnMut() -> bool,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition> + 'static,
+        TPosition: GamePosition + 'static,
+    {
+        self.deepen::<THandler, TPosition, MAX_DEPTH>(handler, pos, Some(window), should_stop)
+    }
+
+    // The implementation of `iterative_deepening` and `iterative_deepening_aspiration`,
+    // where `window` is the initial aspiration window, if any.
+    fn deepen<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        window: Option<<THandler as GameHandler<TPosition>>::Eval>,
+        mut should_stop: impl FnMut() -> bool,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition> + 'static,
+        TPosition: GamePosition + 'static,
+    {
+        let mut moves = handler.get_legal_moves(pos).collect::<Vec<_>>();
+        if MAX_DEPTH == 0 || moves.is_empty() {
+            self.increment_leaf_count();
+            let eval = if moves.is_empty() {
+                handler.terminal_eval(pos, MAX_DEPTH, MAX_DEPTH)
             } else {
-                handler.evaluate(pos, depth, max_depth) > v
+                handler.evaluate(pos, MAX_DEPTH, MAX_DEPTH)
+            };
+            return (eval, [None; MAX_DEPTH]);
+        }
+
+        self.nodes_since_poll = 0;
+        self.aborted = false;
+        let (start, leaves) = (Instant::now(), self.get_leaf_count());
+        let mut result = (<THandler as GameHandler<TPosition>>::EVAL_MINIMUM, [None; MAX_DEPTH]);
+        let first_depth = handler
+            .plies_to_leaves(pos)
+            .map_or(1, |plies| plies.clamp(1, MAX_DEPTH));
+
+        'depths: for depth in first_depth..=MAX_DEPTH {
+            if let Some(index) = moves.iter().position(|&mv| Some(mv) == result.1[0]) {
+                moves[..=index].rotate_right(1);
+            }
+
+            let (mut fails_low, mut fails_high) = (0, 0);
+            let (m, pv) = loop {
+                let (alpha, beta) = match window {
+                    Some(window) if depth > first_depth => aspiration_window::<THandler, TPosition>(
+                        result.0, window, fails_low, fails_high,
+                    ),
+                    _ => (
+                        <THandler as GameHandler<TPosition>>::EVAL_MINIMUM,
+                        <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM,
+                    ),
+                };
+                let searched = if depth == first_depth {
+                    self.deepen_root(handler, pos, &moves, depth, (alpha, beta), &mut || false)
+                } else {
+                    self.deepen_root(handler, pos, &moves, depth, (alpha, beta), &mut should_stop)
+                };
+                let Some(((m, pv), bound)) = searched else {
+                    break 'depths;
+                };
+                match bound {
+                    Bound::Lower if beta < <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM => {
+                        fails_high += 1;
+                    }
+                    Bound::Upper if alpha > <THandler as GameHandler<TPosition>>::EVAL_MINIMUM => {
+                        fails_low += 1;
+                    }
+                    _ => break (m, pv),
+                }
+            };
+
+            result = (m, [None; MAX_DEPTH]);
+            for (slot, mv) in result.1.iter_mut().zip(pv.into_iter().rev()) {
+                *slot = Some(mv);
             }
+            let (nodes, elapsed) = self.record_search_time(start, leaves);
+            self.report_info(depth, m, result.1[0], nodes, elapsed);
         }
+
+        self.record_search_time(start, leaves);
+        result
     }
 
-    // Algorithm F.
-    pub fn sss<THandler, TPosition, const MAX_DEPTH: usize>(
+    // A single depth of `deepen`, searching the root `moves` in order with the window
+    // `(alpha, beta)` given by `window` as `alpha_beta_dyn` would,
+    // and returning the line leaf first with how the evaluation bounds the true value,
+    // as in `alpha_beta_bounded`.
+    // If every move evaluates to `EVAL_MINIMUM`, the first is kept so that the line has a move.
+    // This is `None` if the search was stopped by `should_stop`, which is polled as described at
+    // `iterative_deepening` by `deepen_node`.
+    fn deepen_root<THandler, TPosition>(
         &mut self,
         handler: &THandler,
-        root: TPosition,
+        pos: TPosition,
+        moves: &[<TPosition as GamePosition>::Move],
         depth: usize,
-    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+        window: (
+            <THandler as GameHandler<TPosition>>::Eval,
+            <THandler as GameHandler<TPosition>>::Eval,
+        ),
+        should_stop: &mut impl FnMut() -> bool,
+    ) -> Option<(EvalAndLine<THandler, TPosition>, Bound)>
     where
         THandler: GameHandler<TPosition>,
         TPosition: GamePosition,
     {
-        // The `State` data structure for use in the SSS* algorithm is defined here,
-        // since this function is only called once at the root due to its iterative nature.
-        // It is not defined earlier as it is only used by this algorithm and will not be returned either.
-        #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-        enum State<TPos, TEval, TMove, const SIZE: usize>
-        where
-            TPos: Clone + Copy + std::fmt::Debug + PartialEq + Eq,
-            TEval: Clone + Copy + std::fmt::Debug + PartialEq + Eq + PartialOrd + Ord,
-            TMove: Clone + Copy + std::fmt::Debug + PartialEq + Eq,
-        {
-            Live {
-                node: TPos,
-                merit: (TEval, [Option<TMove>; SIZE]),
-                depth: usize,
-                line: [Option<TMove>; SIZE],
-                iteration: usize,
-            },
-            Solved {
-                node: TPos,
-                merit: (TEval, [Option<TMove>; SIZE]),
-                depth: usize,
-                line: [Option<TMove>; SIZE],
-                iteration: usize,
-            },
-        }
-
-        impl<TPos, TEval, TMove, const SIZE: usize> State<TPos, TEval, TMove, SIZE>
-        where
-            TPos: Clone + Copy + std::fmt::Debug + PartialEq + Eq,
-            TEval: Clone + Copy + std::fmt::Debug + PartialEq + Eq + PartialOrd + Ord,
-            TMove: Clone + Copy + std::fmt::Debug + PartialEq + Eq,
-        {
-            fn merit(&self) -> (TEval, [Option<TMove>; SIZE]) {
-                match *self {
-                    Self::Solved {
-                        node: _,
-                        merit,
-                        depth: _,
-                        line: _,
-                        iteration: _,
-                    } => merit,
-                    Self::Live {
-                        node: _,
-                        merit,
-                        depth: _,
-                        line: _,
-                        iteration: _,
-                    } => merit,
-                }
-            }
-
-            fn depth(&self) -> usize {
-                match *self {
-                    Self::Solved {
-                        node: _,
-                        merit: _,
-                        depth,
-                        line: _,
-                        iteration: _,
-                    } => depth,
-                    Self::Live {
-                        node: _,
-                        merit: _,
-                        depth,
-                        line: _,
-                        iteration: _,
-                    } => depth,
-                }
+        let (alpha, beta) = window;
+        let mut m = alpha;
+        let mut pv = Vec::new();
+        for &mv in moves {
+            let (t, mut line) = if depth > 1 {
+                let child = pos.play_move(mv);
+                self.deepen_node(handler, child, depth - 1, depth, (-beta, -m), should_stop)
+            } else {
+                self.alpha_beta_dyn_node(handler, pos.play_move(mv), 0, depth, -beta, -m)
+            };
+            if self.aborted {
+                return None;
             }
-
-            fn line(&self) -> [Option<TMove>; SIZE] {
-                match *self {
-                    Self::Solved {
-                        node: _,
-                        merit: _,
-                        depth: _,
-                        line,
-                        iteration: _,
-                    } => line,
-                    Self::Live {
-                        node: _,
-                        merit: _,
-                        depth: _,
-                        line,
-                        iteration: _,
-                    } => line,
-                }
+            let t = -t;
+            line.push(mv);
+            if t > m {
+                m = t;
+                pv = line;
+            } else if pv.is_empty() && t == <THandler as GameHandler<TPosition>>::EVAL_MINIMUM {
+                pv = line;
             }
-
-            fn iteration(&self) -> usize {
-                match *self {
-                    Self::Solved {
-                        node: _,
-                        merit: _,
-                        depth: _,
-                        line: _,
-                        iteration,
-                    } => iteration,
-                    Self::Live {
-                        node: _,
-                        merit: _,
-                        depth: _,
-                        line: _,
-                        iteration,
-                    } => iteration,
-                }
+            if m >= beta {
+                return Some(((m, pv), Bound::Lower));
             }
+        }
+        let bound = if m <= alpha {
+            Bound::Upper
+        } else {
+            Bound::Exact
+        };
+        Some(((m, pv), bound))
+    }
 
-            fn is_max_player(&self, max_depth: usize) -> bool {
-                ((max_depth - self.depth()) & 1) == 0
-            }
+    // `alpha_beta_dyn_node` searching the window `(alpha, beta)` given by `window`, which also
+    // polls `should_stop` at each interior node through `stop_requested`.
+    // Once the search is stopped, every node returns immediately and its result is meaningless.
+    fn deepen_node<THandler, TPosition>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        max_depth: usize,
+        window: (
+            <THandler as GameHandler<TPosition>>::Eval,
+            <THandler as GameHandler<TPosition>>::Eval,
+        ),
+        should_stop: &mut impl FnMut() -> bool,
+    ) -> EvalAndLine<THandler, TPosition>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        let (alpha, beta) = window;
+        if depth == 0 {
+            self.increment_leaf_count();
+            return (handler.evaluate(pos, depth, max_depth), Vec::new());
         }
 
-        impl<TPos, TEval, TMove, const SIZE: usize> PartialOrd for State<TPos, TEval, TMove, SIZE>
-        where
-            TPos: Clone + Copy + std::fmt::Debug + PartialEq + Eq,
-            TEval: Clone + Copy + std::fmt::Debug + PartialEq + Eq + PartialOrd + Ord,
-            TMove: Clone + Copy + std::fmt::Debug + PartialEq + Eq,
-        {
-            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-                Some(self.cmp(other))
-            }
+        if self.stop_requested(should_stop) {
+            return (alpha, Vec::new());
         }
 
-        impl<TPos, TEval, TMove, const SIZE: usize> Ord for State<TPos, TEval, TMove, SIZE>
-        where
-            TPos: Clone + Copy + std::fmt::Debug + PartialEq + Eq,
-            TEval: Clone + Copy + std::fmt::Debug + PartialEq + Eq + PartialOrd + Ord,
-            TMove: Clone + Copy + std::fmt::Debug + PartialEq + Eq,
-        {
-            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-                self.merit()
-                    .0
-                    .cmp(&other.merit().0)
-                    .then_with(|| self.iteration().cmp(&other.iteration()))
+        let mut m = alpha;
+        let mut pv = Vec::new();
+        let mut has_move = false;
+        for mv in handler.get_legal_moves(pos) {
+            has_move = true;
+            let child = pos.play_move(mv);
+            let (t, mut line) =
+                self.deepen_node(handler, child, depth - 1, max_depth, (-beta, -m), should_stop);
+            if self.aborted {
+                return (m, pv);
+            }
+            let t = -t;
+            line.push(mv);
+            if t > m {
+                m = t;
+                pv = line;
+            }
+            if m >= beta {
+                break;
             }
         }
 
-        let mut open: BinaryHeap<
-            State<
-                TPosition,
-                <THandler as GameHandler<TPosition>>::Eval,
-                <TPosition as GamePosition>::Move,
-                MAX_DEPTH,
-            >,
-        > = BinaryHeap::new();
-
-        open.push(State::Live {
-            node: root,
-            merit: (
-                <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM,
-                [None; MAX_DEPTH],
-            ),
-            depth,
-            line: [None; MAX_DEPTH],
-            iteration: 0,
-        });
+        if has_move {
+            (m, pv)
+        } else {
+            self.increment_leaf_count();
+            (handler.terminal_eval(pos, depth, max_depth), Vec::new())
+        }
+    }
 
-        let mut i: usize = 1;
+    // Algorithm B with search extensions, where a child node is searched one ply deeper
+    // when `GameHandler::should_extend` holds for it (e.g. it is in check in Chess),
+    // or when it is the only legal move from its parent (a single-reply extension).
+    // These are not singular extensions in the usual sense, which would also extend a move
+    // shown by a reduced verification search to be better than every alternative by a margin.
+    // Here `depth` is the nominal search depth, and `MAX_DEPTH` bounds the length of any line,
+    // so `MAX_DEPTH - depth` is the maximum number of extensions along a single line.
+    pub fn alpha_beta_ext<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        self.alpha_beta_ext_ply::<THandler, TPosition, MAX_DEPTH>(
+            handler, pos, depth, 0, alpha, beta,
+        )
+    }
+
+    // Since extensions decouple the remaining depth from the distance to the root,
+    // the number of plies from the root is tracked separately as `ply`.
+    fn alpha_beta_ext_ply<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        ply: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        self.update_seldepth(ply);
+        if depth == 0 {
+            self.increment_leaf_count();
+            return (handler.evaluate(pos, MAX_DEPTH - ply, MAX_DEPTH), [None; MAX_DEPTH]);
+        }
+
+        let moves = handler.get_legal_moves(pos).collect::<Vec<_>>();
+        if moves.is_empty() {
+            self.increment_leaf_count();
+            return (handler.terminal_eval(pos, MAX_DEPTH - ply, MAX_DEPTH), [None; MAX_DEPTH]);
+        }
+
+        let mut m = alpha;
+        let mut pv = [None; MAX_DEPTH];
+
+        for &mv in moves.iter() {
+            let next_pos = pos.play_move(mv);
+            let extend = (moves.len() == 1 || handler.should_extend(next_pos))
+                && ply + depth < MAX_DEPTH;
+            let (t, mut line) = self.alpha_beta_ext_ply::<THandler, TPosition, MAX_DEPTH>(
+                handler,
+                next_pos,
+                if extend { depth } else { depth - 1 },
+                ply + 1,
+                -beta,
+                -m,
+            );
+            let t = -t;
+            line[ply] = Some(mv);
+
+            if t > m {
+                m = t;
+                pv = line;
+            }
+
+            if m >= beta {
+                return (m, line);
+            }
+        }
+
+        (m, pv)
+    }
+
+    // Algorithm B, where each node at the horizon is resolved by `quiescence`
+    // rather than evaluated directly, so that the search does not stop in the middle
+    // of a sequence of loud moves such as an exchange of pieces in Chess.
+    // For games without loud moves, this is identical to `alpha_beta`.
+    pub fn alpha_beta_quiescent<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        if depth == 0 {
+            let ply = MAX_DEPTH - depth;
+            return (self.quiescence(handler, pos, alpha, beta, ply), [None; MAX_DEPTH]);
+        }
+
+        let mut move_iter = handler.get_legal_moves(pos);
+
+        if let Some(mut mv) = move_iter.next() {
+            let mut m = alpha;
+            let mut pv = [None; MAX_DEPTH];
+
+            loop {
+                let (t, mut line) = self.alpha_beta_quiescent::<THandler, TPosition, MAX_DEPTH>(
+                    handler,
+                    pos.play_move(mv),
+                    depth - 1,
+                    -beta,
+                    -m,
+                );
+                let t = -t;
+                line[MAX_DEPTH - depth] = Some(mv);
+
+                if t > m {
+                    m = t;
+                    pv = line;
+                }
+
+                if m >= beta {
+                    return (m, line);
+                }
+
+                if let Some(new_mv) = move_iter.next() {
+                    mv = new_mv;
+                } else {
+                    break;
+                }
+            }
+
+            (m, pv)
+        } else {
+            self.increment_leaf_count();
+            (handler.terminal_eval(pos, depth, MAX_DEPTH), [None; MAX_DEPTH])
+        }
+    }
+
+    // Searches only the loud moves given by `GameHandler::get_loud_moves` until the position
+    // is quiet, where the side to move may stand pat on the static evaluation
+    // instead of playing a loud move, so the evaluation is never below the stand-pat score
+    // unless it fails low. The stand-pat score alone fails high if it is at least `beta`.
+    // Here `ply` is the number of plies from the root, which is recorded as the selective depth,
+    // and positions are evaluated as leaves `ply` plies from the root, even past the horizon,
+    // so that mate scores count the plies of the loud moves played out to reach them.
+    // `alpha_beta_pruned` resolves its horizon nodes in Chess with this search.
+    pub fn quiescence<THandler, TPosition>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+        ply: usize,
+    ) -> <THandler as GameHandler<TPosition>>::Eval
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        self.update_seldepth(ply);
+        self.increment_leaf_count();
+        let stand_pat = handler.evaluate(pos, 0, ply);
+        if stand_pat >= beta {
+            return stand_pat;
+        }
+
+        let mut m = std::cmp::max(alpha, stand_pat);
+        for mv in handler.get_loud_moves(pos) {
+            let t = -self.quiescence(handler, pos.play_move(mv), -beta, -m, ply + 1);
+            if t > m {
+                m = t;
+            }
+            if m >= beta {
+                return m;
+            }
+        }
+        m
+    }
+
+    // Algorithm B, using an explicit stack of frames instead of recursion,
+    // so that very deep trees cannot overflow the call stack.
+    // Each frame holds the state of one invocation of `alpha_beta` that is waiting
+    // on the result of a child node, and the results are identical to `alpha_beta`,
+    // including the number of leaf nodes evaluated.
+    pub fn alpha_beta_iter<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        struct Frame<TPos, TEval, TMove, TIter, const SIZE: usize> {
+            pos: TPos,
+            depth: usize,
+            beta: TEval,
+            m: TEval,
+            pv: [Option<TMove>; SIZE],
+            mv: TMove,
+            move_iter: TIter,
+        }
+
+        let mut stack = Vec::with_capacity(MAX_DEPTH);
+        // The node to be entered next, given as the arguments a recursive call would receive.
+        let mut pending = (pos, depth, alpha, beta);
+        // The result of the most recently completed node.
+        let mut completed: EvalAndPV<THandler, TPosition, MAX_DEPTH>;
+
+        loop {
+            let (pos, depth, alpha, beta) = pending;
+            // Statement 5.
+            if depth == 0 {
+                self.increment_leaf_count();
+                completed = (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+            } else {
+                // Statement 4.
+                let mut move_iter = handler.get_legal_moves(pos);
+                if let Some(mv) = move_iter.next() {
+                    // Statement 6.
+                    stack.push(Frame {
+                        pos,
+                        depth,
+                        beta,
+                        m: alpha,
+                        pv: [None; MAX_DEPTH],
+                        mv,
+                        move_iter,
+                    });
+                    // Statement 9.
+                    pending = (pos.play_move(mv), depth - 1, -beta, -alpha);
+                    continue;
+                }
+                // Statement 5.
+                self.increment_leaf_count();
+                completed = (handler.terminal_eval(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+            }
+
+            // Propagate the completed result up the stack until a frame has another child to search.
+            loop {
+                let Some(frame) = stack.last_mut() else {
+                    return completed;
+                };
+                let (t, mut line) = completed;
+                let t = -t;
+                line[MAX_DEPTH - frame.depth] = Some(frame.mv);
+
+                if t > frame.m {
+                    frame.m = t;
+                    frame.pv = line;
+                }
+
+                // Statement 10.
+                if frame.m >= frame.beta {
+                    completed = (frame.m, line);
+                    stack.pop();
+                    continue;
+                }
+
+                if let Some(new_mv) = frame.move_iter.next() {
+                    frame.mv = new_mv;
+                    pending = (
+                        frame.pos.play_move(new_mv),
+                        frame.depth - 1,
+                        -frame.beta,
+                        -frame.m,
+                    );
+                    break;
+                }
+
+                completed = (frame.m, frame.pv);
+                stack.pop();
+            }
+        }
+    }
+
+    // Algorithm B, updating a single game state in-place with `make_move` and `unmake_move`
+    // instead of creating a new game state for every node with `play_move`.
+    // The game state is restored before returning, and the results are identical to `alpha_beta`.
+    pub fn alpha_beta_inplace<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: &mut TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: MakeUnmake,
+    {
+        if depth == 0 {
+            self.increment_leaf_count();
+            return (handler.evaluate(*pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+        }
+
+        // The move iterator takes a copy of the game state,
+        // so it is unaffected by the in-place updates below.
+        let mut move_iter = handler.get_legal_moves(*pos).peekable();
+        if move_iter.peek().is_none() {
+            self.increment_leaf_count();
+            return (handler.terminal_eval(*pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+        }
+
+        let mut m = alpha;
+        let mut pv = [None; MAX_DEPTH];
+
+        for mv in move_iter {
+            let undo = pos.make_move(mv);
+            let (t, mut line) = self.alpha_beta_inplace::<THandler, TPosition, MAX_DEPTH>(
+                handler,
+                pos,
+                depth - 1,
+                -beta,
+                -m,
+            );
+            pos.unmake_move(undo);
+            let t = -t;
+            line[MAX_DEPTH - depth] = Some(mv);
+
+            if t > m {
+                m = t;
+                pv = line;
+            }
+
+            if m >= beta {
+                return (m, line);
+            }
+        }
+
+        (m, pv)
+    }
+
+    // Algorithm B, backed by a transposition table.
+    // Before its moves are searched, each node is looked up in `tt`, and a stored bound
+    // from a search of the same depth that already falls outside the window is returned directly.
+    // An exact entry inside the window is searched again so that the principal variation
+    // can be recovered, but its best move is searched first. Every node's result is then stored.
+    // Only entries of exactly the same depth are used for cutoffs, so that the evaluation
+    // is always the same as `alpha_beta`, and only the move ordering and leaf count differ.
+    // If `IID` is set, a node of at least `IID_MIN_DEPTH` plies with no stored best move
+    // is first searched `IID_REDUCTION` plies shallower (internal iterative deepening),
+    // which fills in a best move to try first when the table is cold.
+    // This is skipped where the shallower search would stop above `GameHandler::plies_to_leaves`.
+    pub fn alpha_beta_tt<THandler, TPosition, const MAX_DEPTH: usize, const IID: bool>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+        tt: &mut TranspositionTable<THandler, TPosition>,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition + ZobristKey,
+    {
+        self.alpha_beta_tt_node::<THandler, TPosition, MAX_DEPTH, IID>(
+            handler,
+            (pos, pos.key()),
+            depth,
+            alpha,
+            beta,
+            tt,
+        )
+    }
+
+    // A node of `alpha_beta_tt`, given with its key, which is carried down from the root
+    // through `ZobristKey::play_move_with_key` rather than computed at every node.
+    fn alpha_beta_tt_node<THandler, TPosition, const MAX_DEPTH: usize, const IID: bool>(
+        &mut self,
+        handler: &THandler,
+        (pos, key): (TPosition, u64),
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+        tt: &mut TranspositionTable<THandler, TPosition>,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition + ZobristKey,
+    {
+        if depth == 0 {
+            self.increment_leaf_count();
+            return (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+        }
+
+        let mut hash_move = None;
+        if let Some(entry) = tt.probe_key(key) {
+            if entry.depth == depth {
+                match entry.bound {
+                    Bound::Exact | Bound::Lower if entry.eval >= beta => {
+                        return (entry.eval, [None; MAX_DEPTH]);
+                    }
+                    Bound::Exact | Bound::Upper if entry.eval <= alpha => {
+                        return (alpha, [None; MAX_DEPTH]);
+                    }
+                    _ => {}
+                }
+            }
+            hash_move = entry.best_move;
+        }
+
+        let mut moves = handler.get_legal_moves(pos).collect::<Vec<_>>();
+        if moves.is_empty() {
+            self.increment_leaf_count();
+            return (handler.terminal_eval(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+        }
+
+        let reduced_depth = depth.saturating_sub(IID_REDUCTION);
+        if hash_move.is_none()
+            && IID
+            && depth >= IID_MIN_DEPTH
+            && handler.plies_to_leaves(pos).is_none_or(|plies| plies <= reduced_depth)
+        {
+            self.alpha_beta_tt_node::<THandler, TPosition, MAX_DEPTH, IID>(
+                handler,
+                (pos, key),
+                reduced_depth,
+                alpha,
+                beta,
+                tt,
+            );
+            hash_move = tt.probe_key(key).and_then(|entry| entry.best_move);
+        }
+        if let Some(index) = moves.iter().position(|&mv| Some(mv) == hash_move) {
+            moves[..=index].rotate_right(1);
+        }
+
+        let mut m = alpha;
+        let mut pv = [None; MAX_DEPTH];
+        let mut best_move = None;
+
+        for (move_index, mv) in moves.into_iter().enumerate() {
+            let (t, mut line) = self.alpha_beta_tt_node::<THandler, TPosition, MAX_DEPTH, IID>(
+                handler,
+                pos.play_move_with_key(key, mv),
+                depth - 1,
+                -beta,
+                -m,
+                tt,
+            );
+            let t = -t;
+            line[MAX_DEPTH - depth] = Some(mv);
+
+            if t > m {
+                m = t;
+                pv = line;
+                best_move = Some(mv);
+            }
+
+            if m >= beta {
+                self.record_cutoff(move_index);
+                tt.store_key(key, depth, m, Bound::Lower, Some(mv));
+                return (m, line);
+            }
+        }
+
+        // No move raised the evaluation above `alpha` exactly when the search failed low.
+        let bound = if best_move.is_some() {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        tt.store_key(key, depth, m, bound, best_move.or(hash_move));
+        (m, pv)
+    }
+
+    // Algorithm B, backed by the caller's transposition table `tt` if one is given,
+    // as described at `alpha_beta_tt` (without internal iterative deepening).
+    // The table keeps its entries between searches, so it must only be reused for searches
+    // of the same game tree and `MAX_DEPTH`, since its evaluations depend on both.
+    // The evaluation is always the same as that of `alpha_beta`,
+    // which this falls back to when no table is given.
+    pub fn alpha_beta_cached<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+        tt: Option<&mut TranspositionTable<THandler, TPosition>>,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition + ZobristKey,
+    {
+        match tt {
+            Some(tt) => self.alpha_beta_tt::<THandler, TPosition, MAX_DEPTH, false>(
+                handler, pos, depth, alpha, beta, tt,
+            ),
+            None => {
+                self.alpha_beta::<THandler, TPosition, MAX_DEPTH>(handler, pos, depth, alpha, beta)
+            }
+        }
+    }
+
+    // Algorithm B with the root moves searched in parallel, and no other change to the algorithm.
+    // This is `alpha_beta_root_parallel` with `ParallelKillers::Off`,
+    // so each subtree is searched exactly as `alpha_beta` would.
+    pub fn alpha_beta_parallel<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition> + Sync,
+        TPosition: GamePosition + Sync,
+        <THandler as GameHandler<TPosition>>::Eval: Send + Sync,
+        <TPosition as GamePosition>::Move: Send + Sync,
+    {
+        self.alpha_beta_root_parallel::<THandler, TPosition, MAX_DEPTH>(
+            handler,
+            pos,
+            depth,
+            alpha,
+            beta,
+            ParallelKillers::Off,
+        )
+    }
+
+    // Algorithm B with the root moves searched in parallel.
+    // The first root move is searched alone to establish a lower bound,
+    // and the remaining root moves are then searched in parallel against that bound,
+    // each by a separate `Searcher` from `worker` that counts its leaves in this one.
+    // Only the root is split. Unless `killers` is `ParallelKillers::Off`, moves below the root
+    // are ordered by `GameHandler::order_moves` and then by a `KillerTable`,
+    // which is shared by every worker or kept separately for each root move as `killers` gives.
+    // The evaluation is identical to that of `alpha_beta`, as is the principal variation
+    // when no two lines share an evaluation.
+    pub fn alpha_beta_root_parallel<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+        killers: ParallelKillers,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition> + Sync,
+        TPosition: GamePosition + Sync,
+        <THandler as GameHandler<TPosition>>::Eval: Send + Sync,
+        <TPosition as GamePosition>::Move: Send + Sync,
+    {
+        if depth == 0 {
+            self.increment_leaf_count();
+            return (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+        }
+
+        let moves: Vec<_> = handler.get_legal_moves(pos).collect();
+        let Some((&first, rest)) = moves.split_first() else {
+            self.increment_leaf_count();
+            return (handler.terminal_eval(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+        };
+
+        let shared_killers = KillerTable::new(MAX_DEPTH);
+        let shared = Some(&shared_killers).filter(|_| killers != ParallelKillers::Off);
+        let (t, line) = self.alpha_beta_root_move::<THandler, TPosition, MAX_DEPTH>(
+            handler,
+            (pos, first),
+            depth,
+            (alpha, beta),
+            shared,
+        );
+        let mut m = alpha;
+        let mut pv = [None; MAX_DEPTH];
+        if t > m {
+            m = t;
+            pv = line;
+        }
+        if m >= beta {
+            return (m, line);
+        }
+
+        let bound = m;
+        let parent = &*self;
+        let results = rest
+            .par_iter()
+            .map(|&mv| {
+                let mut searcher = parent.worker();
+                if killers == ParallelKillers::PerRootMove {
+                    let own_killers = KillerTable::new(MAX_DEPTH);
+                    searcher.alpha_beta_root_move::<THandler, TPosition, MAX_DEPTH>(
+                        handler,
+                        (pos, mv),
+                        depth,
+                        (bound, beta),
+                        Some(&own_killers),
+                    )
+                } else {
+                    searcher.alpha_beta_root_move::<THandler, TPosition, MAX_DEPTH>(
+                        handler,
+                        (pos, mv),
+                        depth,
+                        (bound, beta),
+                        shared,
+                    )
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // The results are taken in the order of the moves, as `alpha_beta` would,
+        // so that the first of several moves with the best evaluation is chosen.
+        for (t, line) in results {
+            if t > m && m < beta {
+                m = t;
+                pv = line;
+            }
+        }
+
+        if depth == MAX_DEPTH {
+            debug_assert_line(handler, pos, alpha, beta, (m, pv));
+        }
+        (m, pv)
+    }
+
+    // The search of the root move `mv` of `pos` by `alpha_beta_root_parallel`
+    // with the root window `(alpha, beta)` given by `window`, which is by `alpha_beta`
+    // or by `alpha_beta_killers` with `killers` if there is a table.
+    // The evaluation and line are from the perspective of the root.
+    fn alpha_beta_root_move<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        (pos, mv): (TPosition, <TPosition as GamePosition>::Move),
+        depth: usize,
+        window: (
+            <THandler as GameHandler<TPosition>>::Eval,
+            <THandler as GameHandler<TPosition>>::Eval,
+        ),
+        killers: Option<&KillerTable<<TPosition as GamePosition>::Move>>,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        let (alpha, beta) = window;
+        let child = pos.play_move(mv);
+        let (t, mut line) = match killers {
+            Some(killers) => self.alpha_beta_killers::<THandler, TPosition, MAX_DEPTH>(
+                handler,
+                child,
+                depth - 1,
+                -beta,
+                -alpha,
+                killers,
+            ),
+            None => self.alpha_beta::<THandler, TPosition, MAX_DEPTH>(
+                handler,
+                child,
+                depth - 1,
+                -beta,
+                -alpha,
+            ),
+        };
+        line[MAX_DEPTH - depth] = Some(mv);
+        (-t, line)
+    }
+
+    // Algorithm B with the killer heuristic, trying the killer moves of each ply first,
+    // as kept by this searcher in a `KillerTable` of two moves per ply,
+    // and the remaining moves in the order given by `GameHandler::order_moves`.
+    // Only quiet moves (as given by `GameHandler::is_quiet_move`) that cause a cutoff
+    // are recorded as killer moves, and they are kept from one search to the next
+    // until `clear_killers` is called or a different game or `MAX_DEPTH` is searched.
+    // Since only the move ordering differs, the evaluation is always the same as `alpha_beta`,
+    // as is the principal variation when no two moves share an evaluation,
+    // while `alpha_beta` itself is kept as the unordered algorithm of the benchmarks.
+    pub fn alpha_beta_ordered<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+        <TPosition as GamePosition>::Move: Send + 'static,
+    {
+        let killers = self
+            .killers
+            .take()
+            .filter(|&(max_depth, _)| max_depth == MAX_DEPTH)
+            .and_then(|(_, killers)| {
+                killers
+                    .downcast::<KillerTable<<TPosition as GamePosition>::Move>>()
+                    .ok()
+            })
+            .unwrap_or_else(|| Box::new(KillerTable::new(MAX_DEPTH)));
+        let result = self.alpha_beta_killers::<THandler, TPosition, MAX_DEPTH>(
+            handler, pos, depth, alpha, beta, &killers,
+        );
+        self.killers = Some((MAX_DEPTH, killers));
+        result
+    }
+
+    // Algorithm B, trying the killer moves of each ply first, then the other moves
+    // as ordered by `GameHandler::order_moves`, and recording the quiet moves that cause cutoffs.
+    fn alpha_beta_killers<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+        killers: &KillerTable<<TPosition as GamePosition>::Move>,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        if depth == 0 {
+            self.increment_leaf_count();
+            return (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+        }
+
+        let mut moves: Vec<_> = handler.get_legal_moves(pos).collect();
+        if moves.is_empty() {
+            self.increment_leaf_count();
+            return (handler.terminal_eval(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+        }
+        let ply = MAX_DEPTH - depth;
+        handler.order_moves(pos, &mut moves);
+        killers.order(ply, &mut moves);
+
+        let mut m = alpha;
+        let mut pv = [None; MAX_DEPTH];
+
+        for (move_index, mv) in moves.into_iter().enumerate() {
+            let (t, mut line) = self.alpha_beta_killers::<THandler, TPosition, MAX_DEPTH>(
+                handler,
+                pos.play_move(mv),
+                depth - 1,
+                -beta,
+                -m,
+                killers,
+            );
+            let t = -t;
+            line[ply] = Some(mv);
+
+            if t > m {
+                m = t;
+                pv = line;
+            }
+
+            if m >= beta {
+                self.record_cutoff(move_index);
+                if handler.is_quiet_move(pos, mv) {
+                    killers.record(ply, mv);
+                }
+                return (m, line);
+            }
+        }
+
+        (m, pv)
+    }
+
+    // Algorithm C.
+    pub fn p_alpha_beta<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        // A node `MAX_DEPTH` plies ahead of the root is considered a leaf.
+        // Statement 5.
+        if depth == 0 {
+            self.increment_leaf_count();
+            return (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+        }
+
+        // Statement 4.
+        let mut move_iter = handler.get_legal_moves(pos);
+
+        if let Some(mv) = move_iter.next() {
+            // Statement 6.
+            let (mut m, mut pv) = self.p_alpha_beta::<THandler, TPosition, MAX_DEPTH>(
+                handler,
+                pos.play_move(mv),
+                depth - 1,
+            );
+            m = -m;
+            pv[MAX_DEPTH - depth] = Some(mv);
+
+            // Statement 7.
+            for mv in move_iter {
+                let next_pos = pos.play_move(mv);
+
+                // Statement 9.
+                let t = -self
+                    .f_alpha_beta::<THandler, TPosition, MAX_DEPTH>(
+                        handler,
+                        next_pos,
+                        depth - 1,
+                        -m - <THandler as GameHandler<TPosition>>::EVAL_EPSILON,
+                        -m,
+                    )
+                    .0;
+
+                // Statement 10.
+                if t > m {
+                    // Statement 11.
+                    // In Muszycka & Shinghal (1985), this statement was erroneously written as
+                    // `m = -alphabeta(p_i, -MAXINT, -t);` as opposed to
+                    // `m = -falphabeta(p_i, -MAXINT, -t);`. Fishburn & Finkel (1980)
+                    // originally describe this algorithm correctly.
+                    let (t, mut line) = self.f_alpha_beta::<THandler, TPosition, MAX_DEPTH>(
+                        handler,
+                        next_pos,
+                        depth - 1,
+                        <THandler as GameHandler<TPosition>>::EVAL_MINIMUM,
+                        -t,
+                    );
+                    m = -t;
+                    line[MAX_DEPTH - depth] = Some(mv);
+                    pv = line;
+                }
+            }
+
+            if depth == MAX_DEPTH {
+                debug_assert_line(
+                    handler,
+                    pos,
+                    <THandler as GameHandler<TPosition>>::EVAL_MINIMUM,
+                    <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM,
+                    (m, pv),
+                );
+            }
+            (m, pv)
+        } else {
+            // Statement 5.
+            self.increment_leaf_count();
+            (handler.terminal_eval(pos, depth, MAX_DEPTH), [None; MAX_DEPTH])
+        }
+    }
+
+    pub fn f_alpha_beta<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        // A node `MAX_DEPTH` plies ahead of the root is considered a leaf.
+        // Statement 5.
+        if depth == 0 {
+            self.increment_leaf_count();
+            return (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+        }
+
+        // Statement 4.
+        let mut move_iter = handler.get_legal_moves(pos);
+
+        if let Some(mut mv) = move_iter.next() {
+            // Statement 6.
+            let mut m = <THandler as GameHandler<TPosition>>::EVAL_MINIMUM;
+            let mut pv = [None; MAX_DEPTH];
+
+            loop {
+                // Statement 9.
+                let (t, mut line) = self.f_alpha_beta::<THandler, TPosition, MAX_DEPTH>(
+                    handler,
+                    pos.play_move(mv),
+                    depth - 1,
+                    -beta,
+                    -std::cmp::max(m, alpha),
+                );
+                let t = -t;
+                line[MAX_DEPTH - depth] = Some(mv);
+
+                if t > m {
+                    m = t;
+                    pv = line;
+                }
+
+                // Statement 10.
+                if m >= beta {
+                    return (m, line);
+                }
+
+                if let Some(new_mv) = move_iter.next() {
+                    mv = new_mv;
+                } else {
+                    break;
+                }
+            }
+
+            (m, pv)
+        } else {
+            // Statement 5.
+            self.increment_leaf_count();
+            (handler.terminal_eval(pos, depth, MAX_DEPTH), [None; MAX_DEPTH])
+        }
+    }
+
+    // NegaC*, which converges on the minimax value from below with null-window searches.
+    // Starting from `EVAL_MINIMUM` as a lower bound `g`, each probe searches
+    // the window `(g, g + EVAL_EPSILON)` with the fail-soft `f_alpha_beta`.
+    // A fail-high returns a new, higher lower bound,
+    // while a fail-low shows that the value is at most `g`, which is then the minimax value.
+    // Since the line returned by a null-window search need not be a principal variation,
+    // the value is searched once more with a window of `EVAL_EPSILON` either side of it.
+    pub fn negac_star<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        let epsilon = <THandler as GameHandler<TPosition>>::EVAL_EPSILON;
+        let mut g = <THandler as GameHandler<TPosition>>::EVAL_MINIMUM;
+
+        while g < <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM {
+            let (t, _) = self.f_alpha_beta::<THandler, TPosition, MAX_DEPTH>(
+                handler,
+                pos,
+                depth,
+                g,
+                g + epsilon,
+            );
+            if t <= g {
+                break;
+            }
+            g = t;
+        }
+
+        self.f_alpha_beta::<THandler, TPosition, MAX_DEPTH>(
+            handler,
+            pos,
+            depth,
+            g - epsilon,
+            g + epsilon,
+        )
+    }
+
+    // MTD(f) (Plaat et al., 1996), which converges on the minimax value from a first guess `f`
+    // with null-window searches of the fail-soft `f_alpha_beta`.
+    // Each probe tests whether the value is at least `beta`
+    // with the window `(beta - EVAL_EPSILON, beta)`, so `EVAL_EPSILON` is the step between windows,
+    // and must be the smallest difference between two evaluations for the probes to be exact.
+    // A fail-high returns a new lower bound and a fail-low a new upper bound,
+    // and the next probe is placed at the bound just returned (one `EVAL_EPSILON` above it
+    // if it is the lower bound), so the bounds meet at the minimax value however far `f` is.
+    // As in `negac_star`, the value is then searched once more with a window of `EVAL_EPSILON`
+    // either side of it, so that the line returned is a principal variation.
+    pub fn mtdf<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        f: <THandler as GameHandler<TPosition>>::Eval,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        let epsilon = <THandler as GameHandler<TPosition>>::EVAL_EPSILON;
+        let mut lower = <THandler as GameHandler<TPosition>>::EVAL_MINIMUM;
+        let mut upper = <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM;
+        let mut g = f.clamp(lower, upper);
+
+        while lower < upper {
+            let beta = if g == lower { g + epsilon } else { g };
+            let (t, _) = self.f_alpha_beta::<THandler, TPosition, MAX_DEPTH>(
+                handler,
+                pos,
+                depth,
+                beta - epsilon,
+                beta,
+            );
+            g = t;
+            if g < beta {
+                upper = g;
+            } else {
+                lower = g;
+            }
+        }
+
+        let result = self.f_alpha_beta::<THandler, TPosition, MAX_DEPTH>(
+            handler,
+            pos,
+            depth,
+            g - epsilon,
+            g + epsilon,
+        );
+        if depth == MAX_DEPTH {
+            debug_assert_line(handler, pos, g - epsilon, g + epsilon, result);
+        }
+        result
+    }
+
+    // Algorithm D.
+    pub fn pvs<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        // A node `MAX_DEPTH` plies ahead of the root is considered a leaf.
+        // Statement 5.
+        if depth == 0 {
+            self.increment_leaf_count();
+            return (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+        }
+
+        // Statement 4.
+        let mut move_iter = handler.get_legal_moves(pos);
+
+        if let Some(mv) = move_iter.next() {
+            // Statement 6.
+            let (mut m, mut pv) = self.pvs::<THandler, TPosition, MAX_DEPTH>(
+                handler,
+                pos.play_move(mv),
+                depth - 1,
+                -beta,
+                -alpha,
+            );
+            m = -m;
+            pv[MAX_DEPTH - depth] = Some(mv);
+
+            // Statement 7.
+            if m < beta {
+                // Statement 8.
+                for mv in move_iter {
+                    // Statement 10.
+                    let bound = std::cmp::max(m, alpha);
+
+                    let next_pos = pos.play_move(mv);
+
+                    // Statement 11.
+                    let t = -self
+                        .pvs::<THandler, TPosition, MAX_DEPTH>(
+                            handler,
+                            next_pos,
+                            depth - 1,
+                            -bound - <THandler as GameHandler<TPosition>>::EVAL_EPSILON,
+                            -bound,
+                        )
+                        .0;
+
+                    // Statement 12.
+                    if t > m {
+                        // Statement 13.
+                        let (value, mut line) = self.pvs::<THandler, TPosition, MAX_DEPTH>(
+                            handler,
+                            next_pos,
+                            depth - 1,
+                            -beta,
+                            -t,
+                        );
+                        m = -value;
+                        line[MAX_DEPTH - depth] = Some(mv);
+                        pv = line;
+                    }
+                    // Statement 14.
+                    if m >= beta {
+                        return (m, pv);
+                    }
+                }
+            }
+
+            if depth == MAX_DEPTH {
+                debug_assert_line(handler, pos, alpha, beta, (m, pv));
+            }
+            (m, pv)
+        } else {
+            // Statement 5.
+            self.increment_leaf_count();
+            (handler.terminal_eval(pos, depth, MAX_DEPTH), [None; MAX_DEPTH])
+        }
+    }
+
+    // Algorithm D, stopping once the leaf count reaches the limit set by `set_node_limit`.
+    // Once it is reached, each node keeps the line of its first move, ignoring the searches
+    // of later moves which were cut short, so the root always has a move.
+    // Without a limit, or if it is not reached, the result is the same as that of `pvs`.
+    pub fn pvs_limited<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        if depth == 0 {
+            self.increment_leaf_count();
+            return (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+        }
+
+        let mut move_iter = handler.get_legal_moves(pos);
+
+        if let Some(mv) = move_iter.next() {
+            let (mut m, mut pv) = self.pvs_limited::<THandler, TPosition, MAX_DEPTH>(
+                handler,
+                pos.play_move(mv),
+                depth - 1,
+                -beta,
+                -alpha,
+            );
+            m = -m;
+            pv[MAX_DEPTH - depth] = Some(mv);
+
+            if self.node_limit_reached() {
+                return (m, pv);
+            }
+
+            if m < beta {
+                for mv in move_iter {
+                    let bound = std::cmp::max(m, alpha);
+
+                    let next_pos = pos.play_move(mv);
+
+                    let t = -self
+                        .pvs_limited::<THandler, TPosition, MAX_DEPTH>(
+                            handler,
+                            next_pos,
+                            depth - 1,
+                            -bound - <THandler as GameHandler<TPosition>>::EVAL_EPSILON,
+                            -bound,
+                        )
+                        .0;
+
+                    if self.node_limit_reached() {
+                        return (m, pv);
+                    }
+
+                    if t > m {
+                        let (value, mut line) = self.pvs_limited::<THandler, TPosition, MAX_DEPTH>(
+                            handler,
+                            next_pos,
+                            depth - 1,
+                            -beta,
+                            -t,
+                        );
+                        if self.node_limit_reached() {
+                            return (m, pv);
+                        }
+                        m = -value;
+                        line[MAX_DEPTH - depth] = Some(mv);
+                        pv = line;
+                    }
+                    if m >= beta {
+                        return (m, pv);
+                    }
+                }
+            }
+
+            if depth == MAX_DEPTH {
+                debug_assert_line(handler, pos, alpha, beta, (m, pv));
+            }
+            (m, pv)
+        } else {
+            self.increment_leaf_count();
+            (handler.terminal_eval(pos, depth, MAX_DEPTH), [None; MAX_DEPTH])
+        }
+    }
+
+    // Algorithm D in analysis mode, which never cuts off at the root so that every root move
+    // gets an exact score, as analysis engines show for each move.
+    // Each root move is searched by `pvs` with the full window,
+    // so the null-window searches are only used below the root.
+    // The evaluation and principal variation are those of the first best move in generation order,
+    // which is the move `pvs` would choose, and the scores are sorted as in `root_scores`.
+    pub fn pvs_analysis<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+    ) -> (EvalAndPV<THandler, TPosition, MAX_DEPTH>, RootScores<THandler, TPosition>)
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        if depth == 0 {
+            self.increment_leaf_count();
+            return ((handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]), Vec::new());
+        }
+
+        let lines = self.root_lines::<THandler, TPosition, MAX_DEPTH>(handler, pos, depth, true);
+        let Some(&result) = lines.first() else {
+            self.increment_leaf_count();
+            return ((handler.terminal_eval(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]), Vec::new());
+        };
+        if depth == MAX_DEPTH {
+            debug_assert_line(
+                handler,
+                pos,
+                <THandler as GameHandler<TPosition>>::EVAL_MINIMUM,
+                <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM,
+                result,
+            );
+        }
+        let scores = lines
+            .into_iter()
+            .map(|(eval, line)| (line[MAX_DEPTH - depth].unwrap(), eval))
+            .collect();
+        (result, scores)
+    }
+
+    // Algorithm D with the depth chosen at runtime, as `alpha_beta_dyn` is to `alpha_beta`.
+    pub fn pvs_dyn<THandler, TPosition>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+    ) -> EvalAndLine<THandler, TPosition>
+    where
+        THandler: GameHandler<TPosition> + 'static,
+        TPosition: GamePosition + 'static,
+    {
+        let (start, leaves) = (Instant::now(), self.get_leaf_count());
+        let (eval, mut line) = self.pvs_dyn_node(handler, pos, depth, depth, alpha, beta);
+        line.reverse();
+        let (nodes, elapsed) = self.record_search_time(start, leaves);
+        self.report_info(depth, eval, line.first().copied(), nodes, elapsed);
+        (eval, line)
+    }
+
+    // The recursive part of `pvs_dyn`, building each line from the leaf upwards.
+    fn pvs_dyn_node<THandler, TPosition>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        max_depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+    ) -> EvalAndLine<THandler, TPosition>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        if depth == 0 {
+            self.increment_leaf_count();
+            return (handler.evaluate(pos, depth, max_depth), Vec::new());
+        }
+
+        let mut move_iter = handler.get_legal_moves(pos);
+
+        if let Some(mv) = move_iter.next() {
+            let (mut m, mut pv) =
+                self.pvs_dyn_node(handler, pos.play_move(mv), depth - 1, max_depth, -beta, -alpha);
+            m = -m;
+            pv.push(mv);
+
+            if m < beta {
+                for mv in move_iter {
+                    let bound = std::cmp::max(m, alpha);
+                    let next_pos = pos.play_move(mv);
+
+                    let t = -self
+                        .pvs_dyn_node(
+                            handler,
+                            next_pos,
+                            depth - 1,
+                            max_depth,
+                            -bound - <THandler as GameHandler<TPosition>>::EVAL_EPSILON,
+                            -bound,
+                        )
+                        .0;
+
+                    if t > m {
+                        let (value, mut line) =
+                            self.pvs_dyn_node(handler, next_pos, depth - 1, max_depth, -beta, -t);
+                        m = -value;
+                        line.push(mv);
+                        pv = line;
+                    }
+                    if m >= beta {
+                        return (m, pv);
+                    }
+                }
+            }
+
+            (m, pv)
+        } else {
+            self.increment_leaf_count();
+            (handler.terminal_eval(pos, depth, max_depth), Vec::new())
+        }
+    }
+
+    // Algorithm D, additionally recording the refutation found at the root.
+    // Whenever the null-window search of a root move fails high (Statement 12),
+    // the line returned by that null-window search is what refuted the assumption
+    // that the move was no better than the current best, and is recorded alongside the move.
+    // Below the root, this is identical to `pvs`, so the evaluation, principal variation
+    // and number of leaf nodes evaluated are all unchanged.
+    pub fn pvs_with_refutations<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        alpha: <THandler as GameHandler<TPosition>>::Eval,
+        beta: <THandler as GameHandler<TPosition>>::Eval,
+    ) -> (
+        EvalAndPV<THandler, TPosition, MAX_DEPTH>,
+        Refutations<TPosition, MAX_DEPTH>,
+    )
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        let mut refutations = Vec::new();
+
+        if depth == 0 {
+            self.increment_leaf_count();
+            return (
+                (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]),
+                refutations,
+            );
+        }
+
+        let mut move_iter = handler.get_legal_moves(pos);
+
+        if let Some(mv) = move_iter.next() {
+            let (mut m, mut pv) = self.pvs::<THandler, TPosition, MAX_DEPTH>(
+                handler,
+                pos.play_move(mv),
+                depth - 1,
+                -beta,
+                -alpha,
+            );
+            m = -m;
+            pv[MAX_DEPTH - depth] = Some(mv);
+
+            if m < beta {
+                for mv in move_iter {
+                    let bound = std::cmp::max(m, alpha);
+
+                    let next_pos = pos.play_move(mv);
+
+                    let (t, mut refutation) = self.pvs::<THandler, TPosition, MAX_DEPTH>(
+                        handler,
+                        next_pos,
+                        depth - 1,
+                        -bound - <THandler as GameHandler<TPosition>>::EVAL_EPSILON,
+                        -bound,
+                    );
+                    let t = -t;
+
+                    if t > m {
+                        refutation[MAX_DEPTH - depth] = Some(mv);
+                        refutations.push((mv, refutation));
+
+                        let (value, mut line) = self.pvs::<THandler, TPosition, MAX_DEPTH>(
+                            handler,
+                            next_pos,
+                            depth - 1,
+                            -beta,
+                            -t,
+                        );
+                        m = -value;
+                        line[MAX_DEPTH - depth] = Some(mv);
+                        pv = line;
+                    }
+                    if m >= beta {
+                        return ((m, pv), refutations);
+                    }
+                }
+            }
+
+            ((m, pv), refutations)
+        } else {
+            self.increment_leaf_count();
+            (
+                (handler.terminal_eval(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]),
+                refutations,
+            )
+        }
+    }
+
+    // Algorithm E.
+    pub fn scout<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        // A node `MAX_DEPTH` plies ahead of the root is considered a leaf.
+        // Statement 5.
+        if depth == 0 {
+            self.increment_leaf_count();
+            return (handler.evaluate(pos, depth, MAX_DEPTH), [None; MAX_DEPTH]);
+        }
+
+        // Statement 4.
+        let mut move_iter = handler.get_legal_moves(pos);
+
+        if let Some(mv) = move_iter.next() {
+            // Statement 6.
+            let (mut m, mut pv) = self.scout::<THandler, TPosition, MAX_DEPTH>(
+                handler,
+                pos.play_move(mv),
+                depth - 1,
+            );
+            m = -m;
+            pv[MAX_DEPTH - depth] = Some(mv);
+
+            // Statement 7.
+            let op = true;
+
+            // Statement 8.
+            for mv in move_iter {
+                let next_pos = pos.play_move(mv);
+
+                // Statement 9.
+                if !self.test::<THandler, TPosition>(
+                    handler,
+                    next_pos,
+                    depth - 1,
+                    MAX_DEPTH,
+                    -m,
+                    !op,
+                ) {
+                    let (new_m, mut line) = self.scout::<THandler, TPosition, MAX_DEPTH>(
+                        handler,
+                        next_pos,
+                        depth - 1,
+                    );
+                    let new_m = -new_m;
+                    line[MAX_DEPTH - depth] = Some(mv);
+                    m = new_m;
+                    pv = line;
+                }
+            }
+
+            if depth == MAX_DEPTH {
+                debug_assert_line(
+                    handler,
+                    pos,
+                    <THandler as GameHandler<TPosition>>::EVAL_MINIMUM,
+                    <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM,
+                    (m, pv),
+                );
+            }
+            (m, pv)
+        } else {
+            // Statement 5.
+            self.increment_leaf_count();
+            (handler.terminal_eval(pos, depth, MAX_DEPTH), [None; MAX_DEPTH])
+        }
+    }
+
+    pub fn test<THandler, TPosition>(
+        &mut self,
+        handler: &THandler,
+        pos: TPosition,
+        depth: usize,
+        max_depth: usize,
+        v: <THandler as GameHandler<TPosition>>::Eval,
+        op: bool,
+    ) -> bool
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        // A node `max_depth` plies ahead of the root is considered a leaf.
+        // Statement 5.
+        if depth == 0 {
+            // Statements 6-9.
+            self.increment_leaf_count();
+            return if op {
+                handler.evaluate(pos, depth, max_depth) >= v
+            } else {
+                handler.evaluate(pos, depth, max_depth) > v
+            };
+        }
+
+        // Statement 4.
+        let mut move_iter = handler.get_legal_moves(pos);
+
+        if let Some(mut mv) = move_iter.next() {
+            loop {
+                // Statement 11.
+                if !self.test::<THandler, TPosition>(
+                    handler,
+                    pos.play_move(mv),
+                    depth - 1,
+                    max_depth,
+                    -v,
+                    !op,
+                ) {
+                    return true;
+                }
+
+                if let Some(new_mv) = move_iter.next() {
+                    mv = new_mv;
+                } else {
+                    break;
+                }
+            }
+            // Statement 13.
+            false
+        } else {
+            // Statements 6-9.
+            self.increment_leaf_count();
+            if op {
+                handler.terminal_eval(pos, depth, max_depth) >= v
+            } else {
+                handler.terminal_eval(pos, depth, max_depth) > v
+            }
+        }
+    }
+
+    // Algorithm F.
+    pub fn sss<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        root: TPosition,
+        depth: usize,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        let (result, _) = self.sss_open_list::<THandler, TPosition, MAX_DEPTH, false>(
+            handler,
+            root,
+            depth,
+            usize::MAX,
+        );
+        let result = result.expect("State space operator is faulty");
+        if depth == MAX_DEPTH {
+            debug_assert_line(
+                handler,
+                root,
+                <THandler as GameHandler<TPosition>>::EVAL_MINIMUM,
+                <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM,
+                result,
+            );
+        }
+        result
+    }
+
+    // DUAL*, the dual of Algorithm F, which searches the game tree from the side of the minimizing
+    // player. This is Algorithm F applied to the dual game tree, where every evaluation is negated
+    // and the players swap roles, so that all moves of the root player's opponent are expanded
+    // but only one move of the root player at a time, and `open` is ordered by lower bounds
+    // on the minimax value, starting from `EVAL_MINIMUM` at the root, instead of upper bounds.
+    // The evaluation is always the same as that of `sss`, while the leaves evaluated differ.
+    pub fn dual_star<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        root: TPosition,
+        depth: usize,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        let (result, _) = self.sss_open_list::<THandler, TPosition, MAX_DEPTH, true>(
+            handler,
+            root,
+            depth,
+            usize::MAX,
+        );
+        let (eval, pv) = result.expect("State space operator is faulty");
+        let result = (-eval, pv);
+        if depth == MAX_DEPTH {
+            debug_assert_line(
+                handler,
+                root,
+                <THandler as GameHandler<TPosition>>::EVAL_MINIMUM,
+                <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM,
+                result,
+            );
+        }
+        result
+    }
+
+    // Algorithm F with the `open` list holding at most `capacity` states after each step.
+    // Whenever it grows beyond this, the lowest-merit states are spilled (discarded),
+    // which is the same as removing their moves from the game tree,
+    // so the value found is only a lower bound on the minimax value.
+    // The highest merit of any spilled state is an upper bound on what the spilled states
+    // could have contributed, so if the value found is at least this, it is exact.
+    // Otherwise, the spilled states are recomputed implicitly by raising the lower bound
+    // with null-window searches until one fails low, as in `negac_star`,
+    // which use memory proportional to the depth rather than the width of the tree.
+    // The result is therefore always the minimax value, trading time for space.
+    pub fn sss_capped<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        root: TPosition,
+        depth: usize,
+        capacity: usize,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        let (result, spill_bound) =
+            self.sss_open_list::<THandler, TPosition, MAX_DEPTH, false>(
+                handler, root, depth, capacity,
+            );
+        let mut g = match (result, spill_bound) {
+            (Some(result), None) => return result,
+            (Some((eval, pv)), Some(bound)) if eval >= bound => return (eval, pv),
+            (Some((eval, _)), _) => eval,
+            // Every line to a solved root was spilled.
+            (None, _) => <THandler as GameHandler<TPosition>>::EVAL_MINIMUM,
+        };
+
+        let epsilon = <THandler as GameHandler<TPosition>>::EVAL_EPSILON;
+        while g < <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM {
+            let (t, _) = self.f_alpha_beta::<THandler, TPosition, MAX_DEPTH>(
+                handler,
+                root,
+                depth,
+                g,
+                g + epsilon,
+            );
+            if t <= g {
+                break;
+            }
+            g = t;
+        }
+
+        self.f_alpha_beta::<THandler, TPosition, MAX_DEPTH>(
+            handler,
+            root,
+            depth,
+            g - epsilon,
+            g + epsilon,
+        )
+    }
+
+    // The body of `sss`, `sss_capped` and `dual_star`, returning `None` if `open` runs out
+    // of states before the root is solved, along with the highest merit of any spilled state.
+    // If `DUAL` is set, the dual of the game tree is searched instead, where the players swap roles
+    // and every evaluation is negated, so the merits and the returned evaluation are negated too.
+    fn sss_open_list<THandler, TPosition, const MAX_DEPTH: usize, const DUAL: bool>(
+        &mut self,
+        handler: &THandler,
+        root: TPosition,
+        depth: usize,
+        capacity: usize,
+    ) -> OpenListResult<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition>,
+        TPosition: GamePosition,
+    {
+        let mut open: BinaryHeap<
+            State<
+                TPosition,
+                <THandler as GameHandler<TPosition>>::Eval,
+                <TPosition as GamePosition>::Move,
+                MAX_DEPTH,
+            >,
+        > = BinaryHeap::new();
+
+        open.push(State::Live {
+            node: root,
+            merit: (
+                <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM,
+                [None; MAX_DEPTH],
+            ),
+            depth,
+            line: [None; MAX_DEPTH],
+            iteration: 0,
+        });
+
+        let mut i: usize = 1;
+        let capacity = capacity.max(1);
+        let mut spill_bound = None;
+
+        while let Some(state) = open.pop() {
+            match state {
+                State::Solved {
+                    node: n,
+                    merit: (h, pv),
+                    depth: d,
+                    line: mut l,
+                    iteration: _,
+                } => {
+                    if d == MAX_DEPTH {
+                        return (Some((h, pv)), spill_bound);
+                    }
+                    let mut parent = root;
+                    let path_length = MAX_DEPTH - d - 1;
+                    for mv in l.iter().take(path_length) {
+                        parent = parent.play_move(mv.unwrap());
+                    }
+                    if state.is_max_player(MAX_DEPTH) != DUAL {
+                        if let Some(next_move) = handler
+                            .get_legal_moves(parent)
+                            .skip_while(|&mv| parent.play_move(mv) != n)
+                            .nth(1)
+                        {
+                            l[path_length] = Some(next_move);
+                            for mv in l.iter_mut().skip(path_length + 1) {
+                                *mv = None;
+                            }
+                            // Case 2.
+                            open.push(State::Live {
+                                node: parent.play_move(next_move),
+                                merit: (h, pv),
+                                depth: d,
+                                line: l,
+                                iteration: i,
+                            });
+                        } else {
+                            // Case 3.
+                            open.push(State::Solved {
+                                node: parent,
+                                merit: (h, pv),
+                                depth: d + 1,
+                                line: l,
+                                iteration: i,
+                            });
+                        }
+                    } else {
+                        // Case 1.
+                        open.retain(|&state| {
+                            state
+                                .line()
+                                .iter()
+                                .zip(l.iter())
+                                .take(path_length)
+                                .any(|(&best, &discard)| best != discard)
+                        });
+                        open.push(State::Solved {
+                            node: parent,
+                            merit: (h, pv),
+                            depth: d + 1,
+                            line: l,
+                            iteration: i,
+                        });
+                    }
+                }
+                State::Live {
+                    node: n,
+                    merit: (h, pv),
+                    depth: d,
+                    line: l,
+                    iteration: _,
+                } => {
+                    let mut legal_moves = handler.get_legal_moves(n);
+                    if d == 0 {
+                        // To account for the negamax construct in conjunction with SSS* node evaluation.
+                        self.increment_leaf_count();
+                        let eval = if (((MAX_DEPTH - d) & 1) == 0) != DUAL {
+                            handler.evaluate(n, d, MAX_DEPTH)
+                        } else {
+                            -handler.evaluate(n, d, MAX_DEPTH)
+                        };
+                        // Extension of Case 4. `MAX_DEPTH` plies from root is considered leaf.
+                        open.push(State::Solved {
+                            node: n,
+                            merit: if h < eval { (h, pv) } else { (eval, l) },
+                            depth: d,
+                            line: l,
+                            iteration: i,
+                        });
+                    } else if let Some(first_move) = legal_moves.next() {
+                        let mut line = l;
+                        line[MAX_DEPTH - d] = Some(first_move);
+                        if state.is_max_player(MAX_DEPTH) != DUAL {
+                            // Case 6.
+                            open.push(State::Live {
+                                node: n.play_move(first_move),
+                                merit: (h, pv),
+                                depth: d - 1,
+                                line,
+                                iteration: i,
+                            });
+                            for mv in legal_moves {
+                                line[MAX_DEPTH - d] = Some(mv);
+                                open.push(State::Live {
+                                    node: n.play_move(mv),
+                                    merit: (h, pv),
+                                    depth: d - 1,
+                                    line,
+                                    iteration: i,
+                                });
+                            }
+                        } else {
+                            // Case 5.
+                            open.push(State::Live {
+                                node: n.play_move(first_move),
+                                merit: (h, pv),
+                                depth: d - 1,
+                                line,
+                                iteration: i,
+                            });
+                        }
+                    } else {
+                        // To account for the negamax construct in conjunction with SSS* node evaluation.
+                        self.increment_leaf_count();
+                        let eval = if (((MAX_DEPTH - d) & 1) == 0) != DUAL {
+                            handler.terminal_eval(n, d, MAX_DEPTH)
+                        } else {
+                            -handler.terminal_eval(n, d, MAX_DEPTH)
+                        };
+                        // Next legal move is `None` on first attempt: leaf node. Thus, Case 4.
+                        open.push(State::Solved {
+                            node: n,
+                            merit: if h < eval { (h, pv) } else { (eval, l) },
+                            depth: d,
+                            line: l,
+                            iteration: i,
+                        });
+                    }
+                }
+            }
+            i += 1;
+
+            if open.len() > capacity {
+                let mut states = std::mem::take(&mut open).into_vec();
+                let spill_count = states.len() - capacity;
+                states.select_nth_unstable(spill_count - 1);
+                let highest = states[spill_count - 1].merit().0;
+                spill_bound = Some(spill_bound.map_or(highest, |bound| highest.max(bound)));
+                open = BinaryHeap::from(states.split_off(spill_count));
+            }
+        }
+        (None, spill_bound)
+    }
+
+    // Algorithm F, evaluating the leaves below the highest-merit states in parallel.
+    // Whenever the state about to be popped is a `Live` leaf that has not been evaluated yet,
+    // or a `Live` parent of leaves, a batch of about `batch_size` leaves is evaluated in parallel.
+    // These are the leaves of the highest-merit `Live` states in `open` that are leaves themselves
+    // or parents of leaves, which are left in `open`, and the evaluations are cached by position.
+    // The states are then popped and processed one at a time exactly as in `sss`,
+    // so the order of expansion, and hence the evaluation and principal variation, are unchanged.
+    // In particular, states discarded by the purge in Case 1 are never expanded,
+    // and their speculative evaluations are simply never read.
+    // The leaf count includes these speculative evaluations, as they were performed.
+    pub fn sss_parallel<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        root: TPosition,
+        depth: usize,
+        batch_size: usize,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: GameHandler<TPosition> + Sync,
+        TPosition: GamePosition + Send + Sync,
+        <THandler as GameHandler<TPosition>>::Eval: Send,
+    {
+        let mut open: BinaryHeap<
+            State<
+                TPosition,
+                <THandler as GameHandler<TPosition>>::Eval,
+                <TPosition as GamePosition>::Move,
+                MAX_DEPTH,
+            >,
+        > = BinaryHeap::new();
+
+        open.push(State::Live {
+            node: root,
+            merit: (
+                <THandler as GameHandler<TPosition>>::EVAL_MAXIMUM,
+                [None; MAX_DEPTH],
+            ),
+            depth,
+            line: [None; MAX_DEPTH],
+            iteration: 0,
+        });
+
+        let mut i: usize = 1;
+        let mut leaf_evals = HashMap::new();
+
+        loop {
+            let needs_evaluation = match open.peek() {
+                Some(&State::Live {
+                    node, depth: 0, ..
+                }) => !leaf_evals.contains_key(&node),
+                Some(&State::Live { depth: 1, .. }) => true,
+                _ => false,
+            };
+            if needs_evaluation {
+                // The leaves that will be reached soonest are those of the highest-merit
+                // `Live` states which are either leaves themselves or parents of leaves.
+                let mut candidates = open
+                    .iter()
+                    .filter(|state| matches!(state, State::Live { depth: 0 | 1, .. }))
+                    .collect::<Vec<_>>();
+                candidates.sort_unstable_by(|a, b| b.cmp(a));
+                let mut nodes = Vec::new();
+                for state in candidates {
+                    if nodes.len() >= batch_size.max(1) {
+                        break;
+                    }
+                    if let State::Live {
+                        node, depth: d, ..
+                    } = *state
+                    {
+                        let leaves = if d == 0 {
+                            vec![node]
+                        } else {
+                            handler
+                                .get_legal_moves(node)
+                                .map(|mv| node.play_move(mv))
+                                .collect()
+                        };
+                        for leaf in leaves {
+                            if !leaf_evals.contains_key(&leaf) && !nodes.contains(&leaf) {
+                                nodes.push(leaf);
+                            }
+                        }
+                    }
+                }
+                let evals = nodes
+                    .par_iter()
+                    .map(|&n| {
+                        // To account for the negamax construct in conjunction with SSS* node evaluation.
+                        if (MAX_DEPTH & 1) == 0 {
+                            (n, handler.evaluate(n, 0, MAX_DEPTH))
+                        } else {
+                            (n, -handler.evaluate(n, 0, MAX_DEPTH))
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                self.add_leaf_count(evals.len() as u128);
+                leaf_evals.extend(evals);
+            }
+
+            let Some(state) = open.pop() else {
+                break;
+            };
+            match state {
+                State::Solved {
+                    node: n,
+                    merit: (h, pv),
+                    depth: d,
+                    line: mut l,
+                    iteration: _,
+                } => {
+                    if d == MAX_DEPTH {
+                        return (h, pv);
+                    }
+                    let mut parent = root;
+                    let path_length = MAX_DEPTH - d - 1;
+                    for mv in l.iter().take(path_length) {
+                        parent = parent.play_move(mv.unwrap());
+                    }
+                    if state.is_max_player(MAX_DEPTH) {
+                        if let Some(next_move) = handler
+                            .get_legal_moves(parent)
+                            .skip_while(|&mv| parent.play_move(mv) != n)
+                            .nth(1)
+                        {
+                            l[path_length] = Some(next_move);
+                            for mv in l.iter_mut().skip(path_length + 1) {
+                                *mv = None;
+                            }
+                            // Case 2.
+                            open.push(State::Live {
+                                node: parent.play_move(next_move),
+                                merit: (h, pv),
+                                depth: d,
+                                line: l,
+                                iteration: i,
+                            });
+                        } else {
+                            // Case 3.
+                            open.push(State::Solved {
+                                node: parent,
+                                merit: (h, pv),
+                                depth: d + 1,
+                                line: l,
+                                iteration: i,
+                            });
+                        }
+                    } else {
+                        // Case 1.
+                        open.retain(|&state| {
+                            state
+                                .line()
+                                .iter()
+                                .zip(l.iter())
+                                .take(path_length)
+                                .any(|(&best, &discard)| best != discard)
+                        });
+                        open.push(State::Solved {
+                            node: parent,
+                            merit: (h, pv),
+                            depth: d + 1,
+                            line: l,
+                            iteration: i,
+                        });
+                    }
+                }
+                State::Live {
+                    node: n,
+                    merit: (h, pv),
+                    depth: d,
+                    line: l,
+                    iteration: _,
+                } => {
+                    let mut legal_moves = handler.get_legal_moves(n);
+                    if d == 0 {
+                        // Evaluated in parallel when this state was among the highest-merit leaves.
+                        let eval = leaf_evals[&n];
+                        // Extension of Case 4. `MAX_DEPTH` plies from root is considered leaf.
+                        open.push(State::Solved {
+                            node: n,
+                            merit: if h < eval { (h, pv) } else { (eval, l) },
+                            depth: d,
+                            line: l,
+                            iteration: i,
+                        });
+                    } else if let Some(first_move) = legal_moves.next() {
+                        let mut line = l;
+                        line[MAX_DEPTH - d] = Some(first_move);
+                        if state.is_max_player(MAX_DEPTH) {
+                            // Case 6.
+                            open.push(State::Live {
+                                node: n.play_move(first_move),
+                                merit: (h, pv),
+                                depth: d - 1,
+                                line,
+                                iteration: i,
+                            });
+                            for mv in legal_moves {
+                                line[MAX_DEPTH - d] = Some(mv);
+                                open.push(State::Live {
+                                    node: n.play_move(mv),
+                                    merit: (h, pv),
+                                    depth: d - 1,
+                                    line,
+                                    iteration: i,
+                                });
+                            }
+                        } else {
+                            // Case 5.
+                            open.push(State::Live {
+                                node: n.play_move(first_move),
+                                merit: (h, pv),
+                                depth: d - 1,
+                                line,
+                                iteration: i,
+                            });
+                        }
+                    } else {
+                        // To account for the negamax construct in conjunction with SSS* node evaluation.
+                        self.increment_leaf_count();
+                        let eval = if ((MAX_DEPTH - d) & 1) == 0 {
+                            handler.terminal_eval(n, d, MAX_DEPTH)
+                        } else {
+                            -handler.terminal_eval(n, d, MAX_DEPTH)
+                        };
+                        // Next legal move is `None` on first attempt: leaf node. Thus, Case 4.
+                        open.push(State::Solved {
+                            node: n,
+                            merit: if h < eval { (h, pv) } else { (eval, l) },
+                            depth: d,
+                            line: l,
+                            iteration: i,
+                        });
+                    }
+                }
+            }
+            i += 1;
+        }
+        panic!("State space operator is faulty");
+    }
+
+    // B* (Berliner, 1979), a selective best-first search which keeps an optimistic
+    // and a pessimistic bound on the value of every node in an explicit game tree,
+    // and expands the tree until the pessimistic bound of one root move is at least
+    // the optimistic bounds of all other root moves, proving it to be the best move.
+    // Unexpanded nodes are bounded with `BoundedEvaluation::evaluate_optimistic`
+    // and `BoundedEvaluation::evaluate_pessimistic`,
+    // while nodes `MAX_DEPTH` plies from the root and game states with no legal moves
+    // have both bounds equal to their static evaluation.
+    // At the root, the search alternates between proving the best move (raising its pessimistic
+    // bound) and disproving the best alternative (lowering its optimistic bound),
+    // and below the root it always follows
+    // the child with the highest optimistic bound to a node to expand.
+    // The evaluation returned is the proven pessimistic bound of the chosen move,
+    // and the principal variation follows the children which established that bound.
+    // For games with `GameHandler::plies_to_leaves`, a `depth` that stops above the leaves
+    // is raised to reach them, up to `MAX_DEPTH`, since interior nodes cannot be evaluated.
+    pub fn b_star<THandler, TPosition, const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &THandler,
+        root: TPosition,
+        depth: usize,
+    ) -> EvalAndPV<THandler, TPosition, MAX_DEPTH>
+    where
+        THandler: BoundedEvaluation<TPosition>,
+        TPosition: GamePosition,
+    {
+        let depth = handler
+            .plies_to_leaves(root)
+            .map_or(depth, |plies| depth.max(plies.min(MAX_DEPTH)));
+        let mut tree = vec![BStarNode {
+            pos: root,
+            mv: None,
+            depth,
+            optimistic: THandler::EVAL_MAXIMUM,
+            pessimistic: THandler::EVAL_MINIMUM,
+            children: Vec::new(),
+        }];
+        self.b_star_expand(handler, &mut tree, 0, MAX_DEPTH);
+        if tree[0].children.is_empty() {
+            return (tree[0].pessimistic, [None; MAX_DEPTH]);
+        }
+
+        let mut prove_best = true;
+        loop {
+            // The two root moves with the highest optimistic bounds, from the root perspective.
+            let children = &tree[0].children;
+            let best = Self::b_star_best_child(&tree, children.iter().copied())
+                .expect("the root has children");
+            let alternative = Self::b_star_best_child(
+                &tree,
+                children.iter().copied().filter(|&child| child != best),
+            );
+            let Some(alternative) = alternative.filter(|&alternative| {
+                -tree[best].optimistic < -tree[alternative].pessimistic
+            }) else {
+                break;
+            };
+
+            // Alternate between the strategies, unless the move to search is already resolved.
+            let resolved = |node: usize| tree[node].optimistic <= tree[node].pessimistic;
+            let target = if (prove_best && !resolved(best)) || resolved(alternative) {
+                best
+            } else {
+                alternative
+            };
+            prove_best = !prove_best;
+
+            let mut path = vec![0, target];
+            let mut node = target;
+            while !tree[node].children.is_empty() {
+                node = Self::b_star_best_child(&tree, tree[node].children.iter().copied())
+                    .expect("expanded nodes have children");
+                path.push(node);
+            }
+            self.b_star_expand(handler, &mut tree, node, MAX_DEPTH);
+            for &node in path.iter().rev().skip(1) {
+                Self::b_star_back_up(&mut tree, node);
+            }
+        }
+
+        let best = Self::b_star_best_child(&tree, tree[0].children.iter().copied())
+            .expect("the root has children");
+        let eval = -tree[best].optimistic;
+        let mut pv = [None; MAX_DEPTH];
+        let mut node = best;
+        pv[MAX_DEPTH - depth] = tree[node].mv;
+        while let Some(child) = tree[node]
+            .children
+            .iter()
+            .copied()
+            .reduce(|a, b| if tree[b].optimistic < tree[a].optimistic { b } else { a })
+        {
+            node = child;
+            pv[MAX_DEPTH - tree[node].depth - 1] = tree[node].mv;
+        }
+        (eval, pv)
+    }
+
+    // Adds the children of a leaf of the B* tree, with their initial bounds,
+    // and sets the bounds of the leaf from them.
+    // A leaf with no remaining depth or no legal moves instead has both bounds set exactly.
+    fn b_star_expand<THandler, TPosition>(
+        &mut self,
+        handler: &THandler,
+        tree: &mut Vec<BStarNode<TPosition, THandler::Eval, TPosition::Move>>,
+        index: usize,
+        max_depth: usize,
+    ) where
+        THandler: BoundedEvaluation<TPosition>,
+        TPosition: GamePosition,
+    {
+        let BStarNode { pos, depth, .. } = tree[index];
+        let mut children = Vec::new();
+        if depth > 0 {
+            for mv in handler.get_legal_moves(pos) {
+                let child = pos.play_move(mv);
+                self.increment_leaf_count();
+                let (optimistic, pessimistic) = if depth == 1 {
+                    let eval = handler.evaluate(child, depth - 1, max_depth);
+                    (eval, eval)
+                } else {
+                    (
+                        handler.evaluate_optimistic(child, depth - 1, max_depth),
+                        handler.evaluate_pessimistic(child, depth - 1, max_depth),
+                    )
+                };
+                children.push(tree.len());
+                tree.push(BStarNode {
+                    pos: child,
+                    mv: Some(mv),
+                    depth: depth - 1,
+                    optimistic,
+                    pessimistic,
+                    children: Vec::new(),
+                });
+            }
+        }
+        if children.is_empty() {
+            self.increment_leaf_count();
+            let eval = if depth == 0 {
+                handler.evaluate(pos, depth, max_depth)
+            } else {
+                handler.terminal_eval(pos, depth, max_depth)
+            };
+            tree[index].optimistic = eval;
+            tree[index].pessimistic = eval;
+        } else {
+            tree[index].children = children;
+            Self::b_star_back_up(tree, index);
+        }
+    }
+
+    // Recomputes the bounds of an expanded node of the B* tree from those of its children.
+    fn b_star_back_up<TPos, TEval, TMove>(tree: &mut [BStarNode<TPos, TEval, TMove>], index: usize)
+    where
+        TEval: Copy + Ord + std::ops::Neg<Output = TEval>,
+    {
+        let node = &tree[index];
+        let optimistic = node.children.iter().map(|&child| -tree[child].pessimistic).max();
+        let pessimistic = node.children.iter().map(|&child| -tree[child].optimistic).max();
+        if let (Some(optimistic), Some(pessimistic)) = (optimistic, pessimistic) {
+            tree[index].optimistic = optimistic;
+            tree[index].pessimistic = pessimistic;
+        }
+    }
+
+    // The first of the given children with the highest optimistic bound from the parent's
+    // perspective, which is the negation of the child's own pessimistic bound.
+    fn b_star_best_child<TPos, TEval, TMove>(
+        tree: &[BStarNode<TPos, TEval, TMove>],
+        children: impl Iterator<Item = usize>,
+    ) -> Option<usize>
+    where
+        TEval: Copy + Ord,
+    {
+        children.reduce(|a, b| {
+            if tree[b].pessimistic < tree[a].pessimistic {
+                b
+            } else {
+                a
+            }
+        })
+    }
+}
+
+// The `State` data structure for use in the SSS* algorithm.
+// It is only used by `sss` and `sss_parallel` and is never returned,
+// so it is kept private to this module.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum State<TPos, TEval, TMove, const SIZE: usize>
+where
+    TPos: Clone + Copy + std::fmt::Debug + PartialEq + Eq,
+    TEval: Clone + Copy + std::fmt::Debug + PartialEq + Eq + PartialOrd + Ord,
+    TMove: Clone + Copy + std::fmt::Debug + PartialEq + Eq,
+{
+    Live {
+        node: TPos,
+        merit: (TEval, [Option<TMove>; SIZE]),
+        depth: usize,
+        line: [Option<TMove>; SIZE],
+        iteration: usize,
+    },
+    Solved {
+        node: TPos,
+        merit: (TEval, [Option<TMove>; SIZE]),
+        depth: usize,
+        line: [Option<TMove>; SIZE],
+        iteration: usize,
+    },
+}
+
+impl<TPos, TEval, TMove, const SIZE: usize> State<TPos, TEval, TMove, SIZE>
+where
+    TPos: Clone + Copy + std::fmt::Debug + PartialEq + Eq,
+    TEval: Clone + Copy + std::fmt::Debug + PartialEq + Eq + PartialOrd + Ord,
+    TMove: Clone + Copy + std::fmt::Debug + PartialEq + Eq,
+{
+    fn merit(&self) -> (TEval, [Option<TMove>; SIZE]) {
+        match *self {
+            Self::Solved {
+                node: _,
+                merit,
+                depth: _,
+                line: _,
+                iteration: _,
+            } => merit,
+            Self::Live {
+                node: _,
+                merit,
+                depth: _,
+                line: _,
+                iteration: _,
+            } => merit,
+        }
+    }
+
+    fn depth(&self) -> usize {
+        match *self {
+            Self::Solved {
+                node: _,
+                merit: _,
+                depth,
+                line: _,
+                iteration: _,
+            } => depth,
+            Self::Live {
+                node: _,
+                merit: _,
+                depth,
+                line: _,
+                iteration: _,
+            } => depth,
+        }
+    }
+
+    fn line(&self) -> [Option<TMove>; SIZE] {
+        match *self {
+            Self::Solved {
+                node: _,
+                merit: _,
+                depth: _,
+                line,
+                iteration: _,
+            } => line,
+            Self::Live {
+                node: _,
+                merit: _,
+                depth: _,
+                line,
+                iteration: _,
+            } => line,
+        }
+    }
+
+    fn iteration(&self) -> usize {
+        match *self {
+            Self::Solved {
+                node: _,
+                merit: _,
+                depth: _,
+                line: _,
+                iteration,
+            } => iteration,
+            Self::Live {
+                node: _,
+                merit: _,
+                depth: _,
+                line: _,
+                iteration,
+            } => iteration,
+        }
+    }
+
+    fn is_max_player(&self, max_depth: usize) -> bool {
+        ((max_depth - self.depth()) & 1) == 0
+    }
+}
+
+impl<TPos, TEval, TMove, const SIZE: usize> PartialOrd for State<TPos, TEval, TMove, SIZE>
+where
+    TPos: Clone + Copy + std::fmt::Debug + PartialEq + Eq,
+    TEval: Clone + Copy + std::fmt::Debug + PartialEq + Eq + PartialOrd + Ord,
+    TMove: Clone + Copy + std::fmt::Debug + PartialEq + Eq,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<TPos, TEval, TMove, const SIZE: usize> Ord for State<TPos, TEval, TMove, SIZE>
+where
+    TPos: Clone + Copy + std::fmt::Debug + PartialEq + Eq,
+    TEval: Clone + Copy + std::fmt::Debug + PartialEq + Eq + PartialOrd + Ord,
+    TMove: Clone + Copy + std::fmt::Debug + PartialEq + Eq,
+{
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.merit()
+            .0
+            .cmp(&other.merit().0)
+            .then_with(|| self.iteration().cmp(&other.iteration()))
+    }
+}
+
+// A node of the explicit game tree built by `b_star`, with bounds on its value
+// from the perspective of the player to move, and the indices of its children in the tree.
+struct BStarNode<TPos, TEval, TMove> {
+    pos: TPos,
+    mv: Option<TMove>,
+    depth: usize,
+    optimistic: TEval,
+    pessimistic: TEval,
+    children: Vec<usize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::chess::*;
+    use crate::games::hypothetical_tree::*;
+    use crate::games::stockman::*;
+    use crate::games::transposition_tree::*;
+    use crate::games::uniform_2b_wide::*;
+    use crate::games::ut3::*;
+
+    fn hyp_tree(depth: usize, width: usize, seed: u64) -> UnordIndHypTreeHandler {
+        UnordIndHypTreeHandler::new(HypTreeParams {
+            depth,
+            width,
+            seed,
+            early_terminal: 0,
+        })
+    }
+
+    // Runs a check, written as a closure of the handler and root, on each game that searches
+    // are compared on: hypothetical trees with and without early terminal nodes, uniform binary
+    // trees and transposition trees of several seeds, the Stockman tree and Ultimate Tic-Tac-Toe.
+    // A closure cannot be generic over the game, so the check becomes the body of a function
+    // generic over `THandler`, `TPosition` and the search depth `DEPTH`, which it may name.
+    macro_rules! for_each_game {
+        (|$handler:ident, $root:ident| $check:block) => {{
+            fn check<THandler, TPosition, const DEPTH: usize>($handler: &THandler, $root: TPosition)
+            where
+                THandler: GameHandler<TPosition> + 'static,
+                TPosition: GamePosition + ZobristKey + 'static,
+            $check
+
+            for seed in 0..4 {
+                check::<_, _, 5>(&hyp_tree(5, 3, seed), HypTreePos::startpos(3));
+                let early_terminal = UnordIndHypTreeHandler::new(HypTreeParams {
+                    depth: 5,
+                    width: 3,
+                    seed,
+                    early_terminal: 30,
+                });
+                check::<_, _, 5>(&early_terminal, HypTreePos::startpos(3));
+                let uniform = Uniform2bWideHandler::new(Uniform2bWideParams {
+                    depth: 6,
+                    seed,
+                    distribution: LeafDistribution::Uniform,
+                });
+                check::<_, _, 6>(&uniform, Uniform2bWidePos::startpos(()));
+                let transp_tree = TranspTreeHandler::new(TranspTreeParams {
+                    depth: 5,
+                    width: 3,
+                    density: 50,
+                    seed,
+                });
+                check::<_, _, 5>(&transp_tree, TranspTreePos::startpos((3, 50)));
+            }
+            check::<_, _, 4>(&StockmanHandler::new(()), StockmanPos::startpos(()));
+            check::<_, _, 3>(&Ut3Handler::new(Ut3Params::default()), Ut3Board::startpos(()));
+        }};
+    }
+
+    // Ultimate Tic-Tac-Toe evaluates from the perspective of the player to move,
+    // so searching it to an odd depth catches leaves evaluated for the wrong player.
+    #[test]
+    fn sss_matches_alpha_beta_at_odd_depth() {
+        let handler = Ut3Handler::new(Ut3Params::default());
+        let root = Ut3Board::startpos(());
+        let mut searcher = Searcher::new();
+        let (expected, _) = searcher.alpha_beta::<Ut3Handler, Ut3Board, 3>(
+            &handler,
+            root,
+            3,
+            Ut3Handler::EVAL_MINIMUM,
+            Ut3Handler::EVAL_MAXIMUM,
+        );
+        let (eval, _) = searcher.sss::<Ut3Handler, Ut3Board, 3>(&handler, root, 3);
+        assert_eq!(eval, expected);
+        let (eval, _) = searcher.sss_parallel::<Ut3Handler, Ut3Board, 3>(&handler, root, 3, 16);
+        assert_eq!(eval, expected);
+    }
+
+    #[test]
+    fn verify_line_detects_corrupted_line() {
+        let handler = hyp_tree(4, 3, 7);
+        let root = HypTreePos::startpos(3);
+        let result = Searcher::new().alpha_beta::<UnordIndHypTreeHandler, HypTreePos, 4>(
+            &handler,
+            root,
+            4,
+            UnordIndHypTreeHandler::EVAL_MINIMUM,
+            UnordIndHypTreeHandler::EVAL_MAXIMUM,
+        );
+        assert_eq!(verify_line(&handler, root, result), Ok(()));
+        // Leaf values are distinct, so any other final move changes the evaluation.
+        let (eval, mut line) = result;
+        let (fanout, shift) = line[3].unwrap();
+        line[3] = Some((fanout, shift % 3 + 1));
+        let mismatch = verify_line(&handler, root, (eval, line)).unwrap_err();
+        assert_eq!(mismatch.returned_eval, eval);
+        assert_ne!(mismatch.recalculated_eval, eval);
+        assert_eq!(mismatch.line_length, 4);
+    }
+
+    // Negamax writing each move at index `depth - 1` instead of `MAX_DEPTH - depth`,
+    // so that its principal variation comes out reversed, checked at the root as the searches are.
+    #[cfg(debug_assertions)]
+    fn negamax_with_reversed_line<const MAX_DEPTH: usize>(
+        handler: &UnordIndHypTreeHandler,
+        pos: HypTreePos,
+        depth: usize,
+    ) -> EvalAndPV<UnordIndHypTreeHandler, HypTreePos, MAX_DEPTH> {
+        let mut best = (UnordIndHypTreeHandler::EVAL_MINIMUM, [None; MAX_DEPTH]);
+        if depth == 0 {
+            return (handler.evaluate(pos, depth, MAX_DEPTH), best.1);
+        }
+        for mv in handler.get_legal_moves(pos) {
+            let (t, mut line) =
+                negamax_with_reversed_line::<MAX_DEPTH>(handler, pos.play_move(mv), depth - 1);
+            line[depth - 1] = Some(mv);
+            if -t > best.0 {
+                best = (-t, line);
+            }
+        }
+        if depth == MAX_DEPTH {
+            let (min, max) =
+                (UnordIndHypTreeHandler::EVAL_MINIMUM, UnordIndHypTreeHandler::EVAL_MAXIMUM);
+            debug_assert_line(handler, pos, min, max, best);
+        }
+        best
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Principal variation does not reproduce its evaluation")]
+    fn pv_index_bug_trips_debug_check() {
+        // The principal variation of this tree is not a palindrome, so reversing it is caught.
+        let handler = hyp_tree(4, 3, 2);
+        negamax_with_reversed_line::<4>(&handler, HypTreePos::startpos(3), 4);
+    }
+
+    #[test]
+    fn alpha_beta_iter_matches_recursive() {
+        for_each_game!(|handler, root| {
+            let mut searcher = Searcher::new();
+            let (alpha, beta) = (THandler::EVAL_MINIMUM, THandler::EVAL_MAXIMUM);
+            let expected = searcher
+                .alpha_beta::<THandler, TPosition, DEPTH>(handler, root, DEPTH, alpha, beta);
+            let expected_leaves = searcher.get_leaf_count();
+            searcher.reset_leaf_count();
+            let result = searcher
+                .alpha_beta_iter::<THandler, TPosition, DEPTH>(handler, root, DEPTH, alpha, beta);
+            assert!(result == expected);
+            assert_eq!(searcher.get_leaf_count(), expected_leaves);
+        });
+    }
+
+    #[test]
+    fn triangular_pv_matches_array_pv() {
+        for_each_game!(|handler, root| {
+            let mut searcher = Searcher::new();
+            let mut pv = TriangularPV::new();
+            let (alpha, beta) = (THandler::EVAL_MINIMUM, THandler::EVAL_MAXIMUM);
+            let (expected_eval, expected_line) = searcher
+                .alpha_beta::<THandler, TPosition, DEPTH>(handler, root, DEPTH, alpha, beta);
+            let eval = searcher.alpha_beta_triangular::<THandler, TPosition, DEPTH>(
+                handler, root, DEPTH, alpha, beta, &mut pv,
+            );
+            assert!(eval == expected_eval);
+            assert!(pv.to_array() == expected_line);
+            assert_eq!(pv.len(), expected_line.iter().flatten().count());
+        });
+    }
+
+    #[test]
+    fn b_star_proves_stockman_best_move() {
+        let handler = StockmanHandler::new(());
+        let root = StockmanPos::startpos(());
+        let mut searcher = Searcher::new();
+        let (expected_eval, expected_line) = searcher.alpha_beta::<StockmanHandler, StockmanPos, 4>(
+            &handler,
+            root,
+            4,
+            StockmanHandler::EVAL_MINIMUM,
+            StockmanHandler::EVAL_MAXIMUM,
+        );
+        searcher.reset_leaf_count();
+        let result = searcher.b_star::<StockmanHandler, StockmanPos, 4>(&handler, root, 4);
+        assert_eq!(result.0, expected_eval);
+        assert_eq!(result.1[0], expected_line[0]);
+        assert_eq!(verify_line(&handler, root, result), Ok(()));
+    }
+
+    #[test]
+    fn sss_capped_matches_sss() {
+        let mut searcher = Searcher::new();
+        for seed in 0..6 {
+            let handler = hyp_tree(4, 4, seed);
+            let root = HypTreePos::startpos(4);
+            let (expected, _) =
+                searcher.sss::<UnordIndHypTreeHandler, HypTreePos, 4>(&handler, root, 4);
+            for capacity in [1, 2, 5, 1000] {
+                let result = searcher.sss_capped::<UnordIndHypTreeHandler, HypTreePos, 4>(
+                    &handler, root, 4, capacity,
+                );
+                assert_eq!(result.0, expected);
+                assert_eq!(verify_line(&handler, root, result), Ok(()));
+            }
+        }
+    }
+
+    // Each side can capture on e4 past the horizon of a one-ply search.
+    #[test]
+    fn quiescence_raises_seldepth_past_nominal_depth() {
+        let handler = ChessHandler::new(ChessParams::default());
+        let pos = ChessPos::from_fen("4k3/8/8/3p4/4p3/5P2/8/4K3 w - - 0 1").unwrap();
+        let mut searcher = Searcher::new();
+        searcher.alpha_beta_quiescent::<ChessHandler, ChessPos, 1>(
+            &handler,
+            pos,
+            1,
+            ChessHandler::EVAL_MINIMUM,
+            ChessHandler::EVAL_MAXIMUM,
+        );
+        assert!(searcher.get_seldepth() >= 2);
+        assert_eq!(searcher.stats().seldepth, searcher.get_seldepth());
+        searcher.reset_seldepth();
+        assert_eq!(searcher.get_seldepth(), 0);
+        searcher.alpha_beta_pruned::<1>(
+            &handler,
+            pos,
+            1,
+            ChessHandler::EVAL_MINIMUM,
+            ChessHandler::EVAL_MAXIMUM,
+            &crate::pruning::PruningParams::default(),
+        );
+        assert!(searcher.get_seldepth() >= 2);
+    }
+
+    #[test]
+    fn runtime_depth_matches_const_depth() {
+        for_each_game!(|handler, root| {
+            let mut searcher = Searcher::new();
+            let (alpha, beta) = (THandler::EVAL_MINIMUM, THandler::EVAL_MAXIMUM);
+            let as_vec = |(eval, line): EvalAndPV<THandler, TPosition, DEPTH>| {
+                (eval, line.iter().flatten().copied().collect::<Vec<_>>())
+            };
+            let expected = as_vec(searcher.alpha_beta::<THandler, TPosition, DEPTH>(
+                handler, root, DEPTH, alpha, beta,
+            ));
+            assert!(searcher.alpha_beta_dyn(handler, root, DEPTH, alpha, beta) == expected);
+            let expected = as_vec(searcher.pvs::<THandler, TPosition, DEPTH>(
+                handler, root, DEPTH, alpha, beta,
+            ));
+            assert!(searcher.pvs_dyn(handler, root, DEPTH, alpha, beta) == expected);
+        });
+    }
+
+    #[test]
+    fn info_callback_reports_every_completed_depth() {
+        use std::sync::Arc;
+
+        let handler = Ut3Handler::new(Ut3Params::default());
+        let root = Ut3Board::startpos(());
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let mut searcher = Searcher::new();
+        let sink = Arc::clone(&reports);
+        searcher.set_info_callback::<i32, u64>(Box::new(move |info| {
+            sink.lock().unwrap().push(info);
+        }));
+        let sink = Arc::clone(&progress);
+        searcher.set_progress_callback(
+            10,
+            Box::new(move |info| sink.lock().unwrap().push(info.nodes)),
+        );
+        let (eval, line) =
+            searcher.iterative_deepening::<Ut3Handler, Ut3Board, 4>(&handler, root, || false);
+        let leaves = searcher.get_leaf_count();
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(
+            reports.iter().map(|info| info.depth).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4]
+        );
+        let last = reports.last().unwrap();
+        assert_eq!((last.score, last.best_move), (eval, line[0]));
+        assert_eq!(last.nodes, leaves);
+        let progress = progress.lock().unwrap();
+        assert_eq!(progress.len() as u128, leaves / 10);
+        assert!(progress.iter().zip(1..).all(|(&nodes, i)| nodes == 10 * i));
+    }
+
+    #[test]
+    fn root_calls_record_search_time() {
+        let handler = Ut3Handler::new(Ut3Params::default());
+        let root = Ut3Board::startpos(());
+        let mut searcher = Searcher::new();
+        assert_eq!((searcher.stats().elapsed, searcher.stats().nps), (Duration::ZERO, 0.0));
+
+        searcher.iterative_deepening::<Ut3Handler, Ut3Board, 4>(&handler, root, || false);
+        let stats = searcher.stats();
+        assert!(stats.elapsed > Duration::ZERO);
+        assert!(stats.nps > 0.0 && stats.nps.is_finite());
+        let expected = stats.leaf_count as f64 / stats.elapsed.as_secs_f64();
+        assert!((stats.nps - expected).abs() <= 1e-9 * expected);
+
+        // Only the leaves of the last timed search count towards its rate.
+        let (min, max) = (Ut3Handler::EVAL_MINIMUM, Ut3Handler::EVAL_MAXIMUM);
+        let before = searcher.get_leaf_count();
+        searcher.alpha_beta_dyn(&handler, root, 3, min, max);
+        let stats = searcher.stats();
+        let expected = (stats.leaf_count - before) as f64 / stats.elapsed.as_secs_f64();
+        assert!(stats.nps > 0.0 && (stats.nps - expected).abs() <= 1e-9 * expected);
+    }
+
+    #[test]
+    fn root_scores_are_exact_and_sorted() {
+        type H = UnordIndHypTreeHandler;
+        let (min, max) = (H::EVAL_MINIMUM, H::EVAL_MAXIMUM);
+        for seed in 0..6 {
+            let handler = hyp_tree(4, 3, seed);
+            let root = HypTreePos::startpos(3);
+            let mut searcher = Searcher::new();
+            let scores = searcher.root_scores::<H, HypTreePos, 4>(&handler, root, 4);
+            let moves = handler.get_legal_moves(root).collect::<Vec<_>>();
+            assert_eq!(scores.len(), moves.len());
+            assert!(scores.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+            for mv in moves {
+                let child = root.play_move(mv);
+                let (t, _) = searcher.alpha_beta::<H, HypTreePos, 4>(&handler, child, 3, min, max);
+                assert!(scores.contains(&(mv, -t)));
+            }
+            let (eval, _) = searcher.alpha_beta::<H, HypTreePos, 4>(&handler, root, 4, min, max);
+            assert_eq!(scores[0].1, eval);
+            assert!(searcher.root_scores::<H, HypTreePos, 4>(&handler, root, 0).is_empty());
+        }
+    }
+
+    #[test]
+    fn eval_override_with_handler_evaluate_matches_alpha_beta() {
+        let handler = Ut3Handler::new(Ut3Params::default());
+        let (min, max) = (Ut3Handler::EVAL_MINIMUM, Ut3Handler::EVAL_MAXIMUM);
+        let evaluate = |pos: &Ut3Board, depth, max_depth| handler.evaluate(*pos, depth, max_depth);
+        let negated = |pos: &Ut3Board, depth, max_depth| -handler.evaluate(*pos, depth, max_depth);
+        let mut root = Ut3Board::startpos(());
+        for mv in [40, 36, 4] {
+            root = root.play_move(mv);
+            let mut searcher = Searcher::new();
+            let expected =
+                searcher.alpha_beta::<Ut3Handler, Ut3Board, 3>(&handler, root, 3, min, max);
+            let leaves = searcher.get_leaf_count();
+            searcher.reset_leaf_count();
+            let result = searcher.alpha_beta_with_eval::<Ut3Handler, Ut3Board, _, 3>(
+                &handler, root, 3, min, max, &evaluate,
+            );
+            assert_eq!(result, expected);
+            assert_eq!(searcher.get_leaf_count(), leaves);
+            // Scoring every leaf for the wrong player finds a different line.
+            let (eval, line) = searcher.alpha_beta_with_eval::<Ut3Handler, Ut3Board, _, 3>(
+                &handler, root, 3, min, max, &negated,
+            );
+            assert!((eval, line) != expected);
+        }
+    }
+
+    // Iterative deepening and internal iterative deepening search the tree games only to
+    // their leaves, where the debug assertions of `evaluate` hold, and still agree with
+    // `alpha_beta`.
+    #[test]
+    fn tree_games_are_searched_to_their_leaves() {
+        for_each_game!(|handler, root| {
+            let (min, max) = (THandler::EVAL_MINIMUM, THandler::EVAL_MAXIMUM);
+            let mut searcher = Searcher::new();
+            let (expected, _) =
+                searcher.alpha_beta::<THandler, TPosition, DEPTH>(handler, root, DEPTH, min, max);
+            let (eval, _) =
+                searcher.iterative_deepening::<THandler, TPosition, DEPTH>(handler, root, || false);
+            assert_eq!(eval, expected);
+            let (eval, _) = searcher.iterative_deepening_aspiration::<THandler, TPosition, DEPTH>(
+                handler,
+                root,
+                THandler::EVAL_EPSILON,
+                || false,
+            );
+            assert_eq!(eval, expected);
+            let mut tt = TranspositionTable::new(1 << 10);
+            let (eval, _) = searcher.alpha_beta_tt::<THandler, TPosition, DEPTH, true>(
+                handler, root, DEPTH, min, max, &mut tt,
+            );
+            assert_eq!(eval, expected);
+        });
+        let stockman = StockmanHandler::new(());
+        let root = StockmanPos::startpos(());
+        // B* given a depth above the leaves searches to them instead.
+        let mut searcher = Searcher::new();
+        let expected = searcher.b_star::<StockmanHandler, StockmanPos, 4>(&stockman, root, 4);
+        let result = searcher.b_star::<StockmanHandler, StockmanPos, 4>(&stockman, root, 2);
+        assert_eq!(result, expected);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "search is 2 plies deeper than the game tree")]
+    fn searching_stockman_deeper_than_the_tree_panics_in_debug() {
+        let handler = StockmanHandler::new(());
+        Searcher::new().alpha_beta::<StockmanHandler, StockmanPos, 6>(
+            &handler,
+            StockmanPos::startpos(()),
+            6,
+            StockmanHandler::EVAL_MINIMUM,
+            StockmanHandler::EVAL_MAXIMUM,
+        );
+    }
+
+    #[test]
+    fn best_move_of_shallower_search() {
+        let handler = Ut3Handler::new(Ut3Params::default());
+        let (min, max) = (Ut3Handler::EVAL_MINIMUM, Ut3Handler::EVAL_MAXIMUM);
+        let root = Ut3Board::startpos(()).play_move(40);
+        let mut searcher = Searcher::new();
+        let search = |searcher: &mut Searcher, handler: &Ut3Handler, pos, depth| {
+            searcher.alpha_beta::<Ut3Handler, Ut3Board, 4>(handler, pos, depth, min, max)
+        };
+        for depth in 1..=4 {
+            let (_, line) = searcher.alpha_beta_dyn(&handler, root, depth, min, max);
+            let expected = line.first().copied();
+            assert!(expected.is_some());
+            assert_eq!(searcher.best_move(search, &handler, root, depth), expected);
+        }
+        assert_eq!(searcher.best_move(search, &handler, root, 0), None);
+    }
+
+    #[test]
+    fn minimax_explicit_matches_negamax() {
+        type H = StockmanHandler;
+        let stockman = StockmanHandler::new(());
+        let root = StockmanPos::startpos(());
+        let (min, max) = (H::EVAL_MINIMUM, H::EVAL_MAXIMUM);
+        let mut searcher = Searcher::new();
+        let expected = searcher.alpha_beta::<H, StockmanPos, 4>(&stockman, root, 4, min, max);
+        let result = searcher.minimax_explicit::<H, StockmanPos, 4>(&stockman, root, 4);
+        assert_eq!(result, expected);
+        // Below the root, the absolute value is negated wherever the root player is not to move.
+        for mv in stockman.get_legal_moves(root) {
+            let child = root.play_move(mv);
+            let (negamax, _) =
+                searcher.alpha_beta::<H, StockmanPos, 4>(&stockman, child, 3, min, max);
+            let (absolute, _) = searcher.minimax_explicit::<H, StockmanPos, 4>(&stockman, child, 3);
+            assert_eq!(absolute, -negamax);
+        }
+
+        let ut3 = Ut3Handler::new(Ut3Params::default());
+        let (min, max) = (Ut3Handler::EVAL_MINIMUM, Ut3Handler::EVAL_MAXIMUM);
+        let board = Ut3Board::startpos(()).play_move(40);
+        let (expected, _) =
+            searcher.alpha_beta::<Ut3Handler, Ut3Board, 3>(&ut3, board, 3, min, max);
+        let (eval, _) = searcher.minimax_explicit::<Ut3Handler, Ut3Board, 3>(&ut3, board, 3);
+        assert_eq!(eval, expected);
+    }
+
+    #[test]
+    fn tree_totals_match_perft() {
+        // The leaves of a full tree are the nodes at its depth, and all of its nodes
+        // are those at every depth up to it.
+        fn totals<THandler, TPosition>(
+            handler: &THandler,
+            root: TPosition,
+            depth: usize,
+        ) -> (u128, u128)
+        where
+            THandler: GameHandler<TPosition>,
+            TPosition: GamePosition,
+        {
+            let nodes = (0..=depth).map(|d| perft(d, root, handler)).sum();
+            (perft(depth, root, handler), nodes)
+        }
 
-        while let Some(state) = open.pop() {
-            match state {
-                State::Solved {
-                    node: n,
-                    merit: (h, pv),
-                    depth: d,
-                    line: mut l,
-                    iteration: _,
-                } => {
-                    if d == MAX_DEPTH {
-                        return (h, pv);
-                    }
-                    let mut parent = root;
-                    let path_length = MAX_DEPTH - d - 1;
-                    for mv in l.iter().take(path_length) {
-                        parent = parent.play_move(mv.unwrap());
+        let stockman = StockmanHandler::new(());
+        assert_eq!(
+            totals(&stockman, StockmanPos::startpos(()), 4),
+            (stockman.leaf_total(), stockman.node_total())
+        );
+        for (depth, width) in [(1, 5), (3, 3), (4, 2)] {
+            let handler = hyp_tree(depth, width, 1);
+            assert_eq!(
+                totals(&handler, HypTreePos::startpos(width), depth),
+                (handler.leaf_total(), handler.node_total())
+            );
+        }
+        for depth in [1, 4, 7] {
+            let handler = Uniform2bWideHandler::new(Uniform2bWideParams {
+                depth,
+                seed: 1,
+                distribution: LeafDistribution::Uniform,
+            });
+            assert_eq!(
+                totals(&handler, Uniform2bWidePos::startpos(()), depth as usize),
+                (handler.leaf_total(), handler.node_total())
+            );
+        }
+    }
+
+    #[test]
+    fn perft_counts_known_trees() {
+        let stockman = StockmanHandler::new(());
+        let root = StockmanPos::startpos(());
+        for depth in 0..=4 {
+            assert_eq!(perft(depth, root, &stockman), 1 << depth);
+        }
+
+        let chess = ChessHandler::new(ChessParams::default());
+        let start = ChessPos::startpos(());
+        let counts = (1..=3).map(|depth| perft(depth, start, &chess)).collect::<Vec<_>>();
+        assert_eq!(counts, [20, 400, 8902]);
+        let divide = perft_divide(3, start, &chess);
+        assert_eq!(divide.len(), 20);
+        assert_eq!(divide.iter().map(|&(_, count)| count).sum::<u128>(), 8902);
+        let e4 = chess.move_from_uci(start, "e2e4").unwrap();
+        assert_eq!(divide.iter().find(|&&(mv, _)| mv == e4).map(|&(_, count)| count), Some(600));
+    }
+
+    #[test]
+    fn negac_star_matches_alpha_beta() {
+        for_each_game!(|handler, root| {
+            let (min, max) = (THandler::EVAL_MINIMUM, THandler::EVAL_MAXIMUM);
+            let mut searcher = Searcher::new();
+            let (expected, _) =
+                searcher.alpha_beta::<THandler, TPosition, DEPTH>(handler, root, DEPTH, min, max);
+            let result = searcher.negac_star::<THandler, TPosition, DEPTH>(handler, root, DEPTH);
+            assert!(result.0 == expected);
+            assert_eq!(verify_line(handler, root, result), Ok(()));
+        });
+    }
+
+    // At depth 1, each probe at `g` reads leaves until one beats `g`,
+    // so there is one probe per new running maximum, then one that fails low,
+    // and a final probe around the value, both of which read every leaf.
+    #[test]
+    fn negac_star_probe_trace() {
+        for seed in 0..8 {
+            let handler = hyp_tree(1, 5, seed);
+            let root = HypTreePos::startpos(5);
+            let values: Vec<i64> = handler
+                .get_legal_moves(root)
+                .map(|mv| -handler.evaluate(root.play_move(mv), 0, 1))
+                .collect();
+            let mut expected_leaves = 0;
+            let mut g = UnordIndHypTreeHandler::EVAL_MINIMUM;
+            loop {
+                match values.iter().position(|&v| v > g) {
+                    Some(index) => {
+                        expected_leaves += index + 1;
+                        g = values[index];
                     }
-                    if state.is_max_player(MAX_DEPTH) {
-                        if let Some(next_move) = handler
-                            .get_legal_moves(parent)
-                            .skip_while(|&mv| parent.play_move(mv) != n)
-                            .nth(1)
-                        {
-                            l[path_length] = Some(next_move);
-                            for i in path_length + 1..MAX_DEPTH {
-                                l[i] = None;
-                            }
-                            // Case 2.
-                            open.push(State::Live {
-                                node: parent.play_move(next_move),
-                                merit: (h, pv),
-                                depth: d,
-                                line: l,
-                                iteration: i,
-                            });
-                        } else {
-                            // Case 3.
-                            open.push(State::Solved {
-                                node: parent,
-                                merit: (h, pv),
-                                depth: d + 1,
-                                line: l,
-                                iteration: i,
-                            });
-                        }
-                    } else {
-                        // Case 1.
-                        open.retain(|&state| {
-                            state
-                                .line()
-                                .iter()
-                                .zip(l.iter())
-                                .take(path_length)
-                                .any(|(&best, &discard)| best != discard)
-                        });
-                        open.push(State::Solved {
-                            node: parent,
-                            merit: (h, pv),
-                            depth: d + 1,
-                            line: l,
-                            iteration: i,
-                        });
+                    None => {
+                        expected_leaves += values.len();
+                        break;
                     }
                 }
-                State::Live {
-                    node: n,
-                    merit: (h, pv),
-                    depth: d,
-                    line: l,
-                    iteration: _,
-                } => {
-                    let mut legal_moves = handler.get_legal_moves(n);
-                    if d == 0 {
-                        // To account for the negamax construct in conjunction with SSS* node evaluation.
-                        self.increment_leaf_count();
-                        let eval = if ((MAX_DEPTH - depth) & 1) == 0 {
-                            handler.evaluate(n, depth, MAX_DEPTH)
-                        } else {
-                            -handler.evaluate(n, depth, MAX_DEPTH)
-                        };
-                        // Extension of Case 4. `MAX_DEPTH` plies from root is considered leaf.
-                        open.push(State::Solved {
-                            node: n,
-                            merit: if h < eval { (h, pv) } else { (eval, l) },
-                            depth: d,
-                            line: l,
-                            iteration: i,
-                        });
-                    } else if let Some(first_move) = legal_moves.next() {
-                        let mut line = l;
-                        line[MAX_DEPTH - d] = Some(first_move);
-                        if state.is_max_player(MAX_DEPTH) {
-                            // Case 6.
-                            open.push(State::Live {
-                                node: n.play_move(first_move),
-                                merit: (h, pv),
-                                depth: d - 1,
-                                line,
-                                iteration: i,
-                            });
-                            for mv in legal_moves {
-                                line[MAX_DEPTH - d] = Some(mv);
-                                open.push(State::Live {
-                                    node: n.play_move(mv),
-                                    merit: (h, pv),
-                                    depth: d - 1,
-                                    line,
-                                    iteration: i,
-                                });
-                            }
-                        } else {
-                            // Case 5.
-                            open.push(State::Live {
-                                node: n.play_move(first_move),
-                                merit: (h, pv),
-                                depth: d - 1,
-                                line,
-                                iteration: i,
-                            });
-                        }
-                    } else {
-                        // To account for the negamax construct in conjunction with SSS* node evaluation.
-                        self.increment_leaf_count();
-                        let eval = if ((MAX_DEPTH - depth) & 1) == 0 {
-                            handler.evaluate(n, depth, MAX_DEPTH)
-                        } else {
-                            -handler.evaluate(n, depth, MAX_DEPTH)
-                        };
-                        // Next legal move is `None` on first attempt: leaf node. Thus, Case 4.
-                        open.push(State::Solved {
-                            node: n,
-                            merit: if h < eval { (h, pv) } else { (eval, l) },
-                            depth: d,
-                            line: l,
-                            iteration: i,
-                        });
-                    }
+            }
+            expected_leaves += values.len();
+            let mut searcher = Searcher::new();
+            let (eval, _) =
+                searcher.negac_star::<UnordIndHypTreeHandler, HypTreePos, 1>(&handler, root, 1);
+            assert_eq!(eval, g);
+            assert_eq!(searcher.get_leaf_count(), expected_leaves as u128);
+        }
+    }
+
+    // In a tree of depth 2 and width 2, the second root move is searched again
+    // exactly when its worse leaf is better than the worse leaf of the first move.
+    #[test]
+    fn pvs_records_single_refutation() {
+        let mut research_seen = false;
+        for seed in 0..8 {
+            let handler = hyp_tree(2, 2, seed);
+            let root = HypTreePos::startpos(2);
+            let leaves = handler.leaf_values();
+            let researched = leaves[2].min(leaves[3]) > leaves[0].min(leaves[1]);
+            research_seen |= researched;
+            let mut searcher = Searcher::new();
+            let (alpha, beta) = (
+                UnordIndHypTreeHandler::EVAL_MINIMUM,
+                UnordIndHypTreeHandler::EVAL_MAXIMUM,
+            );
+            let expected = searcher
+                .pvs::<UnordIndHypTreeHandler, HypTreePos, 2>(&handler, root, 2, alpha, beta);
+            let (result, refutations) = searcher
+                .pvs_with_refutations::<UnordIndHypTreeHandler, HypTreePos, 2>(
+                    &handler, root, 2, alpha, beta,
+                );
+            assert_eq!(result, expected);
+            if researched {
+                assert_eq!(refutations.len(), 1);
+                let (mv, line) = refutations[0];
+                assert_eq!(mv, (2, 2));
+                assert_eq!(line[0], Some(mv));
+                assert!(line[1].is_some());
+            } else {
+                assert!(refutations.is_empty());
+            }
+        }
+        assert!(research_seen);
+    }
+
+    // White mates in two with a check, Black's only reply and another check,
+    // which extensions search in full from a nominal depth of two plies.
+    #[test]
+    fn extensions_find_mate_past_horizon() {
+        let handler = ChessHandler::new(ChessParams::default());
+        let pos = ChessPos::from_fen("6k1/5ppp/4r3/8/8/8/5PPP/1Q2R1K1 w - - 0 1").unwrap();
+        let (alpha, beta) = (ChessHandler::EVAL_MINIMUM, ChessHandler::EVAL_MAXIMUM);
+        let (eval, line) = Searcher::new()
+            .alpha_beta_ext::<ChessHandler, ChessPos, 6>(&handler, pos, 2, alpha, beta);
+        assert_eq!(eval.mate_in(), Some(3));
+        assert_eq!(line.iter().flatten().count(), 3);
+        let (eval, _) =
+            Searcher::new().alpha_beta::<ChessHandler, ChessPos, 2>(&handler, pos, 2, alpha, beta);
+        assert!(!eval.is_mate());
+    }
+
+    #[test]
+    fn iid_changes_only_leaf_count() {
+        let handler = Ut3Handler::new(Ut3Params::default());
+        let root = Ut3Board::startpos(()).play_move(40);
+        let (alpha, beta) = (Ut3Handler::EVAL_MINIMUM, Ut3Handler::EVAL_MAXIMUM);
+        let mut searcher = Searcher::new();
+        let (expected, _) =
+            searcher.alpha_beta::<Ut3Handler, Ut3Board, 4>(&handler, root, 4, alpha, beta);
+        let mut leaf_counts = [0; 2];
+        for (iid, leaves) in leaf_counts.iter_mut().enumerate() {
+            let mut tt = TranspositionTable::new(1 << 16);
+            searcher.reset_leaf_count();
+            let (eval, line) = if iid == 1 {
+                searcher.alpha_beta_tt::<Ut3Handler, Ut3Board, 4, true>(
+                    &handler, root, 4, alpha, beta, &mut tt,
+                )
+            } else {
+                searcher.alpha_beta_tt::<Ut3Handler, Ut3Board, 4, false>(
+                    &handler, root, 4, alpha, beta, &mut tt,
+                )
+            };
+            assert_eq!(eval, expected);
+            assert_eq!(eval_from_line(&handler, root, line), eval);
+            *leaves = searcher.get_leaf_count();
+        }
+        // The shallower searches of internal iterative deepening evaluate leaves of their own.
+        assert_ne!(leaf_counts[0], leaf_counts[1]);
+    }
+
+    #[test]
+    fn sss_parallel_matches_serial() {
+        for seed in 0..6 {
+            let handler = hyp_tree(5, 4, seed);
+            let root = HypTreePos::startpos(4);
+            let mut searcher = Searcher::new();
+            let expected =
+                searcher.sss::<UnordIndHypTreeHandler, HypTreePos, 5>(&handler, root, 5);
+            for batch in [1, 3, 16] {
+                let result = searcher.sss_parallel::<UnordIndHypTreeHandler, HypTreePos, 5>(
+                    &handler, root, 5, batch,
+                );
+                assert_eq!(result, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn root_parallel_matches_serial() {
+        type H = UnordIndHypTreeHandler;
+        let (min, max) = (H::EVAL_MINIMUM, H::EVAL_MAXIMUM);
+        for seed in 0..6 {
+            let handler = hyp_tree(5, 4, seed);
+            let root = HypTreePos::startpos(4);
+            let mut searcher = Searcher::new();
+            let expected = searcher.alpha_beta::<H, HypTreePos, 5>(&handler, root, 5, min, max);
+            for killers in [
+                ParallelKillers::Off,
+                ParallelKillers::Shared,
+                ParallelKillers::PerRootMove,
+            ] {
+                let result = searcher.alpha_beta_root_parallel::<H, HypTreePos, 5>(
+                    &handler, root, 5, min, max, killers,
+                );
+                assert_eq!(result, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn parallel_matches_serial_on_ultimate_tic_tac_toe() {
+        type H = Ut3Handler;
+        let (min, max) = (H::EVAL_MINIMUM, H::EVAL_MAXIMUM);
+        let handler = Ut3Handler::new(Ut3Params::default());
+        let root = Ut3Board::startpos(());
+        let mut searcher = Searcher::new();
+        let (expected, _) = searcher.alpha_beta::<H, Ut3Board, 6>(&handler, root, 6, min, max);
+        let serial_leaves = searcher.get_leaf_count();
+        searcher.reset_leaf_count();
+        let (eval, line) =
+            searcher.alpha_beta_parallel::<H, Ut3Board, 6>(&handler, root, 6, min, max);
+        assert_eq!(eval, expected);
+        // The workers search against the bound of the first root move alone,
+        // so they can only cut off less than the serial search.
+        assert!(searcher.get_leaf_count() >= serial_leaves);
+        let child = root.play_move(line[0].unwrap());
+        let (t, _) = searcher.alpha_beta::<H, Ut3Board, 6>(&handler, child, 5, min, max);
+        assert_eq!(-t, expected);
+    }
+
+    #[test]
+    fn stockman_playouts_end_at_leaves() {
+        use rand_chacha::rand_core::SeedableRng;
+
+        let handler = StockmanHandler::new(());
+        let root = StockmanPos::startpos(());
+        // Every leaf has a distinct value, and all leaves are an even number of plies deep.
+        let leaf_values = (16..32)
+            .map(|node| handler.evaluate(StockmanPos { node }, 0, 4))
+            .collect::<Vec<_>>();
+        let mut rng = ChaChaRng::seed_from_u64(2089);
+        let mut searcher = Searcher::new();
+        let mut reached = [false; 16];
+        for _ in 0..200 {
+            let eval = searcher.random_playout(&handler, root, &mut rng);
+            let leaf = leaf_values.iter().position(|&value| value == eval).unwrap();
+            reached[leaf] = true;
+        }
+        assert_eq!(searcher.get_leaf_count(), 200);
+        assert!(reached.iter().all(|&reached| reached));
+    }
+
+    // A hypothetical tree whose moves are generated best first, found by searching each child
+    // exhaustively, so that every cutoff of a search that keeps that order is a first-move cutoff.
+    struct PerfectlyOrdered(UnordIndHypTreeHandler);
+
+    impl PerfectlyOrdered {
+        // The negamax value of `pos`, which is `ply` plies from the root.
+        fn exact(&self, pos: HypTreePos, ply: usize) -> i64 {
+            let children = self.0.get_legal_moves(pos);
+            let values = children.map(|mv| -self.exact(pos.play_move(mv), ply + 1));
+            values.max().unwrap_or_else(|| self.0.evaluate(pos, 0, ply))
+        }
+    }
+
+    impl GameHandler<HypTreePos> for PerfectlyOrdered {
+        type Eval = i64;
+        type Params = HypTreeParams;
+
+        const EVAL_MINIMUM: i64 = UnordIndHypTreeHandler::EVAL_MINIMUM;
+        const EVAL_MAXIMUM: i64 = UnordIndHypTreeHandler::EVAL_MAXIMUM;
+        const EVAL_EPSILON: i64 = UnordIndHypTreeHandler::EVAL_EPSILON;
+
+        fn new(params: HypTreeParams) -> Self {
+            Self(UnordIndHypTreeHandler::new(params))
+        }
+
+        fn get_legal_moves(&self, pos: HypTreePos) -> impl Iterator<Item = (usize, usize)> {
+            let mut moves = self.0.get_legal_moves(pos).collect::<Vec<_>>();
+            // Only the parity of the ply matters to the evaluation of the leaves.
+            let ply = self.0.current_player(pos) + 1;
+            moves.sort_by_cached_key(|&mv| self.exact(pos.play_move(mv), ply));
+            moves.into_iter()
+        }
+
+        fn evaluate(&self, pos: HypTreePos, depth: usize, max_depth: usize) -> i64 {
+            self.0.evaluate(pos, depth, max_depth)
+        }
+
+        fn current_player(&self, pos: HypTreePos) -> usize {
+            self.0.current_player(pos)
+        }
+    }
+
+    #[test]
+    fn perfect_ordering_cuts_off_on_first_move() {
+        let params = HypTreeParams {
+            depth: 5,
+            width: 4,
+            seed: 2131,
+            early_terminal: 0,
+        };
+        let root = HypTreePos::startpos(4);
+        let (min, max) = (i64::MIN + 1, i64::MAX);
+
+        let mut searcher = Searcher::new();
+        let handler = PerfectlyOrdered::new(params);
+        searcher.alpha_beta::<PerfectlyOrdered, HypTreePos, 5>(&handler, root, 5, min, max);
+        let stats = searcher.stats();
+        assert!(stats.cutoffs[0] > 0);
+        assert_eq!(stats.first_move_cutoff_rate(), 1.0);
+        // Searching exactly the minimal tree, of `4^3 + 4^2 - 1` leaves.
+        assert_eq!(stats.leaf_count, 79);
+
+        let mut searcher = Searcher::new();
+        let handler = hyp_tree(5, 4, 2131);
+        searcher.alpha_beta::<UnordIndHypTreeHandler, HypTreePos, 5>(&handler, root, 5, min, max);
+        assert!(searcher.stats().first_move_cutoff_rate() < 1.0);
+    }
+
+    #[test]
+    fn pvs_analysis_scores_every_root_move() {
+        for_each_game!(|handler, root| {
+            let (min, max) = (THandler::EVAL_MINIMUM, THandler::EVAL_MAXIMUM);
+            let mut searcher = Searcher::new();
+            let expected =
+                searcher.pvs::<THandler, TPosition, DEPTH>(handler, root, DEPTH, min, max);
+            let (result, scores) =
+                searcher.pvs_analysis::<THandler, TPosition, DEPTH>(handler, root, DEPTH);
+            assert_eq!(result, expected);
+            let moves = handler.get_legal_moves(root).collect::<Vec<_>>();
+            assert_eq!(scores.len(), moves.len());
+            assert!(scores.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+            assert_eq!(scores[0].1, expected.0);
+            for mv in moves {
+                let child = root.play_move(mv);
+                let (t, _) = searcher
+                    .alpha_beta::<THandler, TPosition, DEPTH>(handler, child, DEPTH - 1, min, max);
+                assert!(scores.contains(&(mv, -t)));
+            }
+        });
+    }
+
+    #[test]
+    fn self_play_ends_at_terminal_positions() {
+        type H = StockmanHandler;
+        let handler = StockmanHandler::new(());
+        let root = StockmanPos::startpos(());
+        let mut searcher = Searcher::new();
+        let record = searcher.self_play::<H, StockmanPos, 4>(&handler, root, 4, 10);
+        assert_eq!(record.len(), 4);
+        let end = handler.replay(root, &record).unwrap();
+        assert_eq!(handler.get_legal_moves(end).count(), 0);
+        // Both sides play perfectly, so the game follows the principal variation.
+        let (min, max) = (H::EVAL_MINIMUM, H::EVAL_MAXIMUM);
+        let (_, line) = searcher.alpha_beta::<H, StockmanPos, 4>(&handler, root, 4, min, max);
+        assert_eq!(record, line.map(Option::unwrap));
+        // The move cap stops the game early.
+        assert_eq!(searcher.self_play::<H, StockmanPos, 4>(&handler, root, 4, 2), record[..2]);
+
+        let handler = Ut3Handler::new(Ut3Params::default());
+        let root = Ut3Board::startpos(());
+        let record = searcher.self_play::<Ut3Handler, Ut3Board, 2>(&handler, root, 2, 81);
+        assert!(record.len() < 81);
+        let end = handler.replay(root, &record).unwrap();
+        assert_eq!(handler.get_legal_moves(end).count(), 0);
+        let again = searcher.self_play::<Ut3Handler, Ut3Board, 2>(&handler, root, 2, 81);
+        assert_eq!(again, record);
+    }
+
+    #[test]
+    fn bounded_tags_failed_searches_as_bounds() {
+        type H = UnordIndHypTreeHandler;
+        let root = HypTreePos::startpos(3);
+        for seed in 0..4 {
+            let handler = hyp_tree(4, 3, seed);
+            let mut searcher = Searcher::new();
+            let (min, max) = (H::EVAL_MINIMUM, H::EVAL_MAXIMUM);
+            let exact = searcher.alpha_beta::<H, HypTreePos, 4>(&handler, root, 4, min, max);
+            let value = exact.0;
+            let result =
+                searcher.alpha_beta_bounded::<H, HypTreePos, 4>(&handler, root, 4, min, max);
+            assert_eq!(result, (exact, Bound::Exact));
+            // A window wholly below the value cuts off at the root, giving a lower bound.
+            let ((eval, _), bound) = searcher.alpha_beta_bounded::<H, HypTreePos, 4>(
+                &handler,
+                root,
+                4,
+                value - 10,
+                value - 5,
+            );
+            assert_eq!(bound, Bound::Lower);
+            assert!(value - 5 <= eval && eval <= value);
+            // A window wholly above the value fails low, giving an upper bound.
+            let ((eval, _), bound) = searcher.alpha_beta_bounded::<H, HypTreePos, 4>(
+                &handler,
+                root,
+                4,
+                value + 5,
+                value + 10,
+            );
+            assert_eq!(bound, Bound::Upper);
+            assert!(value <= eval && eval <= value + 5);
+        }
+    }
+
+    #[test]
+    fn cached_search_over_several_trees() {
+        type H = Uniform2bWideHandler;
+        type P = Uniform2bWidePos;
+        let (min, max) = (H::EVAL_MINIMUM, H::EVAL_MAXIMUM);
+        let root = P::startpos(());
+        let mut searcher = Searcher::new();
+        for seed in 0..6 {
+            let handler = H::new(Uniform2bWideParams {
+                depth: 8,
+                seed,
+                distribution: LeafDistribution::Uniform,
+            });
+            let expected = searcher.alpha_beta::<H, P, 8>(&handler, root, 8, min, max);
+            // Without a table, this is `alpha_beta`.
+            let uncached = searcher.alpha_beta_cached::<H, P, 8>(&handler, root, 8, min, max, None);
+            assert_eq!(uncached, expected);
+            // Each tree has a table of its own.
+            let mut tt = TranspositionTable::new(1 << 12);
+            searcher.reset_leaf_count();
+            let cached =
+                searcher.alpha_beta_cached::<H, P, 8>(&handler, root, 8, min, max, Some(&mut tt));
+            assert_eq!(cached, expected);
+            // Searching the same tree again reuses the entries of the first search.
+            let leaves = searcher.get_leaf_count();
+            let again =
+                searcher.alpha_beta_cached::<H, P, 8>(&handler, root, 8, min, max, Some(&mut tt));
+            assert_eq!(again, expected);
+            assert!(searcher.get_leaf_count() - leaves < leaves / 4);
+        }
+    }
+
+    #[test]
+    fn iterative_deepening_matches_alpha_beta_on_stockman() {
+        type H = StockmanHandler;
+        let (min, max) = (H::EVAL_MINIMUM, H::EVAL_MAXIMUM);
+        let handler = StockmanHandler::new(());
+        let root = StockmanPos::startpos(());
+        let mut searcher = Searcher::new();
+        let expected = searcher.alpha_beta::<H, StockmanPos, 4>(&handler, root, 4, min, max);
+        let result = searcher.iterative_deepening::<H, StockmanPos, 4>(&handler, root, || false);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn iterative_deepening_stops_inside_the_search() {
+        use std::sync::Arc;
+
+        let handler = Ut3Handler::new(Ut3Params::default());
+        let (min, max) = (Ut3Handler::EVAL_MINIMUM, Ut3Handler::EVAL_MAXIMUM);
+        let root = Ut3Board::startpos(());
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let mut searcher = Searcher::new();
+        let sink = Arc::clone(&reports);
+        searcher.set_info_callback::<i32, u64>(Box::new(move |info| {
+            sink.lock().unwrap().push((info.depth, info.nodes));
+        }));
+        // The first poll lets the search run on, and the second stops it.
+        let mut polls = 0;
+        let (eval, line) =
+            searcher.iterative_deepening::<Ut3Handler, Ut3Board, 8>(&handler, root, || {
+                polls += 1;
+                polls > 1
+            });
+        assert_eq!(polls, 2);
+
+        let (depth, nodes) = *reports.lock().unwrap().last().unwrap();
+        assert!(depth < 8);
+        // Every interior node polls, so little of the abandoned depth is searched.
+        let abandoned = searcher.get_leaf_count() - nodes;
+        assert!(abandoned <= 4 * DEADLINE_POLL_INTERVAL as u128);
+        // Root moves tie in Ultimate Tic-Tac-Toe, so only the evaluation need match.
+        let (expected, _) = searcher.alpha_beta_dyn(&handler, root, depth, min, max);
+        assert_eq!(eval, expected);
+        assert!(handler.get_legal_moves(root).any(|mv| Some(mv) == line[0]));
+    }
+
+    #[test]
+    fn mtdf_converges_from_any_first_guess() {
+        for_each_game!(|handler, root| {
+            let (min, max) = (THandler::EVAL_MINIMUM, THandler::EVAL_MAXIMUM);
+            let mut searcher = Searcher::new();
+            let (expected, _) =
+                searcher.alpha_beta::<THandler, TPosition, DEPTH>(handler, root, DEPTH, min, max);
+            for guess in [expected, min, max] {
+                let (eval, line) =
+                    searcher.mtdf::<THandler, TPosition, DEPTH>(handler, root, DEPTH, guess);
+                assert!(eval == expected);
+                // The first move of the line must achieve the value.
+                let child = root.play_move(line[0].unwrap());
+                let (t, _) = searcher
+                    .alpha_beta::<THandler, TPosition, DEPTH>(handler, child, DEPTH - 1, min, max);
+                assert!(-t == expected);
+            }
+        });
+    }
+
+    #[test]
+    fn aspiration_search_matches_full_window() {
+        type H = Uniform2bWideHandler;
+        let (min, max) = (H::EVAL_MINIMUM, H::EVAL_MAXIMUM);
+        for seed in 0..6 {
+            let handler = Uniform2bWideHandler::new(Uniform2bWideParams {
+                depth: 8,
+                seed,
+                distribution: LeafDistribution::Uniform,
+            });
+            let root = Uniform2bWidePos::startpos(());
+            let mut searcher = Searcher::new();
+            let expected =
+                searcher.alpha_beta::<H, Uniform2bWidePos, 8>(&handler, root, 8, min, max);
+            // Guesses inside the window, just outside it on either side (one widening),
+            // and far outside it (opened to the extreme evaluation).
+            for guess in [expected.0, expected.0 - 15, expected.0 + 15, min, max] {
+                for window in [1, 10, 1000] {
+                    let result = searcher.aspiration_search::<H, Uniform2bWidePos, 8>(
+                        &handler, root, 8, guess, window,
+                    );
+                    assert_eq!(result, expected);
                 }
             }
-            i += 1;
         }
-        panic!("State space operator is faulty");
+    }
+
+    #[test]
+    fn killer_moves_prune_ultimate_tic_tac_toe() {
+        type H = Ut3Handler;
+        let (min, max) = (H::EVAL_MINIMUM, H::EVAL_MAXIMUM);
+        let handler = Ut3Handler::new(Ut3Params::default());
+        let mut pos = Ut3Board::startpos(());
+        for ply in 0..6 {
+            let mut searcher = Searcher::new();
+            let (expected, _) = searcher.alpha_beta::<H, Ut3Board, 5>(&handler, pos, 5, min, max);
+            let unordered = searcher.get_leaf_count();
+            searcher.reset_leaf_count();
+            let (eval, _) =
+                searcher.alpha_beta_ordered::<H, Ut3Board, 5>(&handler, pos, 5, min, max);
+            assert_eq!(eval, expected);
+            assert!(searcher.get_leaf_count() <= unordered);
+            let moves = handler.get_legal_moves(pos).collect::<Vec<_>>();
+            pos = pos.play_move(moves[ply * 7 % moves.len()]);
+        }
+    }
+
+    #[test]
+    fn timed_search_abandons_large_trees_with_a_legal_move() {
+        type H = UnordIndHypTreeHandler;
+        let (min, max) = (H::EVAL_MINIMUM, H::EVAL_MAXIMUM);
+        for seed in 0..4 {
+            // Far too large to search, so the deadline that has already passed stops it.
+            let handler = hyp_tree(9, 4, seed);
+            let root = HypTreePos::startpos(4);
+            let mut searcher = Searcher::new();
+            let deadline = Instant::now();
+            let ((_, line), completed) = searcher
+                .alpha_beta_timed::<H, HypTreePos, 9>(&handler, root, 9, min, max, deadline);
+            assert!(!completed);
+            assert!(handler.get_legal_moves(root).any(|mv| Some(mv) == line[0]));
+            assert!(searcher.get_leaf_count() <= 4 * DEADLINE_POLL_INTERVAL as u128);
+
+            // A generous deadline lets the search complete with the result of `alpha_beta`.
+            let handler = hyp_tree(5, 3, seed);
+            let root = HypTreePos::startpos(3);
+            let deadline = Instant::now() + Duration::from_secs(3600);
+            let expected = searcher.alpha_beta::<H, HypTreePos, 5>(&handler, root, 5, min, max);
+            let result = searcher
+                .alpha_beta_timed::<H, HypTreePos, 5>(&handler, root, 5, min, max, deadline);
+            assert_eq!(result, (expected, true));
+        }
+    }
+
+    #[test]
+    fn node_limit_stops_limited_searches() {
+        type H = UnordIndHypTreeHandler;
+        let (min, max) = (H::EVAL_MINIMUM, H::EVAL_MAXIMUM);
+        let handler = hyp_tree(6, 8, 314159);
+        let root = HypTreePos::startpos(8);
+        let mut searcher = Searcher::new();
+        searcher.set_node_limit(Some(100));
+        // Each search stops at the leaf that reaches the limit, with a root move to play.
+        let (_, line) =
+            searcher.alpha_beta_limited::<H, HypTreePos, 6>(&handler, root, 6, min, max);
+        assert!(searcher.node_limit_reached());
+        assert_eq!(searcher.get_leaf_count(), 100);
+        assert!(handler.get_legal_moves(root).any(|mv| Some(mv) == line[0]));
+        searcher.reset_leaf_count();
+        let (_, line) = searcher.pvs_limited::<H, HypTreePos, 6>(&handler, root, 6, min, max);
+        assert!(searcher.node_limit_reached());
+        assert_eq!(searcher.get_leaf_count(), 100);
+        assert!(handler.get_legal_moves(root).any(|mv| Some(mv) == line[0]));
+
+        // A limit that is never reached leaves the results of the unlimited searches.
+        for seed in 0..4 {
+            let handler = hyp_tree(5, 3, seed);
+            let root = HypTreePos::startpos(3);
+            searcher.set_node_limit(Some(u128::MAX));
+            let expected = searcher.alpha_beta::<H, HypTreePos, 5>(&handler, root, 5, min, max);
+            let result =
+                searcher.alpha_beta_limited::<H, HypTreePos, 5>(&handler, root, 5, min, max);
+            assert_eq!(result, expected);
+            let expected = searcher.pvs::<H, HypTreePos, 5>(&handler, root, 5, min, max);
+            let result = searcher.pvs_limited::<H, HypTreePos, 5>(&handler, root, 5, min, max);
+            assert_eq!(result, expected);
+            assert!(!searcher.node_limit_reached());
+        }
+    }
+
+    #[test]
+    fn leaf_count_is_shared_between_threads() {
+        let searcher = Searcher::new();
+        let workers = (0..4).map(|_| searcher.worker()).collect::<Vec<_>>();
+        std::thread::scope(|scope| {
+            // Threads count leaves both through the shared searcher and through workers.
+            for _ in 0..4 {
+                scope.spawn(|| (0..10000).for_each(|_| searcher.increment_leaf_count()));
+            }
+            for worker in &workers {
+                scope.spawn(move || (0..10000).for_each(|_| worker.increment_leaf_count()));
+            }
+        });
+        assert_eq!(searcher.get_leaf_count(), 80000);
+        assert!(workers.iter().all(|worker| worker.get_leaf_count() == 80000));
+
+        searcher.reset_leaf_count();
+        assert_eq!(workers[0].get_leaf_count(), 0);
+        searcher.add_leaf_count(u128::MAX);
+        assert_eq!(searcher.get_leaf_count(), u64::MAX as u128);
+    }
+
+    #[test]
+    fn dual_star_matches_alpha_beta() {
+        type H = UnordIndHypTreeHandler;
+        let (min, max) = (H::EVAL_MINIMUM, H::EVAL_MAXIMUM);
+        for seed in 0..8 {
+            let handler = hyp_tree(4, 4, seed);
+            let root = HypTreePos::startpos(4);
+            let mut searcher = Searcher::new();
+            let (expected, _) =
+                searcher.alpha_beta::<H, HypTreePos, 4>(&handler, root, 4, min, max);
+            let (eval, line) = searcher.dual_star::<H, HypTreePos, 4>(&handler, root, 4);
+            assert_eq!(eval, expected);
+            let child = root.play_move(line[0].unwrap());
+            let (t, _) = searcher.alpha_beta::<H, HypTreePos, 4>(&handler, child, 3, min, max);
+            assert_eq!(-t, expected);
+        }
+        let handler = StockmanHandler::new(());
+        let root = StockmanPos::startpos(());
+        let mut searcher = Searcher::new();
+        let expected = searcher.sss::<StockmanHandler, StockmanPos, 4>(&handler, root, 4);
+        let result = searcher.dual_star::<StockmanHandler, StockmanPos, 4>(&handler, root, 4);
+        assert_eq!(result.0, expected.0);
+    }
+
+    #[test]
+    fn multipv_lines_are_sorted_best_first() {
+        for_each_game!(|handler, root| {
+            let (min, max) = (THandler::EVAL_MINIMUM, THandler::EVAL_MAXIMUM);
+            let mut searcher = Searcher::new();
+            let expected =
+                searcher.alpha_beta::<THandler, TPosition, DEPTH>(handler, root, DEPTH, min, max);
+            let lines = searcher.alpha_beta_multipv::<THandler, TPosition, DEPTH>(
+                handler,
+                root,
+                DEPTH,
+                usize::MAX,
+            );
+            assert!(lines[0].0 == expected.0);
+            assert!(lines.windows(2).all(|pair| pair[0].0 >= pair[1].0));
+            // Every root move has a line, scored as `root_scores` scores it.
+            let scores = searcher.root_scores::<THandler, TPosition, DEPTH>(handler, root, DEPTH);
+            assert_eq!(lines.len(), handler.get_legal_moves(root).count());
+            for ((eval, line), &(mv, score)) in lines.iter().zip(&scores) {
+                assert!(line[0] == Some(mv) && *eval == score);
+            }
+            let best =
+                searcher.alpha_beta_multipv::<THandler, TPosition, DEPTH>(handler, root, DEPTH, 2);
+            assert!(best[..] == lines[..2]);
+        });
+    }
+
+    #[test]
+    fn fail_soft_bounds_the_value_outside_the_window() {
+        type H = Uniform2bWideHandler;
+        let (min, max) = (H::EVAL_MINIMUM, H::EVAL_MAXIMUM);
+        let mut differs = false;
+        for seed in 0..6 {
+            let handler = Uniform2bWideHandler::new(Uniform2bWideParams {
+                depth: 8,
+                seed,
+                distribution: LeafDistribution::Uniform,
+            });
+            let root = Uniform2bWidePos::startpos(());
+            let mut searcher = Searcher::new();
+            let (value, _) =
+                searcher.alpha_beta::<H, Uniform2bWidePos, 8>(&handler, root, 8, min, max);
+            // The full window, a window around the value, and windows above and below it.
+            let windows = [
+                (min, max),
+                (value - 5, value + 5),
+                (value + 1, value + 20),
+                (value - 20, value - 1),
+            ];
+            for (alpha, beta) in windows {
+                let (hard, hard_line) =
+                    searcher.alpha_beta::<H, Uniform2bWidePos, 8>(&handler, root, 8, alpha, beta);
+                let (soft, soft_line) = searcher
+                    .alpha_beta_fail_soft::<H, Uniform2bWidePos, 8>(&handler, root, 8, alpha, beta);
+                if soft <= alpha {
+                    // Failing low, the evaluation is an upper bound on the value.
+                    assert!(value <= soft && hard == alpha);
+                } else if soft >= beta {
+                    // Failing high, the evaluation is a lower bound reached by the same move.
+                    assert!(value >= soft && hard >= beta);
+                    assert_eq!(soft_line[0], hard_line[0]);
+                } else {
+                    assert_eq!((soft, soft_line), (value, hard_line));
+                }
+                differs |= soft != hard;
+            }
+        }
+        assert!(differs);
     }
 }
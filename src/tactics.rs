@@ -0,0 +1,249 @@
+use crate::games::chess::*;
+use crate::prelude::*;
+use crate::search::*;
+
+impl Searcher {
+    // Searches for a forced mate within `depth` plies, where the side to move at the root
+    // only considers the moves of `ChessHandler::get_forcing_moves`
+    // and the defending side considers every legal move.
+    // Lines in which the attacker runs out of forcing moves or depth are scored as `0`,
+    // so the evaluation is a mate score exactly when a forced mate was found through checks,
+    // captures and promotions alone, in which case the principal variation is the mating line.
+    // Mates that need a quiet move from the attacker are not found.
+    pub fn solve_tactic<const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &ChessHandler,
+        pos: ChessPos,
+        depth: usize,
+    ) -> EvalAndPV<ChessHandler, ChessPos, MAX_DEPTH> {
+        self.solve_tactic_node::<MAX_DEPTH>(
+            handler,
+            pos,
+            depth,
+            ChessHandler::EVAL_MINIMUM,
+            ChessHandler::EVAL_MAXIMUM,
+            true,
+        )
+    }
+
+    fn solve_tactic_node<const MAX_DEPTH: usize>(
+        &mut self,
+        handler: &ChessHandler,
+        pos: ChessPos,
+        depth: usize,
+        alpha: Centipawn,
+        beta: Centipawn,
+        attacking: bool,
+    ) -> EvalAndPV<ChessHandler, ChessPos, MAX_DEPTH> {
+        if handler.get_legal_moves(pos).next().is_none() {
+            self.increment_leaf_count();
+            return (
+                handler.terminal_eval(pos, depth, MAX_DEPTH),
+                [None; MAX_DEPTH],
+            );
+        }
+
+        let moves = if depth == 0 {
+            Vec::new()
+        } else if attacking {
+            handler.get_forcing_moves(pos)
+        } else {
+            handler.get_legal_moves(pos).collect()
+        };
+        if moves.is_empty() {
+            self.increment_leaf_count();
+            return (Centipawn(0), [None; MAX_DEPTH]);
+        }
+
+        let mut m = alpha;
+        let mut pv = [None; MAX_DEPTH];
+
+        for mv in moves {
+            let (t, mut line) = self.solve_tactic_node::<MAX_DEPTH>(
+                handler,
+                pos.play_move(mv),
+                depth - 1,
+                -beta,
+                -m,
+                !attacking,
+            );
+            let t = -t;
+            line[MAX_DEPTH - depth] = Some(mv);
+
+            if t > m {
+                m = t;
+                pv = line;
+            }
+
+            if m >= beta {
+                return (m, line);
+            }
+        }
+
+        (m, pv)
+    }
+}
+
+impl Searcher {
+    // Finds the shortest forced mate for the side to move in `pos` of at most `max_n` moves,
+    // returning the mating line, or `None` if there is no forced mate within `max_n` moves.
+    // Mates of `1` to `max_n` moves are searched in turn, each with a window that only
+    // admits mate scores, so lines which do not end in mate are refuted as soon as possible.
+    // The attacking side tries its forcing moves first and, on its final move, only tries checks,
+    // since no other move can deliver mate. The defending side tries every legal move,
+    // and the returned line follows the defence which delays mate for longest.
+    pub fn find_mate(
+        &mut self,
+        handler: &ChessHandler,
+        pos: ChessPos,
+        max_n: usize,
+    ) -> Option<Vec<u64>> {
+        for n in 1..=max_n {
+            let max_depth = 2 * n - 1;
+            let threshold = Centipawn::mate(max_depth);
+            let (eval, mut line) = self.find_mate_node(
+                handler,
+                pos,
+                max_depth,
+                max_depth,
+                threshold - ChessHandler::EVAL_EPSILON,
+                ChessHandler::EVAL_MAXIMUM,
+            );
+            if eval >= threshold {
+                line.reverse();
+                return Some(line);
+            }
+        }
+        None
+    }
+
+    // Lines are built leaf first, as in `alpha_beta_dyn`.
+    fn find_mate_node(
+        &mut self,
+        handler: &ChessHandler,
+        pos: ChessPos,
+        depth: usize,
+        max_depth: usize,
+        alpha: Centipawn,
+        beta: Centipawn,
+    ) -> (Centipawn, Vec<u64>) {
+        let mut moves: Vec<u64> = handler.get_legal_moves(pos).collect();
+        if moves.is_empty() {
+            self.increment_leaf_count();
+            return (handler.terminal_eval(pos, depth, max_depth), Vec::new());
+        }
+        if depth == 0 {
+            self.increment_leaf_count();
+            return (Centipawn(0), Vec::new());
+        }
+
+        if (max_depth - depth) & 1 == 0 {
+            if depth == 1 {
+                moves.retain(|&mv| handler.in_check(pos.play_move(mv)));
+            } else {
+                let forcing = handler.get_forcing_moves(pos);
+                moves.sort_by_key(|mv| !forcing.contains(mv));
+            }
+            if moves.is_empty() {
+                self.increment_leaf_count();
+                return (Centipawn(0), Vec::new());
+            }
+        }
+
+        let mut m = alpha;
+        let mut pv = Vec::new();
+
+        for mv in moves {
+            let (t, mut line) =
+                self.find_mate_node(handler, pos.play_move(mv), depth - 1, max_depth, -beta, -m);
+            let t = -t;
+            line.push(mv);
+
+            if t > m {
+                m = t;
+                pv = line;
+            }
+
+            if m >= beta {
+                return (m, pv);
+            }
+        }
+
+        (m, pv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handler() -> ChessHandler {
+        ChessHandler::new(ChessParams::default())
+    }
+
+    fn move_strings(handler: &ChessHandler, pos: ChessPos, line: &[Option<u64>]) -> Vec<String> {
+        let mut pos = pos;
+        line.iter()
+            .map_while(|&mv| mv)
+            .map(|mv| {
+                let side = (pos.squares >> 19) & 1;
+                pos = pos.play_move(mv);
+                handler.move_string(mv, side)
+            })
+            .collect()
+    }
+
+    // The doubled rooks give a back-rank mate in two, through a check and a capture.
+    #[test]
+    fn forcing_search_finds_mate_in_two() {
+        let handler = handler();
+        let pos = ChessPos::from_fen("r6k/6pp/8/8/8/8/4R3/4R1K1 w - - 0 1").unwrap();
+        // The only check, since the rook on e1 is blocked.
+        let forcing = handler.get_forcing_moves(pos);
+        assert_eq!(forcing.len(), 1);
+        assert_eq!(move_strings(&handler, pos, &[Some(forcing[0])]), ["e2e8"]);
+
+        let (eval, line) = Searcher::new().solve_tactic::<3>(&handler, pos, 3);
+        assert_eq!(eval.mate_in(), Some(3));
+        assert_eq!(move_strings(&handler, pos, &line), ["e2e8", "a8e8", "e1e8"]);
+    }
+
+    #[test]
+    fn forcing_search_scores_quiet_position_as_zero() {
+        let handler = handler();
+        let pos = ChessPos::startpos(());
+        assert!(handler.get_forcing_moves(pos).is_empty());
+        let (eval, _) = Searcher::new().solve_tactic::<3>(&handler, pos, 3);
+        assert_eq!(eval, Centipawn(0));
+    }
+
+    #[test]
+    fn find_mate_returns_shortest_mating_line() {
+        let handler = handler();
+        let mut searcher = Searcher::new();
+        let pos = ChessPos::from_fen("r6k/6pp/8/8/8/8/4R3/4R1K1 w - - 0 1").unwrap();
+        let line = searcher.find_mate(&handler, pos, 3).unwrap();
+        let line = line.into_iter().map(Some).collect::<Vec<_>>();
+        assert_eq!(move_strings(&handler, pos, &line), ["e2e8", "a8e8", "e1e8"]);
+
+        // With a third rook against a second defending rook, mate takes a move longer.
+        let pos = ChessPos::from_fen("rr5k/6pp/8/8/8/4R3/4R3/4R1K1 w - - 0 1").unwrap();
+        assert_eq!(searcher.find_mate(&handler, pos, 2), None);
+        let line = searcher.find_mate(&handler, pos, 3).unwrap();
+        let line = line.into_iter().map(Some).collect::<Vec<_>>();
+        assert_eq!(line.len(), 5);
+        assert_eq!(handler.classify_line(pos, &line), LineOutcome::Checkmate);
+    }
+
+    #[test]
+    fn find_mate_rejects_positions_without_forced_mate() {
+        let handler = handler();
+        let mut searcher = Searcher::new();
+        // A rook mate exists, but takes far more than three moves.
+        let pos = ChessPos::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        assert_eq!(searcher.find_mate(&handler, pos, 3), None);
+        // The second rook covers the back rank.
+        let pos = ChessPos::from_fen("rr5k/6pp/8/8/8/8/4R3/4R1K1 w - - 0 1").unwrap();
+        assert_eq!(searcher.find_mate(&handler, pos, 3), None);
+    }
+}
@@ -0,0 +1,248 @@
+use crate::prelude::*;
+
+// How the evaluation stored in a table entry relates to the true value of the position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Bound {
+    // The search completed within its window, so the evaluation is exact.
+    Exact,
+    // The search failed high, so the true value is at least the evaluation.
+    Lower,
+    // The search failed low, so the true value is at most the evaluation.
+    Upper,
+}
+
+// The result of searching a position, as recorded in a `TranspositionTable`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "<THandler as GameHandler<TPosition>>::Eval: serde::Serialize, \
+            <TPosition as GamePosition>::Move: serde::Serialize",
+        deserialize = "<THandler as GameHandler<TPosition>>::Eval: serde::Deserialize<'de>, \
+            <TPosition as GamePosition>::Move: serde::Deserialize<'de>"
+    ))
+)]
+pub struct TTEntry<THandler, TPosition>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    pub key: u64,
+    pub depth: usize,
+    pub eval: <THandler as GameHandler<TPosition>>::Eval,
+    pub bound: Bound,
+    pub best_move: Option<<TPosition as GamePosition>::Move>,
+}
+
+// Deriving these would place the bounds on `THandler` and `TPosition` themselves.
+impl<THandler, TPosition> Clone for TTEntry<THandler, TPosition>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<THandler, TPosition> Copy for TTEntry<THandler, TPosition>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+}
+
+// A fixed-capacity table of search results indexed by `ZobristKey::key`.
+// Each key maps to a single slot, and a new entry always replaces the one in its slot.
+// The full key is stored alongside each entry so that slot collisions are detected on lookup.
+pub struct TranspositionTable<THandler, TPosition>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition,
+{
+    entries: Vec<Option<TTEntry<THandler, TPosition>>>,
+}
+
+impl<THandler, TPosition> TranspositionTable<THandler, TPosition>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition + ZobristKey,
+{
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: vec![None; capacity.max(1)],
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+
+    pub fn probe(&self, pos: TPosition) -> Option<TTEntry<THandler, TPosition>> {
+        self.probe_key(pos.key())
+    }
+
+    // Looks up the game state whose key is `key`, for searches that carry the key
+    // down the game tree through `ZobristKey::play_move_with_key`.
+    pub fn probe_key(&self, key: u64) -> Option<TTEntry<THandler, TPosition>> {
+        self.entries[self.index(key)].filter(|entry| entry.key == key)
+    }
+
+    pub fn store(
+        &mut self,
+        pos: TPosition,
+        depth: usize,
+        eval: <THandler as GameHandler<TPosition>>::Eval,
+        bound: Bound,
+        best_move: Option<<TPosition as GamePosition>::Move>,
+    ) {
+        self.store_key(pos.key(), depth, eval, bound, best_move);
+    }
+
+    // Stores the result for the game state whose key is `key`, as `probe_key` looks it up.
+    pub fn store_key(
+        &mut self,
+        key: u64,
+        depth: usize,
+        eval: <THandler as GameHandler<TPosition>>::Eval,
+        bound: Bound,
+        best_move: Option<<TPosition as GamePosition>::Move>,
+    ) {
+        let index = self.index(key);
+        self.entries[index] = Some(TTEntry {
+            key,
+            depth,
+            eval,
+            bound,
+            best_move,
+        });
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+}
+
+// The layout of a table saved by `TranspositionTable::save`.
+// `TT_FILE_VERSION` must be incremented whenever this layout or that of `TTEntry` changes,
+// so that files written in an older layout are rejected rather than misread.
+#[cfg(feature = "serde")]
+pub const TT_FILE_VERSION: u32 = 1;
+
+// The occupied slots of a saved table, each with its index.
+#[cfg(feature = "serde")]
+type SavedEntries<THandler, TPosition> = Vec<(usize, TTEntry<THandler, TPosition>)>;
+
+#[cfg(feature = "serde")]
+impl<THandler, TPosition> TranspositionTable<THandler, TPosition>
+where
+    THandler: GameHandler<TPosition>,
+    TPosition: GamePosition + ZobristKey,
+    <THandler as GameHandler<TPosition>>::Eval: serde::Serialize + serde::de::DeserializeOwned,
+    <TPosition as GamePosition>::Move: serde::Serialize + serde::de::DeserializeOwned,
+{
+    // Writes the table to the file at `path`, replacing its contents,
+    // so that the results of an expensive search can be reused in a later run through `load`.
+    // The file holds `TT_FILE_VERSION`, the capacity, and the occupied slots with their entries.
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> std::io::Result<()> {
+        let occupied: SavedEntries<THandler, TPosition> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| entry.map(|entry| (index, entry)))
+            .collect();
+        let writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        bincode::serialize_into(writer, &(TT_FILE_VERSION, self.capacity(), occupied))
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    // Reads a table written by `save`, with the same capacity and entries.
+    // Files written with a different `TT_FILE_VERSION` are rejected with `InvalidData`,
+    // as are files whose entries do not fit the stored capacity.
+    // The evaluation and move types must match those of the table that was saved,
+    // since the file does not record which game it belongs to.
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Self> {
+        let invalid = |msg: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+        let mut reader = std::io::BufReader::new(std::fs::File::open(path)?);
+        let version: u32 = bincode::deserialize_from(&mut reader)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        if version != TT_FILE_VERSION {
+            return Err(invalid("unsupported transposition table file version"));
+        }
+        let (capacity, occupied): (usize, SavedEntries<THandler, TPosition>) =
+            bincode::deserialize_from(&mut reader)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let mut table = Self::new(capacity);
+        for (index, entry) in occupied {
+            if index >= table.capacity() {
+                return Err(invalid("transposition table entry out of range"));
+            }
+            table.entries[index] = Some(entry);
+        }
+        Ok(table)
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::games::ut3::*;
+    use crate::search::*;
+
+    type Ut3Table = TranspositionTable<Ut3Handler, Ut3Board>;
+    type EntryFields = (u64, usize, i32, Bound, Option<u64>);
+
+    fn contents(table: &Ut3Table) -> Vec<Option<EntryFields>> {
+        table
+            .entries
+            .iter()
+            .map(|entry| entry.map(|e| (e.key, e.depth, e.eval, e.bound, e.best_move)))
+            .collect()
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("{}-{}.tt", name, std::process::id()))
+    }
+
+    #[test]
+    fn saved_table_loads_unchanged() {
+        let handler = Ut3Handler::new(Ut3Params::default());
+        let mut table = Ut3Table::new(1 << 10);
+        Searcher::new().alpha_beta_tt::<Ut3Handler, Ut3Board, 3, false>(
+            &handler,
+            Ut3Board::startpos(()),
+            3,
+            Ut3Handler::EVAL_MINIMUM,
+            Ut3Handler::EVAL_MAXIMUM,
+            &mut table,
+        );
+        assert!(table.entries.iter().any(Option::is_some));
+
+        let path = temp_path("saved_table_loads_unchanged");
+        table.save(&path).unwrap();
+        let loaded = Ut3Table::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.capacity(), table.capacity());
+        assert_eq!(contents(&loaded), contents(&table));
+    }
+
+    #[test]
+    fn other_file_versions_are_rejected() {
+        let path = temp_path("other_file_versions_are_rejected");
+        let saved: SavedEntries<Ut3Handler, Ut3Board> = Vec::new();
+        let file = std::fs::File::create(&path).unwrap();
+        bincode::serialize_into(file, &(TT_FILE_VERSION + 1, 16usize, saved)).unwrap();
+        let loaded = Ut3Table::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            loaded.err().map(|e| e.kind()),
+            Some(std::io::ErrorKind::InvalidData)
+        );
+    }
+}